@@ -0,0 +1,16 @@
+load ../utils/_
+
+setup() {
+    standard_nns_setup
+}
+
+teardown() {
+    standard_nns_teardown
+}
+
+@test "json output" {
+    # 64-bit integers are written as strings, as JSON numbers lose precision past 2^53
+    assert_command quill account-balance 345f723e9e619934daac6ae0f4be13a7b0ba57d6a608e511a00fd0ded5866752 --output json --yes --insecure-local-dev-mode
+    assert_string_match '"e8s": "100000000000000000"' "$stdout"
+    assert_not_match 'Sending message' "$stdout"
+}
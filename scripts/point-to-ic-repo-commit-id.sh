@@ -72,10 +72,13 @@ function download_from_ic_repo {
 
 echo "Updating candid files..." >&2
 download_from_ic_repo rs/bitcoin/ckbtc/minter/ckbtc_minter.did   candid/ckbtc_minter.did
+download_from_ic_repo rs/ethereum/cketh/minter/cketh_minter.did   candid/cketh_minter.did
 download_from_ic_repo rs/nns/governance/canister/governance.did  candid/governance.did
 download_from_ic_repo rs/nns/gtc/canister/gtc.did                candid/gtc.did
 download_from_ic_repo rs/ledger_suite/icrc1/ledger/ledger.did    candid/icrc1.did
+download_from_ic_repo rs/ledger_suite/icrc1/index-ng/index-ng.did candid/icrc1-index.did
 download_from_ic_repo rs/ledger_suite/icp/ledger.did             candid/ledger.did
+download_from_ic_repo rs/ledger_suite/icp/index/index.did        candid/icp-index.did
 download_from_ic_repo rs/registry/canister/canister/registry.did candid/registry.did
 download_from_ic_repo rs/sns/governance/canister/governance.did  candid/sns-governance.did
 download_from_ic_repo rs/sns/root/canister/root.did              candid/sns-root.did
@@ -1,7 +1,7 @@
 use crate::{
     commands::{send::submit_unsigned_ingress, SendingOpts},
     lib::{
-        ledger_canister_id, AnyhowResult, AuthInfo, ParsedNnsAccount, ROLE_ICRC1_LEDGER,
+        network::Network, AnyhowResult, AuthInfo, ParsedNnsAccount, ROLE_ICRC1_LEDGER,
         ROLE_NNS_LEDGER,
     },
     AUTH_FLAGS,
@@ -26,7 +26,7 @@ pub struct AccountBalanceOpts {
 
 // We currently only support a subset of the functionality.
 #[tokio::main]
-pub async fn exec(auth: &AuthInfo, opts: AccountBalanceOpts, fetch_root_key: bool) -> AnyhowResult {
+pub async fn exec(auth: &AuthInfo, opts: AccountBalanceOpts, network: &Network) -> AnyhowResult {
     let account_id = if let Some(id) = opts.account_id {
         id
     } else {
@@ -42,24 +42,24 @@ pub async fn exec(auth: &AuthInfo, opts: AccountBalanceOpts, fetch_root_key: boo
                 account: id.to_address()
             })?;
             submit_unsigned_ingress(
-                ledger_canister_id(),
+                network.ledger_canister_id(),
                 ROLE_NNS_LEDGER,
                 "account_balance",
                 args,
                 opts.sending_opts,
-                fetch_root_key,
+                network,
             )
             .await
         }
         ParsedNnsAccount::Icrc1(id) => {
             let args = Encode!(&id)?;
             submit_unsigned_ingress(
-                ledger_canister_id(),
+                network.ledger_canister_id(),
                 ROLE_ICRC1_LEDGER,
                 "icrc1_balance_of",
                 args,
                 opts.sending_opts,
-                fetch_root_key,
+                network,
             )
             .await
         }
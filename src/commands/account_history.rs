@@ -0,0 +1,84 @@
+use crate::{
+    commands::{send::submit_unsigned_ingress, SendingOpts},
+    lib::{
+        get_principal, network::Network, AnyhowResult, AuthInfo, ParsedNnsAccount, ROLE_NNS_INDEX,
+    },
+    AUTH_FLAGS,
+};
+use candid::{CandidType, Encode, Nat};
+use clap::Parser;
+use icrc_ledger_types::icrc1::account::Account;
+
+#[derive(CandidType)]
+pub struct GetAccountIdentifierTransactionsArgs {
+    pub account_identifier: String,
+    pub start: Option<u64>,
+    pub max_results: u64,
+}
+
+/// Arguments to `get_account_transactions` on both the ICP index canister and ICRC-1 index canisters.
+#[derive(CandidType)]
+pub struct GetAccountTransactionsArgs {
+    pub account: Account,
+    pub start: Option<Nat>,
+    pub max_results: Nat,
+}
+
+/// Queries the transaction history of a ledger account, newest first, using the ICP index canister.
+#[derive(Parser)]
+pub struct AccountHistoryOpts {
+    /// The id of the account to query. Optional if a key is used.
+    #[arg(required_unless_present_any = AUTH_FLAGS)]
+    account_id: Option<ParsedNnsAccount>,
+
+    /// Only show transactions older than the transaction with this id.
+    #[arg(long)]
+    start: Option<u64>,
+
+    /// The maximum number of transactions to show.
+    #[arg(long, default_value_t = 20)]
+    max_results: u64,
+
+    #[command(flatten)]
+    sending_opts: SendingOpts,
+}
+
+#[tokio::main]
+pub async fn exec(auth: &AuthInfo, opts: AccountHistoryOpts, network: &Network) -> AnyhowResult {
+    let account_id = if let Some(id) = opts.account_id {
+        id
+    } else {
+        let account = Account {
+            owner: get_principal(auth)?,
+            subaccount: None,
+        };
+        ParsedNnsAccount::Icrc1(account)
+    };
+    let (method_name, args) = match account_id {
+        ParsedNnsAccount::Original(id) => (
+            "get_account_identifier_transactions",
+            Encode!(&GetAccountIdentifierTransactionsArgs {
+                account_identifier: id.to_hex(),
+                start: opts.start,
+                max_results: opts.max_results,
+            })?,
+        ),
+        ParsedNnsAccount::Icrc1(account) => (
+            "get_account_transactions",
+            Encode!(&GetAccountTransactionsArgs {
+                account,
+                start: opts.start.map(Nat::from),
+                max_results: opts.max_results.into(),
+            })?,
+        ),
+    };
+    submit_unsigned_ingress(
+        network.icp_index_canister_id(),
+        ROLE_NNS_INDEX,
+        method_name,
+        args,
+        opts.sending_opts,
+        network,
+    )
+    .await
+}
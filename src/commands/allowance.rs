@@ -0,0 +1,52 @@
+use crate::{
+    commands::{get_account, send::submit_unsigned_ingress, SendingOpts},
+    lib::{
+        network::Network, AnyhowResult, AuthInfo, ParsedAccount, ParsedSubaccount,
+        ROLE_ICRC1_LEDGER,
+    },
+    AUTH_FLAGS,
+};
+use candid::Encode;
+use clap::Parser;
+use icrc_ledger_types::icrc2::allowance::AllowanceArgs;
+
+/// Queries how much ICP a spender may still transfer from an account.
+///
+/// The `--of` parameter is required if a signing key is not provided.
+#[derive(Parser)]
+pub struct AllowanceOpts {
+    /// The account allowed to spend the ICP.
+    spender: ParsedAccount,
+
+    /// The subaccount of the spender.
+    #[arg(long)]
+    spender_subaccount: Option<ParsedSubaccount>,
+
+    /// The account that approved the spending. Optional if a key is used.
+    #[arg(long, required_unless_present_any = AUTH_FLAGS)]
+    of: Option<ParsedAccount>,
+
+    /// The subaccount of the account that approved the spending.
+    #[arg(long)]
+    of_subaccount: Option<ParsedSubaccount>,
+
+    #[command(flatten)]
+    sending_opts: SendingOpts,
+}
+
+#[tokio::main]
+pub async fn exec(auth: &AuthInfo, opts: AllowanceOpts, network: &Network) -> AnyhowResult {
+    let args = AllowanceArgs {
+        account: get_account(Some(auth), opts.of, opts.of_subaccount)?,
+        spender: get_account(None, Some(opts.spender), opts.spender_subaccount)?,
+    };
+    submit_unsigned_ingress(
+        network.ledger_canister_id(),
+        ROLE_ICRC1_LEDGER,
+        "icrc2_allowance",
+        Encode!(&args)?,
+        opts.sending_opts,
+        network,
+    )
+    .await
+}
@@ -0,0 +1,90 @@
+use crate::commands::{get_account, transfer::parse_tokens};
+use crate::lib::{
+    network::Network,
+    now_nanos,
+    signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
+    AnyhowResult, AuthInfo, IngressExpiryOpts, ParsedAccount, ParsedSubaccount, ROLE_ICRC1_LEDGER,
+};
+use anyhow::Context;
+use candid::Encode;
+use chrono::DateTime;
+use clap::Parser;
+use icp_ledger::Tokens;
+use icrc_ledger_types::{icrc1::transfer::Memo, icrc2::approve::ApproveArgs};
+
+/// Signs a message approving another account to spend ICP from one of your accounts.
+///
+/// The spender can then transfer up to the approved amount with `quill transfer-from`.
+#[derive(Parser)]
+pub struct ApproveOpts {
+    /// The account allowed to spend the ICP.
+    pub spender: ParsedAccount,
+
+    /// The subaccount of the spender.
+    #[arg(long)]
+    pub spender_subaccount: Option<ParsedSubaccount>,
+
+    /// The amount of ICP the spender may transfer (with up to 8 decimal digits after comma).
+    #[arg(long, value_parser = parse_tokens)]
+    pub amount: Tokens,
+
+    /// The subaccount to approve spending from.
+    #[arg(long)]
+    pub from_subaccount: Option<ParsedSubaccount>,
+
+    /// Only replace the current allowance if it is exactly this amount, to avoid approving
+    /// twice if the spender used the allowance in between.
+    #[arg(long, value_parser = parse_tokens)]
+    pub expected_allowance: Option<Tokens>,
+
+    /// When the approval expires, as an RFC 3339 timestamp (e.g. 2025-01-01T00:00:00Z).
+    #[arg(long, value_parser = parse_expiry)]
+    pub expires_at: Option<u64>,
+
+    /// Reference number, default is none.
+    #[arg(long)]
+    pub memo: Option<u64>,
+
+    /// Transaction fee, default is 0.0001 ICP.
+    #[arg(long, value_parser = parse_tokens)]
+    pub fee: Option<Tokens>,
+}
+
+pub fn exec(
+    auth: &AuthInfo,
+    opts: ApproveOpts,
+    ingress_expiry: IngressExpiryOpts,
+    network: &Network,
+) -> AnyhowResult<Vec<IngressWithRequestId>> {
+    let spender = get_account(None, Some(opts.spender), opts.spender_subaccount)?;
+    let args = ApproveArgs {
+        from_subaccount: opts.from_subaccount.map(|x| x.0 .0),
+        spender,
+        amount: opts.amount.get_e8s().into(),
+        expected_allowance: opts.expected_allowance.map(|x| x.get_e8s().into()),
+        expires_at: opts.expires_at,
+        fee: opts.fee.map(|x| x.get_e8s().into()),
+        memo: opts.memo.map(Memo::from),
+        created_at_time: Some(now_nanos()),
+    };
+    let msg = sign_ingress_with_request_status_query(
+        auth,
+        ingress_expiry,
+        network.ledger_canister_id(),
+        ROLE_ICRC1_LEDGER,
+        "icrc2_approve",
+        Encode!(&args)?,
+    )?;
+    Ok(vec![msg])
+}
+
+/// Parses an RFC 3339 timestamp into nanoseconds since the Unix epoch.
+pub fn parse_expiry(timestamp: &str) -> AnyhowResult<u64> {
+    let datetime = DateTime::parse_from_rfc3339(timestamp).with_context(|| {
+        format!("Invalid timestamp {timestamp}, expected e.g. 2025-01-01T00:00:00Z")
+    })?;
+    datetime
+        .timestamp_nanos_opt()
+        .and_then(|nanos| u64::try_from(nanos).ok())
+        .context("Timestamp out of range")
+}
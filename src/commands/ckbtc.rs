@@ -8,14 +8,18 @@ use icrc_ledger_types::icrc1::account::Account;
 use num_bigint::Sign;
 use sha2::{Digest, Sha256};
 
-use crate::lib::{ckbtc_minter_canister_id, AnyhowResult, AuthInfo};
+use crate::lib::{network::Network, qr::QrOutputOpts, AnyhowResult, AuthInfo, IngressExpiryOpts};
 
 use super::print_vec;
 
+mod account_history;
+mod allowance;
+mod approve;
 mod balance;
 mod retrieve_btc;
 mod retrieve_btc_status;
 mod transfer;
+mod transfer_from;
 mod update_balance;
 mod withdrawal_address;
 
@@ -23,8 +27,12 @@ mod withdrawal_address;
 #[derive(Subcommand)]
 pub enum CkbtcCommand {
     Balance(balance::BalanceOpts),
+    AccountHistory(account_history::AccountHistoryOpts),
     UpdateBalance(update_balance::UpdateBalanceOpts),
     Transfer(transfer::TransferOpts),
+    Approve(approve::ApproveOpts),
+    Allowance(allowance::AllowanceOpts),
+    TransferFrom(transfer_from::TransferFromOpts),
     RetrieveBtc(retrieve_btc::RetrieveBtcOpts),
     RetrieveBtcStatus(retrieve_btc_status::RetrieveBtcStatusOpts),
     WithdrawalAddress(withdrawal_address::GetWithdrawalAddressOpts),
@@ -33,30 +41,45 @@ pub enum CkbtcCommand {
 pub fn dispatch(
     auth: &AuthInfo,
     command: CkbtcCommand,
-    qr: bool,
-    fetch_root_key: bool,
+    ingress_expiry: IngressExpiryOpts,
+    qr: &QrOutputOpts,
+    network: &Network,
 ) -> AnyhowResult {
     match command {
         CkbtcCommand::UpdateBalance(opts) => {
-            let out = update_balance::exec(auth, opts)?;
+            let out = update_balance::exec(auth, opts, ingress_expiry, network)?;
             print_vec(qr, &out)?;
         }
         CkbtcCommand::Transfer(opts) => {
-            let out = transfer::exec(auth, opts)?;
+            let out = transfer::exec(auth, opts, ingress_expiry, network)?;
+            print_vec(qr, &out)?;
+        }
+        CkbtcCommand::Approve(opts) => {
+            let out = approve::exec(auth, opts, ingress_expiry, network)?;
+            print_vec(qr, &out)?;
+        }
+        CkbtcCommand::Allowance(opts) => {
+            allowance::exec(auth, opts, network)?;
+        }
+        CkbtcCommand::TransferFrom(opts) => {
+            let out = transfer_from::exec(auth, opts, ingress_expiry, network)?;
             print_vec(qr, &out)?;
         }
         CkbtcCommand::RetrieveBtc(opts) => {
-            let out = retrieve_btc::exec(auth, opts)?;
+            let out = retrieve_btc::exec(auth, opts, ingress_expiry, network)?;
             print_vec(qr, &out)?;
         }
         CkbtcCommand::RetrieveBtcStatus(opts) => {
-            retrieve_btc_status::exec(opts, fetch_root_key)?;
+            retrieve_btc_status::exec(opts, network)?;
         }
         CkbtcCommand::Balance(opts) => {
-            balance::exec(auth, opts, fetch_root_key)?;
+            balance::exec(auth, opts, network)?;
+        }
+        CkbtcCommand::AccountHistory(opts) => {
+            account_history::exec(auth, opts, network)?;
         }
         CkbtcCommand::WithdrawalAddress(opts) => {
-            withdrawal_address::exec(auth, opts)?;
+            withdrawal_address::exec(auth, opts, network)?;
         }
     }
     Ok(())
@@ -86,7 +109,7 @@ impl FromStr for Btc {
 
 // Corresponds to ckbtc_minter.get_withdrawal_address(). We do not actually need to make the call
 // because the algorithm is considered stable.
-fn ckbtc_withdrawal_address(user: &Principal, testnet: bool) -> Account {
+fn ckbtc_withdrawal_address(user: &Principal, testnet: bool, network: &Network) -> Account {
     const DOMAIN: &str = "ckbtc";
     let mut hasher = Sha256::new();
     hasher.update([DOMAIN.len() as u8]);
@@ -94,7 +117,7 @@ fn ckbtc_withdrawal_address(user: &Principal, testnet: bool) -> Account {
     hasher.update(user.as_slice());
     hasher.update([0; 8]);
     Account {
-        owner: ckbtc_minter_canister_id(testnet),
+        owner: network.ckbtc_minter_canister_id(testnet),
         subaccount: Some(hasher.finalize().into()),
     }
 }
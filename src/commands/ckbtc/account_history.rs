@@ -0,0 +1,61 @@
+use candid::{Encode, Nat};
+use clap::Parser;
+
+use crate::{
+    commands::{
+        account_history::GetAccountTransactionsArgs, get_account, send::submit_unsigned_ingress,
+        SendingOpts,
+    },
+    lib::{
+        network::Network, AnyhowResult, AuthInfo, ParsedAccount, ParsedSubaccount, ROLE_ICRC1_INDEX,
+    },
+    AUTH_FLAGS,
+};
+
+/// Queries the ckBTC transaction history of an account, newest first, using the ckBTC index canister.
+///
+/// The `--of` parameter is required if a signing key is not provided.
+#[derive(Parser)]
+pub struct AccountHistoryOpts {
+    /// The account to query. Optional if a key is used.
+    #[arg(long, required_unless_present_any = AUTH_FLAGS)]
+    of: Option<ParsedAccount>,
+
+    /// The subaccount of the account to query.
+    #[arg(long)]
+    of_subaccount: Option<ParsedSubaccount>,
+
+    /// Only show transactions older than the transaction with this id.
+    #[arg(long)]
+    start: Option<u64>,
+
+    /// The maximum number of transactions to show.
+    #[arg(long, default_value_t = 20)]
+    max_results: u64,
+
+    #[command(flatten)]
+    sending_opts: SendingOpts,
+
+    /// Uses ckTESTBTC instead of ckBTC.
+    #[arg(long)]
+    testnet: bool,
+}
+
+#[tokio::main]
+pub async fn exec(auth: &AuthInfo, opts: AccountHistoryOpts, network: &Network) -> AnyhowResult {
+    let account = get_account(Some(auth), opts.of, opts.of_subaccount)?;
+    let args = Encode!(&GetAccountTransactionsArgs {
+        account,
+        start: opts.start.map(Nat::from),
+        max_results: opts.max_results.into(),
+    })?;
+    submit_unsigned_ingress(
+        network.ckbtc_index_canister_id(opts.testnet),
+        ROLE_ICRC1_INDEX,
+        "get_account_transactions",
+        args,
+        opts.sending_opts,
+        network,
+    )
+    .await
+}
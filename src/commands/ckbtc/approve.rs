@@ -0,0 +1,79 @@
+use candid::{Encode, Nat};
+use clap::Parser;
+use icrc_ledger_types::{icrc1::transfer::Memo, icrc2::approve::ApproveArgs};
+
+use crate::{
+    commands::{approve::parse_expiry, get_account},
+    lib::{
+        network::Network,
+        now_nanos,
+        signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
+        AnyhowResult, AuthInfo, IngressExpiryOpts, ParsedAccount, ParsedSubaccount,
+        ROLE_ICRC1_LEDGER,
+    },
+};
+
+use super::Btc;
+
+/// Signs a message approving another account to spend ckBTC from one of your accounts.
+#[derive(Parser)]
+pub struct ApproveOpts {
+    /// The account allowed to spend the ckBTC.
+    spender: ParsedAccount,
+    /// The subaccount of the spender.
+    #[arg(long)]
+    spender_subaccount: Option<ParsedSubaccount>,
+    /// The subaccount to approve spending from.
+    #[arg(long)]
+    from_subaccount: Option<ParsedSubaccount>,
+    /// The amount, in decimal ckBTC, the spender may transfer.
+    #[arg(long)]
+    amount: Option<Btc>,
+    /// The amount, in integer satoshis, the spender may transfer.
+    #[arg(long, conflicts_with = "amount", required_unless_present = "amount")]
+    satoshis: Option<Nat>,
+    /// Only replace the current allowance if it is exactly this many satoshis.
+    #[arg(long)]
+    expected_allowance: Option<Nat>,
+    /// When the approval expires, as an RFC 3339 timestamp (e.g. 2025-01-01T00:00:00Z).
+    #[arg(long, value_parser = parse_expiry)]
+    expires_at: Option<u64>,
+    /// An integer memo for this transaction.
+    #[arg(long)]
+    memo: Option<u64>,
+    /// The expected fee for this transaction.
+    #[arg(long)]
+    fee: Option<Nat>,
+    /// Uses ckTESTBTC instead of ckBTC.
+    #[arg(long)]
+    testnet: bool,
+}
+
+pub fn exec(
+    auth: &AuthInfo,
+    opts: ApproveOpts,
+    ingress_expiry: IngressExpiryOpts,
+    network: &Network,
+) -> AnyhowResult<Vec<IngressWithRequestId>> {
+    let spender = get_account(None, Some(opts.spender), opts.spender_subaccount)?;
+    let amount = opts.satoshis.unwrap_or_else(|| opts.amount.unwrap().0);
+    let args = ApproveArgs {
+        from_subaccount: opts.from_subaccount.map(|x| x.0 .0),
+        spender,
+        amount,
+        expected_allowance: opts.expected_allowance,
+        expires_at: opts.expires_at,
+        fee: opts.fee,
+        memo: opts.memo.map(Memo::from),
+        created_at_time: Some(now_nanos()),
+    };
+    let message = sign_ingress_with_request_status_query(
+        auth,
+        ingress_expiry,
+        network.ckbtc_canister_id(opts.testnet),
+        ROLE_ICRC1_LEDGER,
+        "icrc2_approve",
+        Encode!(&args)?,
+    )?;
+    Ok(vec![message])
+}
@@ -4,7 +4,7 @@ use clap::Parser;
 use crate::{
     commands::{get_account, send::submit_unsigned_ingress, SendingOpts},
     lib::{
-        ckbtc_canister_id, AnyhowResult, AuthInfo, ParsedAccount, ParsedSubaccount,
+        network::Network, AnyhowResult, AuthInfo, ParsedAccount, ParsedSubaccount,
         ROLE_ICRC1_LEDGER,
     },
     AUTH_FLAGS,
@@ -32,15 +32,15 @@ pub struct BalanceOpts {
 }
 
 #[tokio::main]
-pub async fn exec(auth: &AuthInfo, opts: BalanceOpts, fetch_root_key: bool) -> AnyhowResult {
+pub async fn exec(auth: &AuthInfo, opts: BalanceOpts, network: &Network) -> AnyhowResult {
     let account = get_account(Some(auth), opts.of, opts.of_subaccount)?;
     submit_unsigned_ingress(
-        ckbtc_canister_id(opts.testnet),
+        network.ckbtc_canister_id(opts.testnet),
         ROLE_ICRC1_LEDGER,
         "icrc1_balance_of",
         Encode!(&account)?,
         opts.sending_opts,
-        fetch_root_key,
+        network,
     )
     .await?;
     Ok(())
@@ -9,9 +9,11 @@ use icrc_ledger_types::icrc1::transfer::{Memo, TransferArg};
 use crate::{
     commands::get_principal,
     lib::{
-        ckbtc_canister_id, ckbtc_minter_canister_id, now_nanos,
+        network::Network,
+        now_nanos,
         signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
-        AnyhowResult, AuthInfo, ParsedSubaccount, ROLE_CKBTC_MINTER, ROLE_ICRC1_LEDGER,
+        AnyhowResult, AuthInfo, IngressExpiryOpts, ParsedSubaccount, ROLE_CKBTC_MINTER,
+        ROLE_ICRC1_LEDGER,
     },
 };
 
@@ -57,7 +59,12 @@ pub struct RetrieveBtcOpts {
     testnet: bool,
 }
 
-pub fn exec(auth: &AuthInfo, opts: RetrieveBtcOpts) -> AnyhowResult<Vec<IngressWithRequestId>> {
+pub fn exec(
+    auth: &AuthInfo,
+    opts: RetrieveBtcOpts,
+    ingress_expiry: IngressExpiryOpts,
+    network: &Network,
+) -> AnyhowResult<Vec<IngressWithRequestId>> {
     let principal = get_principal(auth)?;
     let mut messages = vec![];
     let amount = opts.satoshis.unwrap_or_else(|| opts.amount.unwrap().0);
@@ -68,11 +75,12 @@ pub fn exec(auth: &AuthInfo, opts: RetrieveBtcOpts) -> AnyhowResult<Vec<IngressW
             fee: opts.fee,
             from_subaccount: opts.from_subaccount.map(|x| x.0 .0),
             memo: opts.memo.map(Memo::from),
-            to: ckbtc_withdrawal_address(&principal, opts.testnet),
+            to: ckbtc_withdrawal_address(&principal, opts.testnet, network),
         };
         messages.push(sign_ingress_with_request_status_query(
             auth,
-            ckbtc_canister_id(opts.testnet),
+            ingress_expiry,
+            network.ckbtc_canister_id(opts.testnet),
             ROLE_ICRC1_LEDGER,
             "icrc1_transfer",
             Encode!(&transfer_args)?,
@@ -84,7 +92,8 @@ pub fn exec(auth: &AuthInfo, opts: RetrieveBtcOpts) -> AnyhowResult<Vec<IngressW
     };
     messages.push(sign_ingress_with_request_status_query(
         auth,
-        ckbtc_minter_canister_id(opts.testnet),
+        ingress_expiry,
+        network.ckbtc_minter_canister_id(opts.testnet),
         ROLE_CKBTC_MINTER,
         "retrieve_btc",
         Encode!(&retrieve_args)?,
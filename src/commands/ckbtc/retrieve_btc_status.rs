@@ -4,7 +4,7 @@ use ic_ckbtc_minter::queries::RetrieveBtcStatusRequest;
 
 use crate::{
     commands::{send::submit_unsigned_ingress, SendingOpts},
-    lib::{ckbtc_minter_canister_id, AnyhowResult, ROLE_CKBTC_MINTER},
+    lib::{network::Network, AnyhowResult, ROLE_CKBTC_MINTER},
 };
 
 /// Sends a message to check the status of a ckBTC-to-BTC conversion.
@@ -22,17 +22,17 @@ pub struct RetrieveBtcStatusOpts {
 }
 
 #[tokio::main]
-pub async fn exec(opts: RetrieveBtcStatusOpts, fetch_root_key: bool) -> AnyhowResult {
+pub async fn exec(opts: RetrieveBtcStatusOpts, network: &Network) -> AnyhowResult {
     let args = RetrieveBtcStatusRequest {
         block_index: opts.block_index,
     };
     submit_unsigned_ingress(
-        ckbtc_minter_canister_id(opts.testnet),
+        network.ckbtc_minter_canister_id(opts.testnet),
         ROLE_CKBTC_MINTER,
         "retrieve_btc_status",
         Encode!(&args)?,
         opts.sending_opts,
-        fetch_root_key,
+        network,
     )
     .await?;
     Ok(())
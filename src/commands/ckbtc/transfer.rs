@@ -5,9 +5,11 @@ use icrc_ledger_types::icrc1::transfer::{Memo, TransferArg};
 use crate::{
     commands::get_account,
     lib::{
-        ckbtc_canister_id, now_nanos,
+        network::Network,
+        now_nanos,
         signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
-        AnyhowResult, AuthInfo, ParsedAccount, ParsedSubaccount, ROLE_ICRC1_LEDGER,
+        AnyhowResult, AuthInfo, IngressExpiryOpts, ParsedAccount, ParsedSubaccount,
+        ROLE_ICRC1_LEDGER,
     },
 };
 
@@ -41,7 +43,12 @@ pub struct TransferOpts {
     testnet: bool,
 }
 
-pub fn exec(auth: &AuthInfo, opts: TransferOpts) -> AnyhowResult<Vec<IngressWithRequestId>> {
+pub fn exec(
+    auth: &AuthInfo,
+    opts: TransferOpts,
+    ingress_expiry: IngressExpiryOpts,
+    network: &Network,
+) -> AnyhowResult<Vec<IngressWithRequestId>> {
     let to = get_account(None, Some(opts.to), opts.to_subaccount)?;
     let amount = opts.satoshis.unwrap_or_else(|| opts.amount.unwrap().0);
     let args = TransferArg {
@@ -54,7 +61,8 @@ pub fn exec(auth: &AuthInfo, opts: TransferOpts) -> AnyhowResult<Vec<IngressWith
     };
     let message = sign_ingress_with_request_status_query(
         auth,
-        ckbtc_canister_id(opts.testnet),
+        ingress_expiry,
+        network.ckbtc_canister_id(opts.testnet),
         ROLE_ICRC1_LEDGER,
         "icrc1_transfer",
         Encode!(&args)?,
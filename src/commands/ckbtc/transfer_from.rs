@@ -0,0 +1,77 @@
+use candid::{Encode, Nat};
+use clap::Parser;
+use icrc_ledger_types::{icrc1::transfer::Memo, icrc2::transfer_from::TransferFromArgs};
+
+use crate::{
+    commands::get_account,
+    lib::{
+        network::Network,
+        now_nanos,
+        signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
+        AnyhowResult, AuthInfo, IngressExpiryOpts, ParsedAccount, ParsedSubaccount,
+        ROLE_ICRC1_LEDGER,
+    },
+};
+
+use super::Btc;
+
+/// Signs a message to transfer ckBTC out of an account that approved you as a spender.
+#[derive(Parser)]
+pub struct TransferFromOpts {
+    /// The account to transfer ckBTC from.
+    from: ParsedAccount,
+    /// The subaccount to transfer ckBTC from.
+    #[arg(long)]
+    from_subaccount: Option<ParsedSubaccount>,
+    /// The account to transfer ckBTC to. Defaults to your own account.
+    #[arg(long)]
+    to: Option<ParsedAccount>,
+    /// The subaccount to transfer ckBTC to.
+    #[arg(long)]
+    to_subaccount: Option<ParsedSubaccount>,
+    /// Your subaccount that was approved as the spender.
+    #[arg(long)]
+    spender_subaccount: Option<ParsedSubaccount>,
+    /// The amount, in decimal ckBTC, to transfer.
+    #[arg(long)]
+    amount: Option<Btc>,
+    /// The amount, in integer satoshis, to transfer.
+    #[arg(long, conflicts_with = "amount", required_unless_present = "amount")]
+    satoshis: Option<Nat>,
+    /// An integer memo for this transaction.
+    #[arg(long)]
+    memo: Option<u64>,
+    /// The expected fee for this transaction.
+    #[arg(long)]
+    fee: Option<Nat>,
+    /// Uses ckTESTBTC instead of ckBTC.
+    #[arg(long)]
+    testnet: bool,
+}
+
+pub fn exec(
+    auth: &AuthInfo,
+    opts: TransferFromOpts,
+    ingress_expiry: IngressExpiryOpts,
+    network: &Network,
+) -> AnyhowResult<Vec<IngressWithRequestId>> {
+    let amount = opts.satoshis.unwrap_or_else(|| opts.amount.unwrap().0);
+    let args = TransferFromArgs {
+        spender_subaccount: opts.spender_subaccount.map(|x| x.0 .0),
+        from: get_account(None, Some(opts.from), opts.from_subaccount)?,
+        to: get_account(Some(auth), opts.to, opts.to_subaccount)?,
+        amount,
+        fee: opts.fee,
+        memo: opts.memo.map(Memo::from),
+        created_at_time: Some(now_nanos()),
+    };
+    let message = sign_ingress_with_request_status_query(
+        auth,
+        ingress_expiry,
+        network.ckbtc_canister_id(opts.testnet),
+        ROLE_ICRC1_LEDGER,
+        "icrc2_transfer_from",
+        Encode!(&args)?,
+    )?;
+    Ok(vec![message])
+}
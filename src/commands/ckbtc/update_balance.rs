@@ -5,9 +5,10 @@ use ic_ckbtc_minter::updates::update_balance::UpdateBalanceArgs;
 use crate::{
     commands::get_account,
     lib::{
-        ckbtc_minter_canister_id,
+        network::Network,
         signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
-        AnyhowResult, AuthInfo, ParsedAccount, ParsedSubaccount, ROLE_CKBTC_MINTER,
+        AnyhowResult, AuthInfo, IngressExpiryOpts, ParsedAccount, ParsedSubaccount,
+        ROLE_CKBTC_MINTER,
     },
     AUTH_FLAGS,
 };
@@ -26,7 +27,12 @@ pub struct UpdateBalanceOpts {
     testnet: bool,
 }
 
-pub fn exec(auth: &AuthInfo, opts: UpdateBalanceOpts) -> AnyhowResult<Vec<IngressWithRequestId>> {
+pub fn exec(
+    auth: &AuthInfo,
+    opts: UpdateBalanceOpts,
+    ingress_expiry: IngressExpiryOpts,
+    network: &Network,
+) -> AnyhowResult<Vec<IngressWithRequestId>> {
     let account = get_account(Some(auth), opts.sender, opts.subaccount)?;
     let args = UpdateBalanceArgs {
         owner: Some(account.owner),
@@ -34,7 +40,8 @@ pub fn exec(auth: &AuthInfo, opts: UpdateBalanceOpts) -> AnyhowResult<Vec<Ingres
     };
     let message = sign_ingress_with_request_status_query(
         auth,
-        ckbtc_minter_canister_id(opts.testnet),
+        ingress_expiry,
+        network.ckbtc_minter_canister_id(opts.testnet),
         ROLE_CKBTC_MINTER,
         "update_balance",
         Encode!(&args)?,
@@ -3,7 +3,7 @@ use clap::Parser;
 
 use crate::{
     commands::get_principal,
-    lib::{AnyhowResult, AuthInfo, ParsedAccount},
+    lib::{network::Network, AnyhowResult, AuthInfo, ParsedAccount},
     AUTH_FLAGS,
 };
 
@@ -26,13 +26,13 @@ pub struct GetWithdrawalAddressOpts {
     testnet: bool,
 }
 
-pub fn exec(auth: &AuthInfo, opts: GetWithdrawalAddressOpts) -> AnyhowResult {
+pub fn exec(auth: &AuthInfo, opts: GetWithdrawalAddressOpts, network: &Network) -> AnyhowResult {
     let principal = if let Some(principal) = opts.of {
         principal
     } else {
         get_principal(auth)?
     };
-    let address = ParsedAccount(ckbtc_withdrawal_address(&principal, opts.testnet));
+    let address = ParsedAccount(ckbtc_withdrawal_address(&principal, opts.testnet, network));
     println!("{address}");
     eprintln!("Use the --already-transferred flag with `quill ckbtc retrieve-btc` to register any transfers.");
     Ok(())
@@ -0,0 +1,99 @@
+use candid::{CandidType, Encode, Nat, Principal};
+use clap::Subcommand;
+use icrc_ledger_types::{icrc1::account::Account, icrc2::approve::ApproveArgs};
+
+use crate::lib::{
+    network::Network,
+    now_nanos,
+    qr::QrOutputOpts,
+    signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
+    AnyhowResult, AuthInfo, IngressExpiryOpts, ROLE_ICRC1_LEDGER,
+};
+
+use super::print_vec;
+
+mod retrieve_eth_status;
+mod withdraw_erc20;
+mod withdraw_eth;
+
+/// Commands for chain-key ether (ckETH) and chain-key ERC-20 tokens (ckERC20), such as ckUSDC.
+#[derive(Subcommand)]
+pub enum CkethCommand {
+    WithdrawEth(withdraw_eth::WithdrawEthOpts),
+    WithdrawErc20(withdraw_erc20::WithdrawErc20Opts),
+    RetrieveEthStatus(retrieve_eth_status::RetrieveEthStatusOpts),
+}
+
+pub fn dispatch(
+    auth: &AuthInfo,
+    command: CkethCommand,
+    ingress_expiry: IngressExpiryOpts,
+    qr: &QrOutputOpts,
+    network: &Network,
+) -> AnyhowResult {
+    match command {
+        CkethCommand::WithdrawEth(opts) => {
+            let out = withdraw_eth::exec(auth, opts, ingress_expiry, network)?;
+            print_vec(qr, &out)?;
+        }
+        CkethCommand::WithdrawErc20(opts) => {
+            let out = withdraw_erc20::exec(auth, opts, ingress_expiry, network)?;
+            print_vec(qr, &out)?;
+        }
+        CkethCommand::RetrieveEthStatus(opts) => {
+            retrieve_eth_status::exec(opts, network)?;
+        }
+    }
+    Ok(())
+}
+
+/// Signs an approval for the ckETH minter to burn `amount` from the given ledger.
+fn approve_minter(
+    auth: &AuthInfo,
+    ledger_canister_id: Principal,
+    amount: Nat,
+    from_subaccount: Option<[u8; 32]>,
+    testnet: bool,
+    ingress_expiry: IngressExpiryOpts,
+    network: &Network,
+) -> AnyhowResult<IngressWithRequestId> {
+    let args = ApproveArgs {
+        from_subaccount,
+        spender: Account {
+            owner: network.cketh_minter_canister_id(testnet),
+            subaccount: None,
+        },
+        amount,
+        expected_allowance: None,
+        expires_at: None,
+        fee: None,
+        memo: None,
+        created_at_time: Some(now_nanos()),
+    };
+    sign_ingress_with_request_status_query(
+        auth,
+        ingress_expiry,
+        ledger_canister_id,
+        ROLE_ICRC1_LEDGER,
+        "icrc2_approve",
+        Encode!(&args)?,
+    )
+}
+
+// Argument types of the ckETH minter, from candid/cketh_minter.did.
+
+#[derive(CandidType)]
+struct WithdrawalArg {
+    recipient: String,
+    amount: Nat,
+    from_subaccount: Option<[u8; 32]>,
+}
+
+#[derive(CandidType)]
+struct WithdrawErc20Arg {
+    amount: Nat,
+    ckerc20_ledger_id: Principal,
+    recipient: String,
+    from_cketh_subaccount: Option<[u8; 32]>,
+    from_ckerc20_subaccount: Option<[u8; 32]>,
+}
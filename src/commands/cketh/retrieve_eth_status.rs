@@ -0,0 +1,35 @@
+use candid::Encode;
+use clap::Parser;
+
+use crate::{
+    commands::{send::submit_unsigned_ingress, SendingOpts},
+    lib::{network::Network, AnyhowResult, ROLE_CKETH_MINTER},
+};
+
+/// Sends a message to check the status of a ckETH-to-ETH or ckERC20-to-ERC-20 withdrawal.
+///
+/// Withdrawals can be made with the `quill cketh withdraw-eth` and `quill cketh withdraw-erc20` commands.
+#[derive(Parser)]
+pub struct RetrieveEthStatusOpts {
+    /// The block index to check. For ckERC20 withdrawals, this is the block index of the ckETH fee.
+    block_index: u64,
+    #[command(flatten)]
+    sending_opts: SendingOpts,
+    /// Uses ckSepoliaETH instead of ckETH.
+    #[arg(long)]
+    testnet: bool,
+}
+
+#[tokio::main]
+pub async fn exec(opts: RetrieveEthStatusOpts, network: &Network) -> AnyhowResult {
+    submit_unsigned_ingress(
+        network.cketh_minter_canister_id(opts.testnet),
+        ROLE_CKETH_MINTER,
+        "retrieve_eth_status",
+        Encode!(&opts.block_index)?,
+        opts.sending_opts,
+        network,
+    )
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use bigdecimal::BigDecimal;
+use candid::{Encode, Principal};
+use clap::Parser;
+
+use crate::{
+    commands::icrc1::{anonymous_agent, query_ledger},
+    lib::{
+        eth::EthAddress,
+        network::Network,
+        signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
+        tokens_to_units, AnyhowResult, AuthInfo, IngressExpiryOpts, ParsedSubaccount,
+        ROLE_CKETH_MINTER,
+    },
+};
+
+use super::{approve_minter, WithdrawErc20Arg};
+
+/// Signs messages to withdraw ERC-20 tokens in exchange for ckERC20 tokens, such as USDC for ckUSDC.
+///
+/// The ckETH minter burns ckETH to pay for the Ethereum transaction, so this command generates three messages by
+/// default; an approval for the minter to burn up to `--max-fee` ckETH, an approval for it to burn the ckERC20
+/// tokens, and a request for the ERC-20 tokens. If you have already approved the minter, you can use the
+/// `--already-approved` flag to skip the first two messages. Unused ckETH is reimbursed.
+///
+/// Unless `--decimals` is passed, the ckERC20 ledger is first queried for the token's decimals, which requires
+/// network access.
+#[derive(Parser)]
+pub struct WithdrawErc20Opts {
+    /// The Ethereum address to send the tokens to.
+    to: EthAddress,
+    /// The canister ID of the ckERC20 token's ledger, e.g. xevnm-gaaaa-aaaar-qafnq-cai for ckUSDC.
+    #[arg(long)]
+    ledger_canister_id: Principal,
+    /// The quantity of tokens to withdraw.
+    #[arg(long)]
+    amount: BigDecimal,
+    /// The number of decimals of the token (e.g. 6 for ckUSDC).
+    #[arg(long)]
+    decimals: Option<u8>,
+    /// The most ckETH, in decimal ETH, that the minter may burn to pay for the Ethereum transaction.
+    #[arg(long, required_unless_present = "already_approved")]
+    max_fee: Option<BigDecimal>,
+    /// The subaccount to withdraw the ckERC20 tokens and the ckETH fee from.
+    #[arg(long)]
+    from_subaccount: Option<ParsedSubaccount>,
+    /// Skips signing the approvals, signing only the request for the ERC-20 tokens.
+    #[arg(long, conflicts_with = "max_fee")]
+    already_approved: bool,
+    /// Uses ckSepoliaETH and the Sepolia ckERC20 tokens.
+    #[arg(long)]
+    testnet: bool,
+}
+
+#[tokio::main]
+pub async fn exec(
+    auth: &AuthInfo,
+    opts: WithdrawErc20Opts,
+    ingress_expiry: IngressExpiryOpts,
+    network: &Network,
+) -> AnyhowResult<Vec<IngressWithRequestId>> {
+    let decimals = if let Some(decimals) = opts.decimals {
+        decimals
+    } else {
+        let agent = anonymous_agent(network).await?;
+        query_ledger(&agent, opts.ledger_canister_id, "icrc1_decimals").await?
+    };
+    let amount = tokens_to_units(&opts.amount, decimals)?;
+    let from_subaccount = opts.from_subaccount.map(|x| x.0 .0);
+    let mut messages = vec![];
+    if let Some(max_fee) = opts.max_fee {
+        messages.push(approve_minter(
+            auth,
+            network.cketh_canister_id(opts.testnet),
+            tokens_to_units(&max_fee, 18)?,
+            from_subaccount,
+            opts.testnet,
+            ingress_expiry,
+            network,
+        )?);
+        messages.push(approve_minter(
+            auth,
+            opts.ledger_canister_id,
+            amount.clone(),
+            from_subaccount,
+            opts.testnet,
+            ingress_expiry,
+            network,
+        )?);
+    }
+    let args = WithdrawErc20Arg {
+        amount,
+        ckerc20_ledger_id: opts.ledger_canister_id,
+        recipient: opts.to.to_string(),
+        from_cketh_subaccount: from_subaccount,
+        from_ckerc20_subaccount: from_subaccount,
+    };
+    messages.push(sign_ingress_with_request_status_query(
+        auth,
+        ingress_expiry,
+        network.cketh_minter_canister_id(opts.testnet),
+        ROLE_CKETH_MINTER,
+        "withdraw_erc20",
+        Encode!(&args)?,
+    )?);
+    Ok(messages)
+}
@@ -0,0 +1,81 @@
+use bigdecimal::BigDecimal;
+use candid::{Encode, Nat};
+use clap::Parser;
+
+use crate::lib::{
+    eth::EthAddress,
+    network::Network,
+    signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
+    tokens_to_units, AnyhowResult, AuthInfo, IngressExpiryOpts, ParsedSubaccount,
+    ROLE_CKETH_MINTER,
+};
+
+use super::{approve_minter, WithdrawalArg};
+
+/// Signs messages to withdraw ETH in exchange for ckETH.
+///
+/// This command generates two messages by default; an approval for the ckETH minter to burn the ckETH, and a request
+/// for ETH. However, if you have already approved the minter, you can use the `--already-approved` flag to skip the
+/// first message.
+///
+/// Ethereum transactions take a while, so the response to the second message will not be a success state, but rather
+/// a block index. Use the `quill cketh retrieve-eth-status` command to check the status of this withdrawal.
+#[derive(Parser)]
+pub struct WithdrawEthOpts {
+    /// The Ethereum address to send the ETH to.
+    to: EthAddress,
+    /// The quantity, in decimal ETH, to withdraw. The Ethereum transaction fee is deducted from this amount.
+    #[arg(long)]
+    amount: Option<BigDecimal>,
+    /// The quantity, in integer wei, to withdraw.
+    #[arg(long, conflicts_with = "amount", required_unless_present = "amount")]
+    wei: Option<Nat>,
+    /// The subaccount to withdraw the ckETH from.
+    #[arg(long)]
+    from_subaccount: Option<ParsedSubaccount>,
+    /// Skips signing the approval, signing only the request for ETH.
+    #[arg(long)]
+    already_approved: bool,
+    /// Uses ckSepoliaETH instead of ckETH.
+    #[arg(long)]
+    testnet: bool,
+}
+
+pub fn exec(
+    auth: &AuthInfo,
+    opts: WithdrawEthOpts,
+    ingress_expiry: IngressExpiryOpts,
+    network: &Network,
+) -> AnyhowResult<Vec<IngressWithRequestId>> {
+    let amount = match opts.wei {
+        Some(wei) => wei,
+        None => tokens_to_units(&opts.amount.unwrap(), 18)?,
+    };
+    let from_subaccount = opts.from_subaccount.map(|x| x.0 .0);
+    let mut messages = vec![];
+    if !opts.already_approved {
+        messages.push(approve_minter(
+            auth,
+            network.cketh_canister_id(opts.testnet),
+            amount.clone(),
+            from_subaccount,
+            opts.testnet,
+            ingress_expiry,
+            network,
+        )?);
+    }
+    let args = WithdrawalArg {
+        recipient: opts.to.to_string(),
+        amount,
+        from_subaccount,
+    };
+    messages.push(sign_ingress_with_request_status_query(
+        auth,
+        ingress_expiry,
+        network.cketh_minter_canister_id(opts.testnet),
+        ROLE_CKETH_MINTER,
+        "withdraw_eth",
+        Encode!(&args)?,
+    )?);
+    Ok(messages)
+}
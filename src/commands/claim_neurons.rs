@@ -1,7 +1,7 @@
 use crate::lib::{
-    genesis_token_canister_id,
+    network::Network,
     signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
-    AnyhowResult, AuthInfo, ROLE_NNS_GTC,
+    AnyhowResult, AuthInfo, IngressExpiryOpts, ROLE_NNS_GTC,
 };
 use anyhow::anyhow;
 use candid::Encode;
@@ -12,31 +12,44 @@ use k256::elliptic_curve::sec1::ToEncodedPoint;
 #[derive(Parser)]
 pub struct ClaimNeuronOpts;
 
-pub fn exec(auth: &AuthInfo) -> AnyhowResult<Vec<IngressWithRequestId>> {
+pub fn exec(
+    auth: &AuthInfo,
+    ingress_expiry: IngressExpiryOpts,
+    network: &Network,
+) -> AnyhowResult<Vec<IngressWithRequestId>> {
     match auth {
         AuthInfo::K256Key(pk) => {
             let point = pk.public_key().to_encoded_point(false);
             let sig = Encode!(&hex::encode(point.as_bytes()))?;
             Ok(vec![sign_ingress_with_request_status_query(
                 auth,
-                genesis_token_canister_id(),
+                ingress_expiry,
+                network.genesis_token_canister_id(),
                 ROLE_NNS_GTC,
                 "claim_neurons",
                 sig,
             )?])
         }
         #[cfg(feature = "ledger")]
-        AuthInfo::Ledger => {
+        AuthInfo::Ledger {
+            derivation_path,
+            speculos,
+        } => {
             use crate::lib::ledger::LedgerIdentity;
             use k256::PublicKey;
             use pkcs8::DecodePublicKey;
 
-            let point = PublicKey::from_public_key_der(&LedgerIdentity::new()?.public_key()?.1)?
-                .to_encoded_point(false);
+            let point = PublicKey::from_public_key_der(
+                &LedgerIdentity::new(derivation_path.clone(), speculos.as_deref())?
+                    .public_key()?
+                    .1,
+            )?
+            .to_encoded_point(false);
             let sig = Encode!(&hex::encode(point.as_bytes()))?;
             Ok(vec![sign_ingress_with_request_status_query(
                 auth,
-                genesis_token_canister_id(),
+                ingress_expiry,
+                network.genesis_token_canister_id(),
                 ROLE_NNS_GTC,
                 "claim_neurons",
                 sig,
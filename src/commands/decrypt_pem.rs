@@ -2,7 +2,8 @@ use std::path::PathBuf;
 
 use anyhow::bail;
 use clap::Parser;
-use sec1::LineEnding;
+use pkcs8::{PrivateKeyInfo, SecretDocument};
+use sec1::{pem::PemLabel, LineEnding};
 
 use crate::{
     lib::{AnyhowResult, AuthInfo},
@@ -17,16 +18,15 @@ pub struct DecryptPemOpts {
 }
 
 pub fn exec(auth: &AuthInfo, opts: DecryptPemOpts) -> AnyhowResult<()> {
-    let AuthInfo::K256Key(pk) = auth else {
-        bail!("--pem-file was not set to an encrypted PEM file")
-    };
-    // technically this permits an unencrypted secp256k1 key, which will just be re-encoded directly
+    // technically this permits an unencrypted key, which will just be re-encoded directly
     // but who cares
-    write_file(
-        &opts.output_path,
-        "PEM",
-        pk.to_sec1_pem(LineEnding::default())?.as_bytes(),
-    )?;
+    let pem = match auth {
+        AuthInfo::K256Key(pk) => pk.to_sec1_pem(LineEnding::default())?,
+        AuthInfo::Ed25519Key(pkcs8) => SecretDocument::try_from(&pkcs8[..])?
+            .to_pem(PrivateKeyInfo::PEM_LABEL, LineEnding::default())?,
+        _ => bail!("--pem-file was not set to an encrypted PEM file"),
+    };
+    write_file(&opts.output_path, "PEM", pem.as_bytes())?;
     eprintln!("Wrote PEM file to {}", opts.output_path.display());
     Ok(())
 }
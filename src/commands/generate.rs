@@ -1,14 +1,23 @@
 use crate::{
-    lib::{get_account_id, key_encryption_params, mnemonic_to_key, AnyhowResult},
+    lib::{
+        decrypt_seed_file, encrypt_pkcs8_pem, encrypt_seed_phrase, get_account_id,
+        mnemonic_to_ed25519_key, mnemonic_to_key, slip39, AnyhowResult, DEFAULT_SCRYPT_LOG_N,
+    },
     read_file,
 };
 use anyhow::{anyhow, bail, ensure, Context};
+use bip32::DerivationPath;
 use bip39::{Language, Mnemonic};
 use clap::{Parser, ValueEnum};
 use dialoguer::{Password, PasswordValidator};
-use ic_agent::{identity::Secp256k1Identity, Identity};
-use pkcs8::{EncodePrivateKey, EncryptedPrivateKeyInfo, PrivateKeyInfo};
-use rand::{rngs::OsRng, thread_rng, RngCore};
+use ic_agent::{
+    identity::{BasicIdentity, Secp256k1Identity},
+    Identity,
+};
+use k256::SecretKey;
+use pkcs8::{EncodePrivateKey, PrivateKeyInfo, SecretDocument};
+use rand::{rngs::OsRng, RngCore};
+use ring::signature::Ed25519KeyPair;
 use sec1::{pem::PemLabel, LineEnding};
 use std::{
     io::{stdin, IsTerminal},
@@ -23,7 +32,9 @@ pub struct GenerateOpts {
     #[arg(long, default_value = "12")]
     words: u32,
 
-    /// File to write the seed phrase to. If unspecified, it will be printed to the terminal.
+    /// File to write the seed phrase to. If unspecified, it will be printed to the terminal. Unless
+    /// --storage-mode=plaintext is used, the seed phrase is encrypted with the PEM password. SLIP-39
+    /// shares are written unencrypted, one per line.
     #[arg(long)]
     seed_file: Option<PathBuf>,
 
@@ -31,9 +42,16 @@ pub struct GenerateOpts {
     #[arg(long, default_value = "identity.pem")]
     pem_file: PathBuf,
 
-    /// A seed phrase in quotes to use to generate the PEM file.
+    /// A seed phrase in quotes to use to generate the PEM file. To recover from SLIP-39 shares,
+    /// pass this once for each share.
     #[arg(long)]
-    phrase: Option<String>,
+    phrase: Vec<String>,
+
+    /// Read the seed phrase to use to generate the PEM file from this file, such as a seed file
+    /// written by --seed-file. Encrypted seed files are decrypted with the --password-file password,
+    /// or a prompted one. SLIP-39 shares can be given one per line.
+    #[arg(long, conflicts_with = "phrase")]
+    phrase_file: Option<PathBuf>,
 
     /// Overwrite any existing seed file.
     #[arg(long)]
@@ -50,6 +68,35 @@ pub struct GenerateOpts {
     /// Read the encryption password from this file. Use "-" for STDIN. Required if STDIN is being piped.
     #[arg(long)]
     password_file: Option<PathBuf>,
+
+    /// The type of key to generate. Ed25519 keys are derived with SLIP-10, which hardens every
+    /// component of the derivation path.
+    #[arg(long, value_enum, default_value_t = KeyType::Secp256k1)]
+    key_type: KeyType,
+
+    /// Prompt for a BIP-39 passphrase (the "25th word"), which is combined with the seed phrase.
+    #[arg(long)]
+    passphrase: bool,
+
+    /// Read the BIP-39 passphrase from this file. Use "-" for STDIN.
+    #[arg(long, conflicts_with = "passphrase")]
+    passphrase_file: Option<PathBuf>,
+
+    /// Back up the seed as this many SLIP-39 shares instead of a single seed phrase.
+    #[arg(
+        long,
+        requires = "threshold",
+        value_parser = clap::value_parser!(u8).range(1..=16)
+    )]
+    shares: Option<u8>,
+
+    /// The number of SLIP-39 shares needed to recover the seed.
+    #[arg(
+        long,
+        requires = "shares",
+        value_parser = clap::value_parser!(u8).range(1..=16)
+    )]
+    threshold: Option<u8>,
 }
 
 #[derive(Debug, ValueEnum, Clone, Copy, PartialEq, Eq)]
@@ -58,8 +105,15 @@ enum StorageMode {
     Plaintext,
 }
 
-/// Generate or recover mnemonic seed phrase and/or PEM file.
-pub fn exec(opts: GenerateOpts) -> AnyhowResult {
+#[derive(Debug, ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum KeyType {
+    Secp256k1,
+    Ed25519,
+}
+
+/// Generate or recover mnemonic seed phrase and/or PEM file, with the key derived at
+/// `derivation_path`.
+pub fn exec(opts: GenerateOpts, derivation_path: &DerivationPath) -> AnyhowResult {
     if let Some(seed_file) = &opts.seed_file {
         if !opts.overwrite_seed_file && seed_file.exists() {
             bail!(
@@ -74,35 +128,146 @@ pub fn exec(opts: GenerateOpts) -> AnyhowResult {
             opts.pem_file.display()
         );
     }
+    // shares are written in plaintext, as they are meant to be handed out separately
+    if opts.shares.is_some()
+        && opts.seed_file.is_some()
+        && opts.storage_mode == StorageMode::PasswordProtected
+    {
+        bail!("--seed-file with --shares writes the shares unencrypted, so it requires --storage-mode plaintext. Leave out --seed-file to print the shares instead.");
+    }
     if opts.storage_mode == StorageMode::PasswordProtected
         && opts.password_file.is_none()
         && !stdin().is_terminal()
     {
         bail!("Must use --password-file if using --storage-mode=password-protected and stdin cannot receive terminal input.");
     }
+    if opts.passphrase && !stdin().is_terminal() {
+        bail!("Must use --passphrase-file if stdin cannot receive terminal input.");
+    }
     let bytes = match opts.words {
         12 => 16,
         24 => 32,
         _ => return Err(anyhow!("Words must be 12 or 24.")),
     };
-    let mnemonic = match opts.phrase {
-        Some(phrase) => {
-            Mnemonic::from_phrase(&phrase, Language::English).context("Failed to parse mnemonic")?
+    // read once, as it may be needed to decrypt the seed file as well as to encrypt the PEM file
+    let password_file = match &opts.password_file {
+        Some(password_file) => Some(read_file(password_file, "password")?),
+        None => None,
+    };
+    let phrases = if let Some(phrase_file) = &opts.phrase_file {
+        let contents = read_file(phrase_file, "seed phrase")?;
+        let contents = decrypt_seed_file(contents, || {
+            match &password_file {
+            Some(password) => Ok(password.clone()),
+            None if stdin().is_terminal() => Ok(Password::new()
+                .with_prompt("Seed file decryption password")
+                .interact()?),
+            None => bail!("Must use --password-file if the seed file is encrypted and stdin cannot receive terminal input."),
         }
-        None => {
+        })?;
+        contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect()
+    } else {
+        opts.phrase.clone()
+    };
+    let mnemonic = match &phrases[..] {
+        [] => {
             let mut key = vec![0u8; bytes];
             OsRng.fill_bytes(&mut key);
             Mnemonic::from_entropy(&key, Language::English).unwrap()
         }
+        // SLIP-39 shares are 20 or 33 words, which no BIP-39 phrase is; a single share is a
+        // whole 1-of-1 set
+        [phrase] if !matches!(phrase.split_whitespace().count(), 20 | 33) => {
+            Mnemonic::from_phrase(phrase, Language::English).context("Failed to parse mnemonic")?
+        }
+        shares => {
+            let entropy =
+                slip39::combine(shares, b"").context("Failed to recover seed from shares")?;
+            Mnemonic::from_entropy(&entropy, Language::English)
+                .context("Recovered seed is not a valid length for a seed phrase")?
+        }
+    };
+    let passphrase = if let Some(passphrase_file) = &opts.passphrase_file {
+        let content = read_file(passphrase_file, "passphrase")?;
+        content.trim_end_matches(['\r', '\n']).to_string()
+    } else if opts.passphrase {
+        Password::new()
+            .with_prompt("BIP-39 passphrase")
+            .with_confirmation("Re-enter passphrase", "Passphrases did not match")
+            .interact()?
+    } else {
+        String::new()
+    };
+    let key = match opts.key_type {
+        KeyType::Secp256k1 => GeneratedKey::Secp256k1(
+            mnemonic_to_key(&mnemonic, &passphrase, derivation_path)
+                .context("Failed to convert mnemonic to PEM")?,
+        ),
+        KeyType::Ed25519 => GeneratedKey::Ed25519(
+            mnemonic_to_ed25519_key(&mnemonic, &passphrase, derivation_path)
+                .context("Failed to convert mnemonic to PEM")?,
+        ),
+    };
+    let shares = match (opts.shares, opts.threshold) {
+        (Some(count), Some(threshold)) => {
+            // the BIP-39 entropy is shared, so that recovery rebuilds the same seed phrase
+            let shares = slip39::split(mnemonic.entropy(), threshold, count, b"")?;
+            Some((threshold, shares))
+        }
+        _ => None,
+    };
+    let password = match opts.storage_mode {
+        StorageMode::Plaintext => None,
+        StorageMode::PasswordProtected => Some(if let Some(content) = password_file {
+            QuillPasswordPolicy.validate(&content)?;
+            content
+        } else {
+            Password::new()
+                .with_prompt("PEM encryption password")
+                .with_confirmation("Re-enter password", "Passwords did not match")
+                .validate_with(QuillPasswordPolicy)
+                .interact()?
+        }),
     };
-    let key = mnemonic_to_key(&mnemonic).context("Failed to convert mnemonic to PEM")?;
     let phrase = mnemonic.into_phrase();
-    if let Some(seed_file) = opts.seed_file {
-        std::fs::write(&seed_file, phrase)?;
-        println!("Written seed file to {}.", seed_file.display());
-        println!("Copy the contents of this file to external media or a piece of paper and store it in a safe place.");
-        if opts.storage_mode != StorageMode::Plaintext {
-            println!("Be sure to delete the file afterwards, as it is not password-protected like the PEM file is.")
+    if let Some((threshold, shares)) = shares {
+        if let Some(seed_file) = &opts.seed_file {
+            std::fs::write(seed_file, shares.join("\n"))?;
+            println!(
+                "Written {} SLIP-39 shares to {}, one per line. Be sure to delete the file once they have been distributed.",
+                shares.len(),
+                seed_file.display()
+            );
+        } else {
+            for (i, share) in shares.iter().enumerate() {
+                println!("Share {}: {share}", i + 1);
+            }
+        }
+        println!(
+            "Any {threshold} of the {} shares can recover the key. Copy each onto a piece of paper or external media, and give it to a different person to store in a safe place.",
+            shares.len()
+        );
+    } else if let Some(seed_file) = opts.seed_file {
+        if let Some(password) = &password {
+            let encrypted = encrypt_seed_phrase(&phrase, password, DEFAULT_SCRYPT_LOG_N)?;
+            std::fs::write(&seed_file, encrypted)?;
+            println!(
+                "Written seed file to {}, encrypted with the PEM password.",
+                seed_file.display()
+            );
+            println!(
+                "Copy this file to external media and store it in a safe place. The key can be recovered from it with `quill generate --phrase-file {}`.",
+                seed_file.display()
+            );
+        } else {
+            std::fs::write(&seed_file, phrase)?;
+            println!("Written seed file to {}.", seed_file.display());
+            println!("Copy the contents of this file to external media or a piece of paper and store it in a safe place.");
         }
     } else {
         println!(
@@ -111,36 +276,30 @@ Seed phrase: {phrase}
 Copy this onto a piece of paper or external media and store it in a safe place."
         );
     }
-    let pem = match opts.storage_mode {
-        StorageMode::Plaintext => key.to_sec1_pem(LineEnding::default())?,
-        StorageMode::PasswordProtected => {
-            let password = if let Some(password_file) = opts.password_file {
-                let content = read_file(password_file, "password")?;
-                QuillPasswordPolicy.validate(&content)?;
-                content
-            } else {
-                Password::new()
-                    .with_prompt("PEM encryption password")
-                    .with_confirmation("Re-enter password", "Passwords did not match")
-                    .validate_with(QuillPasswordPolicy)
-                    .interact()?
+    let pem = match password {
+        None => match &key {
+            GeneratedKey::Secp256k1(key) => key.to_sec1_pem(LineEnding::default())?,
+            GeneratedKey::Ed25519(pkcs8) => SecretDocument::try_from(&pkcs8[..])?
+                .to_pem(PrivateKeyInfo::PEM_LABEL, LineEnding::default())?,
+        },
+        Some(password) => {
+            let key_der = match &key {
+                GeneratedKey::Secp256k1(key) => key.to_pkcs8_der()?.as_bytes().to_vec(),
+                GeneratedKey::Ed25519(pkcs8) => pkcs8.clone(),
             };
-            let key_der = key.to_pkcs8_der()?;
-            let pki = PrivateKeyInfo::try_from(key_der.as_bytes())?;
-            let mut rng = thread_rng();
-            let mut salt = [0u8; 16];
-            rng.fill_bytes(&mut salt);
-            let mut iv = [0u8; 16];
-            rng.fill_bytes(&mut iv);
-            let doc = pki.encrypt_with_params(key_encryption_params(&salt, &iv), password)?;
-            doc.to_pem(EncryptedPrivateKeyInfo::PEM_LABEL, LineEnding::default())?
+            encrypt_pkcs8_pem(&key_der, &password, DEFAULT_SCRYPT_LOG_N)?
         }
     };
     std::fs::write(&opts.pem_file, &pem)?;
     println!("Written PEM file to {}.", opts.pem_file.display());
-    let principal_id = Secp256k1Identity::from_private_key(key)
-        .sender()
-        .map_err(|s| anyhow!(s))?;
+    let principal_id = match key {
+        GeneratedKey::Secp256k1(key) => Secp256k1Identity::from_private_key(key).sender(),
+        GeneratedKey::Ed25519(pkcs8) => BasicIdentity::from_key_pair(
+            Ed25519KeyPair::from_pkcs8(&pkcs8).expect("generated a valid Ed25519 key"),
+        )
+        .sender(),
+    }
+    .map_err(|s| anyhow!(s))?;
     let account_id = get_account_id(principal_id, None)?;
 
     println!("Principal id: {principal_id}");
@@ -148,7 +307,13 @@ Copy this onto a piece of paper or external media and store it in a safe place."
     Ok(())
 }
 
-struct QuillPasswordPolicy;
+enum GeneratedKey {
+    Secp256k1(SecretKey),
+    /// In PKCS#8 format.
+    Ed25519(Vec<u8>),
+}
+
+pub(crate) struct QuillPasswordPolicy;
 
 impl PasswordValidator for QuillPasswordPolicy {
     type Err = anyhow::Error;
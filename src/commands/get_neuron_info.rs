@@ -1,6 +1,6 @@
 use crate::{
     commands::{send::submit_unsigned_ingress, SendingOpts},
-    lib::{governance_canister_id, AnyhowResult, ROLE_NNS_GOVERNANCE},
+    lib::{network::Network, AnyhowResult, ROLE_NNS_GOVERNANCE},
 };
 use candid::Encode;
 use clap::Parser;
@@ -17,15 +17,15 @@ pub struct GetNeuronInfoOpts {
 
 // We currently only support a subset of the functionality.
 #[tokio::main]
-pub async fn exec(opts: GetNeuronInfoOpts, fetch_root_key: bool) -> AnyhowResult {
+pub async fn exec(opts: GetNeuronInfoOpts, network: &Network) -> AnyhowResult {
     let args = Encode!(&opts.ident)?;
     submit_unsigned_ingress(
-        governance_canister_id(),
+        network.governance_canister_id(),
         ROLE_NNS_GOVERNANCE,
         "get_neuron_info",
         args,
         opts.sending_opts,
-        fetch_root_key,
+        network,
     )
     .await
 }
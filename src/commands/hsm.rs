@@ -0,0 +1,230 @@
+//! Key management for PKCS#11 hardware security modules, using the same module as `--hsm`.
+
+use std::ptr;
+
+use anyhow::{bail, ensure, Context};
+use candid::Principal;
+use clap::{Parser, Subcommand};
+use pkcs11::{
+    types::{
+        CKA_CLASS, CKA_EC_PARAMS, CKA_EC_POINT, CKA_EXTRACTABLE, CKA_ID, CKA_KEY_TYPE, CKA_LABEL,
+        CKA_PRIVATE, CKA_SENSITIVE, CKA_SIGN, CKA_TOKEN, CKA_VERIFY, CKF_RW_SESSION,
+        CKF_SERIAL_SESSION, CKK_EC, CKM_EC_KEY_PAIR_GEN, CKO_PUBLIC_KEY, CKR_OK, CKU_USER,
+        CK_ATTRIBUTE, CK_ATTRIBUTE_TYPE, CK_FALSE, CK_MECHANISM, CK_OBJECT_HANDLE,
+        CK_SESSION_HANDLE, CK_SLOT_ID, CK_TRUE,
+    },
+    Ctx,
+};
+use pkcs8::{
+    der::{asn1::OctetStringRef, Decode},
+    EncodePublicKey,
+};
+
+use crate::lib::{get_account_id, AnyhowResult, AuthInfo, HSMInfo};
+
+/// The DER-encoded OID of the P-256 curve, the only one the IC supports for HSM keys.
+const EC_PARAMS_P256: &[u8] = &[0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+
+/// Commands for managing keys on a PKCS#11 hardware security module, such as a Nitrokey HSM.
+/// They use the module from --hsm-libpath and the slot from --hsm-slot.
+#[derive(Subcommand)]
+pub enum HsmCommand {
+    /// Lists the slots that have a token present, with the index to pass to --hsm-slot.
+    ListSlots,
+    /// Lists the P-256 keys in the slot, with their principals and account IDs.
+    ListKeys,
+    GenerateKey(GenerateKeyOpts),
+}
+
+/// Generates a new P-256 key pair in the slot, with the ID from --hsm-id.
+#[derive(Parser)]
+pub struct GenerateKeyOpts {
+    /// The label to give the new key.
+    #[arg(long, default_value = "quill")]
+    label: String,
+}
+
+pub fn dispatch(auth: &AuthInfo, command: HsmCommand) -> AnyhowResult {
+    let new_info;
+    let info = match auth {
+        AuthInfo::Pkcs11Hsm(info) => info,
+        AuthInfo::NoAuth => {
+            new_info = HSMInfo::new()?;
+            &new_info
+        }
+        _ => bail!("`quill hsm` commands can only be used with the HSM flags"),
+    };
+    let ctx = Ctx::new_and_initialize(&info.libpath)
+        .with_context(|| format!("Couldn't load PKCS#11 module {}", info.libpath.display()))?;
+    match command {
+        HsmCommand::ListSlots => list_slots(&ctx),
+        HsmCommand::ListKeys => list_keys(&ctx, info),
+        HsmCommand::GenerateKey(opts) => generate_key(&ctx, info, opts),
+    }
+}
+
+fn list_slots(ctx: &Ctx) -> AnyhowResult {
+    let slots = ctx.get_slot_list(true)?;
+    if slots.is_empty() {
+        println!("No slots with a token present");
+    }
+    for (index, slot) in slots.into_iter().enumerate() {
+        let slot_info = ctx.get_slot_info(slot)?;
+        let token_info = ctx.get_token_info(slot)?;
+        println!(
+            "Slot {index}: {} ({})",
+            padded_string(&token_info.label),
+            padded_string(&slot_info.slotDescription),
+        );
+    }
+    Ok(())
+}
+
+fn list_keys(ctx: &Ctx, info: &HSMInfo) -> AnyhowResult {
+    let session = ctx.open_session(slot_id(ctx, info)?, CKF_SERIAL_SESSION, None, None)?;
+    let template = vec![
+        CK_ATTRIBUTE::new(CKA_CLASS).with_ck_ulong(&CKO_PUBLIC_KEY),
+        CK_ATTRIBUTE::new(CKA_KEY_TYPE).with_ck_ulong(&CKK_EC),
+    ];
+    let keys = find_objects(ctx, session, &template)?;
+    if keys.is_empty() {
+        println!("No EC keys in slot {}", info.slot);
+    }
+    for (i, key) in keys.into_iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!(
+            "Key ID: {}",
+            hex::encode(get_attribute(ctx, session, key, CKA_ID)?)
+        );
+        println!(
+            "Label: {}",
+            String::from_utf8_lossy(&get_attribute(ctx, session, key, CKA_LABEL)?)
+        );
+        if get_attribute(ctx, session, key, CKA_EC_PARAMS)? != EC_PARAMS_P256 {
+            println!("Unsupported curve, only P-256 keys can be used with quill");
+            continue;
+        }
+        print_ids(&get_attribute(ctx, session, key, CKA_EC_POINT)?)?;
+    }
+    ctx.close_session(session)?;
+    Ok(())
+}
+
+fn generate_key(ctx: &Ctx, info: &HSMInfo, opts: GenerateKeyOpts) -> AnyhowResult {
+    let id = hex::decode(&info.ident).context("--hsm-id must be hex-encoded")?;
+    let session = ctx.open_session(
+        slot_id(ctx, info)?,
+        CKF_SERIAL_SESSION | CKF_RW_SESSION,
+        None,
+        None,
+    )?;
+    let pin = info.pin().map_err(anyhow::Error::msg)?;
+    ctx.login(session, CKU_USER, Some(pin.as_str()))
+        .context("Couldn't log in to the HSM. Is the PIN correct?")?;
+    let existing = find_objects(ctx, session, &[CK_ATTRIBUTE::new(CKA_ID).with_bytes(&id)])?;
+    ensure!(
+        existing.is_empty(),
+        "Slot {} already has a key with ID {}",
+        info.slot,
+        info.ident
+    );
+    let mechanism = CK_MECHANISM {
+        mechanism: CKM_EC_KEY_PAIR_GEN,
+        pParameter: ptr::null_mut(),
+        ulParameterLen: 0,
+    };
+    let public_template = vec![
+        CK_ATTRIBUTE::new(CKA_TOKEN).with_bool(&CK_TRUE),
+        CK_ATTRIBUTE::new(CKA_VERIFY).with_bool(&CK_TRUE),
+        CK_ATTRIBUTE::new(CKA_EC_PARAMS).with_bytes(EC_PARAMS_P256),
+        CK_ATTRIBUTE::new(CKA_ID).with_bytes(&id),
+        CK_ATTRIBUTE::new(CKA_LABEL).with_bytes(opts.label.as_bytes()),
+    ];
+    let private_template = vec![
+        CK_ATTRIBUTE::new(CKA_TOKEN).with_bool(&CK_TRUE),
+        CK_ATTRIBUTE::new(CKA_PRIVATE).with_bool(&CK_TRUE),
+        CK_ATTRIBUTE::new(CKA_SENSITIVE).with_bool(&CK_TRUE),
+        CK_ATTRIBUTE::new(CKA_EXTRACTABLE).with_bool(&CK_FALSE),
+        CK_ATTRIBUTE::new(CKA_SIGN).with_bool(&CK_TRUE),
+        CK_ATTRIBUTE::new(CKA_ID).with_bytes(&id),
+        CK_ATTRIBUTE::new(CKA_LABEL).with_bytes(opts.label.as_bytes()),
+    ];
+    let (public_key, _) = ctx
+        .generate_key_pair(session, &mechanism, &public_template, &private_template)
+        .context("Couldn't generate a key pair")?;
+    eprintln!("Generated key {} in slot {}", info.ident, info.slot);
+    print_ids(&get_attribute(ctx, session, public_key, CKA_EC_POINT)?)?;
+    ctx.logout(session)?;
+    ctx.close_session(session)?;
+    Ok(())
+}
+
+/// Looks up the slot the same way `--hsm` does, by its index among slots with a token present.
+fn slot_id(ctx: &Ctx, info: &HSMInfo) -> AnyhowResult<CK_SLOT_ID> {
+    let slots = ctx.get_slot_list(true)?;
+    slots.get(info.slot).copied().with_context(|| {
+        format!(
+            "No slot {} among the {} slots with a token present. Use `quill hsm list-slots` to list them.",
+            info.slot,
+            slots.len()
+        )
+    })
+}
+
+fn find_objects(
+    ctx: &Ctx,
+    session: CK_SESSION_HANDLE,
+    template: &[CK_ATTRIBUTE],
+) -> AnyhowResult<Vec<CK_OBJECT_HANDLE>> {
+    ctx.find_objects_init(session, template)?;
+    let mut objects = vec![];
+    loop {
+        let batch = ctx.find_objects(session, 16)?;
+        if batch.is_empty() {
+            break;
+        }
+        objects.extend(batch);
+    }
+    ctx.find_objects_final(session)?;
+    Ok(objects)
+}
+
+fn get_attribute(
+    ctx: &Ctx,
+    session: CK_SESSION_HANDLE,
+    object: CK_OBJECT_HANDLE,
+    attribute_type: CK_ATTRIBUTE_TYPE,
+) -> AnyhowResult<Vec<u8>> {
+    // the first call only fills in the length
+    let mut template = vec![CK_ATTRIBUTE::new(attribute_type)];
+    let (rv, attributes) = ctx.get_attribute_value(session, object, &mut template)?;
+    ensure!(
+        rv == CKR_OK,
+        "Couldn't read key attribute {attribute_type:#x}"
+    );
+    let mut value = vec![0; attributes[0].ulValueLen as usize];
+    let mut template = vec![CK_ATTRIBUTE::new(attribute_type).with_bytes(value.as_mut_slice())];
+    ctx.get_attribute_value(session, object, &mut template)?;
+    Ok(value)
+}
+
+/// Prints the principal and account ID of a P-256 public key, given its `CKA_EC_POINT`.
+fn print_ids(ec_point: &[u8]) -> AnyhowResult {
+    // the point should be wrapped in a DER OCTET STRING, but some modules return it bare
+    let point = OctetStringRef::from_der(ec_point)
+        .map(|octets| octets.as_bytes())
+        .unwrap_or(ec_point);
+    let public_key = p256::PublicKey::from_sec1_bytes(point).context("Invalid P-256 public key")?;
+    let principal_id = Principal::self_authenticating(public_key.to_public_key_der()?.as_bytes());
+    let account_id = get_account_id(principal_id, None)?;
+    println!("Principal id: {principal_id}");
+    println!("Legacy account id: {account_id}");
+    Ok(())
+}
+
+/// Decodes a blank-padded PKCS#11 string.
+fn padded_string(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).trim_end().to_string()
+}
@@ -0,0 +1,94 @@
+use anyhow::Context;
+use candid::{CandidType, Decode, Encode, Principal};
+use clap::{Parser, Subcommand};
+use ic_agent::Agent;
+use serde::de::DeserializeOwned;
+
+use crate::lib::{
+    format::icrc1::Token, get_agent, network::Network, qr::QrOutputOpts, AnyhowResult, AuthInfo,
+    IngressExpiryOpts,
+};
+
+use super::print_vec;
+
+mod balance;
+mod metadata;
+mod transfer;
+
+/// Commands for any ICRC-1 token, given the canister ID of its ledger.
+///
+/// Amounts are in whole tokens, using the number of decimals the ledger reports.
+#[derive(Parser)]
+pub struct Icrc1Opts {
+    /// The canister ID of the token's ledger.
+    #[arg(long, global = true, help_heading = "COMMON")]
+    ledger_canister_id: Option<Principal>,
+    #[command(subcommand)]
+    subcommand: Icrc1Command,
+}
+
+#[derive(Subcommand)]
+pub enum Icrc1Command {
+    Balance(balance::BalanceOpts),
+    Metadata(metadata::MetadataOpts),
+    Transfer(transfer::TransferOpts),
+}
+
+pub fn dispatch(
+    auth: &AuthInfo,
+    opts: Icrc1Opts,
+    ingress_expiry: IngressExpiryOpts,
+    qr: &QrOutputOpts,
+    network: &Network,
+) -> AnyhowResult {
+    let ledger_canister_id = opts.ledger_canister_id.context(
+        "The token's ledger must be specified with `--ledger-canister-id <canister-id>`",
+    )?;
+    match opts.subcommand {
+        Icrc1Command::Balance(opts) => {
+            balance::exec(auth, ledger_canister_id, opts, network)?;
+        }
+        Icrc1Command::Metadata(opts) => {
+            metadata::exec(ledger_canister_id, opts, network)?;
+        }
+        Icrc1Command::Transfer(opts) => {
+            let out = transfer::exec(auth, ledger_canister_id, opts, ingress_expiry, network)?;
+            print_vec(qr, &out)?;
+        }
+    }
+    Ok(())
+}
+
+pub(super) async fn anonymous_agent(network: &Network) -> AnyhowResult<Agent> {
+    let agent = get_agent(&AuthInfo::NoAuth, network)?;
+    if network.fetch_root_key {
+        agent.fetch_root_key().await?;
+    }
+    Ok(agent)
+}
+
+/// Queries a ledger method that takes no arguments, such as `icrc1_decimals`.
+pub(super) async fn query_ledger<T: CandidType + DeserializeOwned>(
+    agent: &Agent,
+    ledger_canister_id: Principal,
+    method_name: &str,
+) -> AnyhowResult<T> {
+    let blob = agent
+        .query(&ledger_canister_id, method_name)
+        .with_arg(Encode!()?)
+        .call()
+        .await
+        .with_context(|| format!("Failed to query {method_name} from {ledger_canister_id}"))?;
+    Ok(Decode!(&blob, T)?)
+}
+
+/// Reads the decimals and symbol of a ledger's token.
+pub(super) async fn fetch_token(
+    agent: &Agent,
+    ledger_canister_id: Principal,
+) -> AnyhowResult<Token> {
+    Ok(Token {
+        decimals: query_ledger(agent, ledger_canister_id, "icrc1_decimals").await?,
+        symbol: query_ledger(agent, ledger_canister_id, "icrc1_symbol").await?,
+    })
+}
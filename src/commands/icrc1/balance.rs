@@ -0,0 +1,55 @@
+use candid::{Encode, Principal};
+use clap::Parser;
+
+use crate::{
+    commands::{get_account, send::submit_unsigned_ingress, SendingOpts},
+    lib::{
+        network::Network, AnyhowResult, AuthInfo, ParsedAccount, ParsedSubaccount,
+        ROLE_ICRC1_LEDGER,
+    },
+    AUTH_FLAGS,
+};
+
+use super::{anonymous_agent, fetch_token};
+
+/// Sends a message to check the token balance of an account.
+///
+/// The `--of` parameter is required if a signing key is not provided.
+#[derive(Parser)]
+pub struct BalanceOpts {
+    /// The account to check. Optional if a key is used.
+    #[arg(long, required_unless_present_any = AUTH_FLAGS)]
+    of: Option<ParsedAccount>,
+
+    /// The subaccount of the account to check.
+    #[arg(long)]
+    of_subaccount: Option<ParsedSubaccount>,
+
+    #[command(flatten)]
+    sending_opts: SendingOpts,
+}
+
+#[tokio::main]
+pub async fn exec(
+    auth: &AuthInfo,
+    ledger_canister_id: Principal,
+    opts: BalanceOpts,
+    network: &Network,
+) -> AnyhowResult {
+    let account = get_account(Some(auth), opts.of, opts.of_subaccount)?;
+    let mut sending_opts = opts.sending_opts;
+    if !sending_opts.dry_run {
+        // the balance is displayed in the ledger's own token
+        let agent = anonymous_agent(network).await?;
+        sending_opts.display.token = Some(fetch_token(&agent, ledger_canister_id).await?);
+    }
+    submit_unsigned_ingress(
+        ledger_canister_id,
+        ROLE_ICRC1_LEDGER,
+        "icrc1_balance_of",
+        Encode!(&account)?,
+        sending_opts,
+        network,
+    )
+    .await
+}
@@ -0,0 +1,32 @@
+use candid::{Encode, Principal};
+use clap::Parser;
+
+use crate::{
+    commands::{send::submit_unsigned_ingress, SendingOpts},
+    lib::{network::Network, AnyhowResult, ROLE_ICRC1_LEDGER},
+};
+
+/// Sends a message to show a token's metadata, such as its name, symbol, decimals and fee.
+#[derive(Parser)]
+pub struct MetadataOpts {
+    #[command(flatten)]
+    sending_opts: SendingOpts,
+}
+
+#[tokio::main]
+pub async fn exec(
+    ledger_canister_id: Principal,
+    opts: MetadataOpts,
+    network: &Network,
+) -> AnyhowResult {
+    submit_unsigned_ingress(
+        ledger_canister_id,
+        ROLE_ICRC1_LEDGER,
+        "icrc1_metadata",
+        Encode!()?,
+        opts.sending_opts,
+        network,
+    )
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use bigdecimal::BigDecimal;
+use candid::{Encode, Principal};
+use clap::Parser;
+use icrc_ledger_types::icrc1::transfer::{Memo, TransferArg};
+
+use crate::{
+    commands::get_account,
+    lib::{
+        network::Network,
+        now_nanos,
+        signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
+        tokens_to_units, AnyhowResult, AuthInfo, IngressExpiryOpts, ParsedAccount,
+        ParsedSubaccount, ROLE_ICRC1_LEDGER,
+    },
+};
+
+use super::{anonymous_agent, query_ledger};
+
+/// Signs a message to transfer tokens from one account to another.
+///
+/// Unless `--decimals` is passed, the ledger is first queried for the token's decimals, which
+/// requires network access.
+#[derive(Parser)]
+pub struct TransferOpts {
+    /// The account to transfer tokens to.
+    to: ParsedAccount,
+
+    /// The subaccount to transfer tokens to.
+    #[arg(long)]
+    to_subaccount: Option<ParsedSubaccount>,
+
+    /// The subaccount to transfer tokens from.
+    #[arg(long)]
+    from_subaccount: Option<ParsedSubaccount>,
+
+    /// The amount of tokens to transfer.
+    #[arg(long)]
+    amount: BigDecimal,
+
+    /// The fee, in tokens, expected by the ledger. Defaults to the ledger's current fee.
+    #[arg(long)]
+    fee: Option<BigDecimal>,
+
+    /// An integer memo for this transaction.
+    #[arg(long)]
+    memo: Option<u64>,
+
+    /// The number of decimals of the token, as reported by `quill icrc1 metadata`. Allows
+    /// signing on an air-gapped machine.
+    #[arg(long)]
+    decimals: Option<u8>,
+}
+
+#[tokio::main]
+pub async fn exec(
+    auth: &AuthInfo,
+    ledger_canister_id: Principal,
+    opts: TransferOpts,
+    ingress_expiry: IngressExpiryOpts,
+    network: &Network,
+) -> AnyhowResult<Vec<IngressWithRequestId>> {
+    let decimals = if let Some(decimals) = opts.decimals {
+        decimals
+    } else {
+        let agent = anonymous_agent(network).await?;
+        query_ledger(&agent, ledger_canister_id, "icrc1_decimals").await?
+    };
+    let to = get_account(None, Some(opts.to), opts.to_subaccount)?;
+    let amount = tokens_to_units(&opts.amount, decimals)?;
+    let fee = opts
+        .fee
+        .map(|fee| tokens_to_units(&fee, decimals))
+        .transpose()?;
+    let args = TransferArg {
+        amount,
+        created_at_time: Some(now_nanos()),
+        fee,
+        from_subaccount: opts.from_subaccount.map(|x| x.0 .0),
+        to,
+        memo: opts.memo.map(Memo::from),
+    };
+    let message = sign_ingress_with_request_status_query(
+        auth,
+        ingress_expiry,
+        ledger_canister_id,
+        ROLE_ICRC1_LEDGER,
+        "icrc1_transfer",
+        Encode!(&args)?,
+    )?;
+    Ok(vec![message])
+}
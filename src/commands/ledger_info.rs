@@ -0,0 +1,17 @@
+use crate::lib::{default_derivation_path, ledger::LedgerIdentity, AnyhowResult, AuthInfo};
+
+/// Prints the version of the IC app.
+pub fn exec(auth: &AuthInfo) -> AnyhowResult {
+    let speculos = match auth {
+        AuthInfo::Ledger { speculos, .. } => speculos.as_deref(),
+        _ => None,
+    };
+    let version = LedgerIdentity::new(default_derivation_path(), speculos)?.version()?;
+    println!("IC app version: {version}");
+    // quill does not know which release of the IC app added each kind of message
+    println!(
+        "The messages this version can sign are listed in its release notes: \
+        https://github.com/Zondax/ledger-icp/releases"
+    );
+    Ok(())
+}
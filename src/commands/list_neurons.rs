@@ -1,7 +1,7 @@
 use crate::lib::{
-    governance_canister_id,
+    network::Network,
     signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
-    AnyhowResult, AuthInfo, ROLE_NNS_GOVERNANCE,
+    AnyhowResult, AuthInfo, IngressExpiryOpts, ROLE_NNS_GOVERNANCE,
 };
 use candid::{CandidType, Encode};
 use clap::Parser;
@@ -10,6 +10,8 @@ use clap::Parser;
 pub struct ListNeurons {
     pub neuron_ids: Vec<u64>,
     pub include_neurons_readable_by_caller: bool,
+    pub page_number: Option<u64>,
+    pub page_size: Option<u64>,
 }
 
 /// Signs the query for all neurons belonging to the signing principal.
@@ -24,14 +26,22 @@ pub struct ListNeuronsOpts {
 }
 
 // We currently only support a subset of the functionality.
-pub fn exec(auth: &AuthInfo, opts: ListNeuronsOpts) -> AnyhowResult<Vec<IngressWithRequestId>> {
+pub fn exec(
+    auth: &AuthInfo,
+    opts: ListNeuronsOpts,
+    ingress_expiry: IngressExpiryOpts,
+    network: &Network,
+) -> AnyhowResult<Vec<IngressWithRequestId>> {
     let args = Encode!(&ListNeurons {
         neuron_ids: opts.neuron_id.clone(),
         include_neurons_readable_by_caller: opts.neuron_id.is_empty(),
+        page_number: None,
+        page_size: None,
     })?;
     Ok(vec![sign_ingress_with_request_status_query(
         auth,
-        governance_canister_id(),
+        ingress_expiry,
+        network.governance_canister_id(),
         ROLE_NNS_GOVERNANCE,
         "list_neurons",
         args,
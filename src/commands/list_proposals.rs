@@ -1,6 +1,6 @@
 use crate::{
     commands::{send::submit_unsigned_ingress, SendingOpts},
-    lib::{governance_canister_id, AnyhowResult, ROLE_NNS_GOVERNANCE},
+    lib::{network::Network, AnyhowResult, ROLE_NNS_GOVERNANCE},
 };
 use candid::Encode;
 use clap::Parser;
@@ -19,7 +19,7 @@ pub struct ListProposalsOpts {
 
 // We currently only support a subset of the functionality.
 #[tokio::main]
-pub async fn exec(opts: ListProposalsOpts, fetch_root_key: bool) -> AnyhowResult {
+pub async fn exec(opts: ListProposalsOpts, network: &Network) -> AnyhowResult {
     let args = Encode!(&ListProposalInfoRequest {
         limit: opts.limit.unwrap_or(100),
         before_proposal: None,
@@ -31,12 +31,12 @@ pub async fn exec(opts: ListProposalsOpts, fetch_root_key: bool) -> AnyhowResult
         return_self_describing_action: None,
     })?;
     submit_unsigned_ingress(
-        governance_canister_id(),
+        network.governance_canister_id(),
         ROLE_NNS_GOVERNANCE,
         "list_proposals",
         args,
         opts.sending_opts,
-        fetch_root_key,
+        network,
     )
     .await
 }
@@ -1,132 +1,190 @@
 //! This module implements the command-line API.
 
-use crate::lib::{get_principal, AnyhowResult, AuthInfo, ParsedAccount, ParsedSubaccount};
+use crate::lib::{
+    get_principal, network::Network, parse_duration, qr, AnyhowResult, AuthInfo, DisplayOpts,
+    IngressExpiryOpts, ParsedAccount, ParsedSubaccount,
+};
 use anyhow::{bail, Context};
-use clap::{Args, Parser};
+use bip32::DerivationPath;
+use clap::{Args, Parser, ValueEnum};
 use icrc_ledger_types::icrc1::account::Account;
-use std::io::{self, Write};
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
 
 mod account_balance;
+mod account_history;
+mod allowance;
+mod approve;
 mod ckbtc;
+mod cketh;
 mod claim_neurons;
 mod decrypt_pem;
 mod generate;
 mod get_neuron_info;
 mod get_proposal_info;
+#[cfg(feature = "hsm")]
+mod hsm;
+mod icrc1;
+#[cfg(feature = "ledger")]
+mod ledger_info;
 mod list_neurons;
 mod list_proposals;
 mod neuron_manage;
+mod neuron_report;
 mod neuron_stake;
 mod public;
+mod qr_decode;
 mod qrcode;
+mod reencrypt_pem;
 mod replace_node_provider_id;
 mod request_status;
 mod send;
+mod sign;
 mod sns;
 mod transfer;
+mod transfer_from;
 mod update_node_provider;
+mod verify;
+
+/// The QR scanner dapp, which submits the messages it scans.
+const SCANNER_URL: &str = "https://p5deo-6aaaa-aaaab-aaaxq-cai.raw.ic0.app/";
 
 #[derive(Parser)]
 pub enum Command {
     PublicIds(public::PublicOpts),
     Send(send::SendOpts),
+    Verify(verify::VerifyOpts),
+    Sign(sign::SignOpts),
     Transfer(transfer::TransferOpts),
+    Approve(approve::ApproveOpts),
+    Allowance(allowance::AllowanceOpts),
+    TransferFrom(transfer_from::TransferFromOpts),
     ClaimNeurons(claim_neurons::ClaimNeuronOpts),
     NeuronStake(neuron_stake::StakeOpts),
     NeuronManage(neuron_manage::ManageOpts),
     ListNeurons(list_neurons::ListNeuronsOpts),
+    NeuronReport(neuron_report::NeuronReportOpts),
     ListProposals(list_proposals::ListProposalsOpts),
     GetProposalInfo(get_proposal_info::GetProposalInfoOpts),
     GetNeuronInfo(get_neuron_info::GetNeuronInfoOpts),
     AccountBalance(account_balance::AccountBalanceOpts),
+    AccountHistory(account_history::AccountHistoryOpts),
     UpdateNodeProvider(update_node_provider::UpdateNodeProviderOpts),
     ReplaceNodeProviderId(replace_node_provider_id::ReplaceNodeProviderIdOpts),
     #[command(subcommand)]
     Ckbtc(ckbtc::CkbtcCommand),
+    #[command(subcommand)]
+    Cketh(cketh::CkethCommand),
+    Icrc1(icrc1::Icrc1Opts),
     Sns(sns::SnsOpts),
     Generate(generate::GenerateOpts),
     DecryptPem(decrypt_pem::DecryptPemOpts),
+    ReencryptPem(reencrypt_pem::ReencryptPemOpts),
+    #[cfg(feature = "hsm")]
+    #[command(subcommand)]
+    Hsm(hsm::HsmCommand),
+    /// Print the version of the IC app on the Ledger device.
+    #[cfg(feature = "ledger")]
+    LedgerInfo,
     /// Print QR Scanner dapp QR code: scan to start dapp to submit QR results.
     ScannerQRCode,
     QRCode(qrcode::QRCodeOpts),
+    QRDecode(qr_decode::QRDecodeOpts),
 }
 
-pub fn dispatch(auth: &AuthInfo, cmd: Command, fetch_root_key: bool, qr: bool) -> AnyhowResult {
+pub fn dispatch(
+    auth: &AuthInfo,
+    cmd: Command,
+    ingress_expiry: IngressExpiryOpts,
+    derivation_path: &DerivationPath,
+    network: &Network,
+    qr: &qr::QrOutputOpts,
+) -> AnyhowResult {
     match cmd {
-        Command::PublicIds(opts) => public::exec(auth, opts)?,
+        Command::PublicIds(opts) => public::exec(auth, opts, derivation_path)?,
         Command::Transfer(opts) => {
-            let out = transfer::exec(auth, opts)?;
+            let out = transfer::exec(auth, opts, ingress_expiry, network)?;
+            print_vec(qr, &out)?;
+        }
+        Command::Approve(opts) => {
+            let out = approve::exec(auth, opts, ingress_expiry, network)?;
+            print_vec(qr, &out)?;
+        }
+        Command::Allowance(opts) => {
+            allowance::exec(auth, opts, network)?;
+        }
+        Command::TransferFrom(opts) => {
+            let out = transfer_from::exec(auth, opts, ingress_expiry, network)?;
             print_vec(qr, &out)?;
         }
         Command::NeuronStake(opts) => {
-            let out = neuron_stake::exec(auth, opts)?;
+            let out = neuron_stake::exec(auth, opts, ingress_expiry, network)?;
             print_vec(qr, &out)?;
         }
         Command::NeuronManage(opts) => {
-            let out = neuron_manage::exec(auth, opts)?;
+            let out = neuron_manage::exec(auth, opts, ingress_expiry, network)?;
             print_vec(qr, &out)?;
         }
         Command::ListNeurons(opts) => {
-            let out = list_neurons::exec(auth, opts)?;
+            let out = list_neurons::exec(auth, opts, ingress_expiry, network)?;
             print_vec(qr, &out)?;
         }
+        Command::NeuronReport(_) => {
+            neuron_report::exec(auth, ingress_expiry, network)
+                .and_then(|out| print_vec(qr, &out))?;
+        }
         Command::ClaimNeurons(_) => {
-            claim_neurons::exec(auth).and_then(|out| print_vec(qr, &out))?;
+            claim_neurons::exec(auth, ingress_expiry, network)
+                .and_then(|out| print_vec(qr, &out))?;
         }
         Command::ListProposals(opts) => {
-            list_proposals::exec(opts, fetch_root_key)?;
+            list_proposals::exec(opts, network)?;
         }
         Command::GetProposalInfo(opts) => {
-            get_proposal_info::exec(opts, fetch_root_key)?;
+            get_proposal_info::exec(opts, network)?;
         }
         Command::GetNeuronInfo(opts) => {
-            get_neuron_info::exec(opts, fetch_root_key)?;
+            get_neuron_info::exec(opts, network)?;
         }
         Command::AccountBalance(opts) => {
-            account_balance::exec(auth, opts, fetch_root_key)?;
+            account_balance::exec(auth, opts, network)?;
+        }
+        Command::AccountHistory(opts) => {
+            account_history::exec(auth, opts, network)?;
         }
         Command::UpdateNodeProvider(opts) => {
-            let out = update_node_provider::exec(auth, opts)?;
+            let out = update_node_provider::exec(auth, opts, ingress_expiry, network)?;
             print(&out)?;
         }
         Command::ReplaceNodeProviderId(opts) => {
-            let out = replace_node_provider_id::exec(auth, opts)?;
+            let out = replace_node_provider_id::exec(auth, opts, ingress_expiry, network)?;
             print(&out)?;
         }
         Command::Send(opts) => {
-            send::exec(opts, fetch_root_key)?;
+            send::exec(opts, network)?;
         }
-        Command::Generate(opts) => generate::exec(opts)?,
+        Command::Verify(opts) => verify::exec(opts)?,
+        Command::Sign(opts) => {
+            let out = sign::exec(auth, opts)?;
+            print_vec(qr, &out)?;
+        }
+        Command::Generate(opts) => generate::exec(opts, derivation_path)?,
         Command::DecryptPem(opts) => decrypt_pem::exec(auth, opts)?,
-        Command::Ckbtc(subcmd) => ckbtc::dispatch(auth, subcmd, qr, fetch_root_key)?,
-        Command::Sns(opts) => sns::dispatch(auth, opts, qr, fetch_root_key)?,
-        // QR code for URL: https://p5deo-6aaaa-aaaab-aaaxq-cai.raw.ic0.app/
+        Command::ReencryptPem(opts) => reencrypt_pem::exec(auth, opts)?,
+        #[cfg(feature = "hsm")]
+        Command::Hsm(subcmd) => hsm::dispatch(auth, subcmd)?,
+        #[cfg(feature = "ledger")]
+        Command::LedgerInfo => ledger_info::exec(auth)?,
+        Command::Ckbtc(subcmd) => ckbtc::dispatch(auth, subcmd, ingress_expiry, qr, network)?,
+        Command::Cketh(subcmd) => cketh::dispatch(auth, subcmd, ingress_expiry, qr, network)?,
+        Command::Icrc1(opts) => icrc1::dispatch(auth, opts, ingress_expiry, qr, network)?,
+        Command::Sns(opts) => sns::dispatch(auth, opts, ingress_expiry, qr, network)?,
         // Source code: https://github.com/ninegua/ic-qr-scanner
-        Command::ScannerQRCode => {
-            println!(
-                "\
-█████████████████████████████████████
-█████████████████████████████████████
-████ ▄▄▄▄▄ █▀█ █▄▀▄▀▄█ ▄ █ ▄▄▄▄▄ ████
-████ █   █ █▀▀▀█ ▀▀█▄▀████ █   █ ████
-████ █▄▄▄█ █▀ █▀▀██▀▀█ ▄ █ █▄▄▄█ ████
-████▄▄▄▄▄▄▄█▄▀ ▀▄█ ▀▄█▄█▄█▄▄▄▄▄▄▄████
-████▄▄▄▄ ▀▄  ▄▀▄ ▄ █▀▄▀▀▀ ▀ ▀▄█▄▀████
-████▄█  █ ▄█▀█▄▀█▄  ▄▄ █ █   ▀█▀█████
-████▄▀ ▀ █▄▄▄ ▄   █▄▀   █ ▀▀▀▄▄█▀████
-████▄██▀▄▀▄▄ █▀█ ▄▄▄▄███▄█▄▀ ▄▄▀█████
-████ ▀▄▀▄█▄▀▄▄▄▀█ ▄▄▀▄▀▀▀▄▀▀▀▄ █▀████
-████ █▀██▀▄██▀▄█ █▀  █▄█▄▀▀  █▄▀█████
-████▄████▄▄▄  ▀▀█▄▄██▄▀█ ▄▄▄ ▀   ████
-████ ▄▄▄▄▄ █▄▄██▀▄▀ ▄█▄  █▄█ ▄▄▀█████
-████ █   █ █  █▀▄▄▀▄ ▄▀▀▄▄▄ ▄▀ ▄▀████
-████ █▄▄▄█ █ █▄▀▄██ ██▄█▀ ▄█  ▄ █████
-████▄▄▄▄▄▄▄█▄▄▄▄▄▄██▄▄█▄████▄▄▄██████
-█████████████████████████████████████
-█████████████████████████████████████"
-            );
-        }
-        Command::QRCode(opts) => qrcode::exec(opts)?,
+        Command::ScannerQRCode => qrcode::output_qr(SCANNER_URL, qr)?,
+        Command::QRCode(opts) => qrcode::exec(opts, qr)?,
+        Command::QRDecode(opts) => qr_decode::exec(opts)?,
     }
     Ok(())
 }
@@ -148,7 +206,7 @@ where
     Ok(())
 }
 
-fn print_qr<T>(arg: &T, pause: bool) -> AnyhowResult
+fn print_qr<T>(arg: &T, pause: bool, opts: &qr::QrOutputOpts) -> AnyhowResult
 where
     T: serde::ser::Serialize,
 {
@@ -157,7 +215,15 @@ where
     e.write_all(json.as_bytes()).unwrap();
     let json = e.finish().unwrap();
     let json = base64::encode(json);
-    qrcode::print_qr(json.as_str())?;
+    let frames = qr::split(&json, qrcode::fits(&json, opts.ecc));
+    if opts.format != qr::QrFormat::Terminal {
+        return qrcode::write_qr_files(&frames, opts);
+    }
+    if frames.len() > 1 {
+        // the animation already waits for Enter
+        return qrcode::print_qr_frames(&frames, opts.ecc);
+    }
+    qrcode::print_qr(json.as_str(), opts.ecc)?;
     if pause {
         let mut input_string = String::new();
         std::io::stdin()
@@ -167,13 +233,13 @@ where
     Ok(())
 }
 
-fn print_vec<T>(qr: bool, arg: &[T]) -> AnyhowResult
+fn print_vec<T>(qr: &qr::QrOutputOpts, arg: &[T]) -> AnyhowResult
 where
     T: serde::ser::Serialize,
 {
-    if qr {
+    if qr.enabled {
         for (i, a) in arg.iter().enumerate() {
-            print_qr(&a, i != arg.len() - 1).context("Failed to print QR code")?;
+            print_qr(&a, i != arg.len() - 1, qr).context("Failed to print QR code")?;
         }
         Ok(())
     } else {
@@ -216,4 +282,28 @@ pub struct SendingOpts {
     /// Always displays the response in IDL format.
     #[arg(long)]
     raw: bool,
+
+    /// Stops waiting for the response to an update call after this long, e.g. `90s` or `5m`. By
+    /// default, quill waits until there is a response.
+    #[arg(long, value_parser = parse_duration)]
+    timeout: Option<Duration>,
+
+    #[arg(from_global)]
+    output: OutputFormat,
+
+    #[arg(skip)]
+    display: DisplayOpts,
+}
+
+/// How responses from the IC are displayed.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable text
+    #[default]
+    Text,
+    /// JSON, with the messages describing progress written to stderr instead of stdout
+    Json,
+    /// CSV, for responses displayed as a table such as `quill send --neuron-report`, with the
+    /// messages describing progress written to stderr
+    Csv,
 }
@@ -1,8 +1,8 @@
 use crate::commands::transfer::parse_tokens;
 use crate::lib::{
-    governance_canister_id,
+    network::Network,
     signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
-    AnyhowResult, AuthInfo, ParsedNnsAccount, ROLE_NNS_GOVERNANCE,
+    AnyhowResult, AuthInfo, IngressExpiryOpts, ParsedNnsAccount, ROLE_NNS_GOVERNANCE,
 };
 use anyhow::{anyhow, bail, ensure, Context};
 use candid::{Encode, Principal};
@@ -159,7 +159,12 @@ enum NativeVisibility {
     Private = pb::Visibility::Private as isize,
 }
 
-pub fn exec(auth: &AuthInfo, opts: ManageOpts) -> AnyhowResult<Vec<IngressWithRequestId>> {
+pub fn exec(
+    auth: &AuthInfo,
+    opts: ManageOpts,
+    ingress_expiry: IngressExpiryOpts,
+    network: &Network,
+) -> AnyhowResult<Vec<IngressWithRequestId>> {
     if opts.ledger {
         ensure!(
             !opts.disburse_maturity && opts.disburse_maturity_to.is_none()
@@ -475,7 +480,8 @@ or --disburse-maturity-percentage flags with a Ledger device"
     for args in msgs {
         generated.push(sign_ingress_with_request_status_query(
             auth,
-            governance_canister_id(),
+            ingress_expiry,
+            network.governance_canister_id(),
             ROLE_NNS_GOVERNANCE,
             "manage_neuron",
             args,
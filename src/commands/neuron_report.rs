@@ -0,0 +1,46 @@
+use crate::{
+    commands::list_neurons::ListNeurons,
+    lib::{
+        network::Network,
+        signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
+        AnyhowResult, AuthInfo, IngressExpiryOpts, ROLE_NNS_GOVERNANCE,
+    },
+};
+use anyhow::bail;
+use candid::Encode;
+use clap::Parser;
+
+/// The most neurons the governance canister returns in one page.
+const PAGE_SIZE: u64 = 500;
+
+/// Signs the query for all neurons of the signing principal, which `quill send --neuron-report`
+/// displays as a summary with totals.
+///
+/// A hot key of the neurons is sufficient.
+#[derive(Parser)]
+pub struct NeuronReportOpts;
+
+pub fn exec(
+    auth: &AuthInfo,
+    ingress_expiry: IngressExpiryOpts,
+    network: &Network,
+) -> AnyhowResult<Vec<IngressWithRequestId>> {
+    if matches!(auth, AuthInfo::NoAuth) {
+        bail!("neuron-report requires a controller or hot key of the neurons, such as --pem-file");
+    }
+    // the report refuses to display a response that does not fit in this page
+    let args = Encode!(&ListNeurons {
+        neuron_ids: vec![],
+        include_neurons_readable_by_caller: true,
+        page_number: Some(0),
+        page_size: Some(PAGE_SIZE),
+    })?;
+    Ok(vec![sign_ingress_with_request_status_query(
+        auth,
+        ingress_expiry,
+        network.governance_canister_id(),
+        ROLE_NNS_GOVERNANCE,
+        "list_neurons",
+        args,
+    )?])
+}
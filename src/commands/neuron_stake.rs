@@ -1,9 +1,11 @@
 use crate::{
     commands::transfer::{self, parse_tokens},
     lib::{
-        get_principal, governance_canister_id,
+        get_principal,
+        network::Network,
         signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
-        AnyhowResult, AuthInfo, ParsedNnsAccount, ParsedSubaccount, ROLE_NNS_GOVERNANCE,
+        AnyhowResult, AuthInfo, IngressExpiryOpts, ParsedNnsAccount, ParsedSubaccount,
+        ROLE_NNS_GOVERNANCE,
     },
 };
 use anyhow::anyhow;
@@ -56,7 +58,12 @@ pub struct StakeOpts {
     ledger: bool,
 }
 
-pub fn exec(auth: &AuthInfo, opts: StakeOpts) -> AnyhowResult<Vec<IngressWithRequestId>> {
+pub fn exec(
+    auth: &AuthInfo,
+    opts: StakeOpts,
+    ingress_expiry: IngressExpiryOpts,
+    network: &Network,
+) -> AnyhowResult<Vec<IngressWithRequestId>> {
     let controller = crate::lib::get_principal(auth)?;
     let nonce = match (&opts.nonce, &opts.name) {
         (Some(nonce), _) => *nonce,
@@ -75,6 +82,8 @@ pub fn exec(auth: &AuthInfo, opts: StakeOpts) -> AnyhowResult<Vec<IngressWithReq
                 memo: Some(nonce),
                 from_subaccount: opts.from_subaccount,
             },
+            ingress_expiry,
+            network,
         )?
     } else {
         Vec::new()
@@ -91,7 +100,8 @@ pub fn exec(auth: &AuthInfo, opts: StakeOpts) -> AnyhowResult<Vec<IngressWithReq
     };
     messages.push(sign_ingress_with_request_status_query(
         &AuthInfo::NoAuth,
-        governance_canister_id(),
+        ingress_expiry,
+        network.governance_canister_id(),
         ROLE_NNS_GOVERNANCE,
         "manage_neuron",
         Encode!(&args)?,
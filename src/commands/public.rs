@@ -1,15 +1,31 @@
 #[cfg(feature = "ledger")]
 use crate::lib::ledger::LedgerIdentity;
-use crate::lib::{
-    get_account_id, get_principal, AnyhowResult, AuthInfo, ParsedAccount, ParsedSubaccount,
+use crate::{
+    commands::generate::KeyType,
+    lib::{
+        decrypt_seed_file, eth::EthAddress, get_account_id, get_principal, mnemonic_to_ed25519_key,
+        mnemonic_to_key, with_last_index, AnyhowResult, AuthInfo, ParsedAccount, ParsedSubaccount,
+    },
+    read_file,
 };
-use anyhow::{anyhow, bail};
+use anyhow::{anyhow, bail, ensure, Context};
+use bip32::DerivationPath;
+use bip39::{Language, Mnemonic};
 use candid::Principal;
 use clap::Parser;
+use dialoguer::Password;
+use ic_agent::{
+    identity::{BasicIdentity, Secp256k1Identity},
+    Identity,
+};
 use icp_ledger::AccountIdentifier;
 use icrc_ledger_types::icrc1::account::Account;
-use k256::{elliptic_curve::sec1::ToEncodedPoint, SecretKey};
-use sha3::{Digest, Keccak256};
+use k256::SecretKey;
+use ring::signature::Ed25519KeyPair;
+use std::{
+    io::{stdin, IsTerminal},
+    path::PathBuf,
+};
 
 #[derive(Parser)]
 /// Prints the principal and the account IDs.
@@ -27,11 +43,39 @@ pub struct PublicOpts {
     /// Print IDs for the provided subaccount.
     #[arg(long)]
     subaccount: Option<ParsedSubaccount>,
+    /// Derive the key from the seed phrase in this file (use "-" for STDIN), instead of using the
+    /// authentication flags. Seed files encrypted by `quill generate` are decrypted with a prompted
+    /// password.
+    #[arg(long, conflicts_with = "principal_id")]
+    phrase_file: Option<PathBuf>,
+    /// With --phrase-file, read the BIP-39 passphrase used with the seed phrase from this file.
+    #[arg(long, requires = "phrase_file")]
+    passphrase_file: Option<PathBuf>,
+    /// With --phrase-file, the type of key derived from the seed phrase, as passed to `quill generate`.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = KeyType::Secp256k1,
+        requires = "phrase_file"
+    )]
+    key_type: KeyType,
+    /// Print the IDs of this many consecutive keys, starting at the selected derivation path.
+    /// Requires --phrase-file or a Ledger device.
+    #[arg(long, conflicts_with_all = ["principal_id", "genesis_dfn", "display_on_ledger"])]
+    scan: Option<u32>,
 }
 
-/// Prints the account and the principal ids.
-pub fn exec(auth: &AuthInfo, opts: PublicOpts) -> AnyhowResult {
-    let (principal_id, account_id) = get_public_ids(auth, &opts)?;
+/// Prints the account and the principal ids. Keys are derived from seed phrases at
+/// `derivation_path`.
+pub fn exec(auth: &AuthInfo, opts: PublicOpts, derivation_path: &DerivationPath) -> AnyhowResult {
+    ensure!(
+        !opts.genesis_dfn || opts.key_type == KeyType::Secp256k1,
+        "DFN addresses can only be derived from secp256k1 keys"
+    );
+    if let Some(count) = opts.scan {
+        return scan(auth, &opts, count, derivation_path);
+    }
+    let (principal_id, account_id) = get_public_ids(auth, &opts, derivation_path)?;
     println!("Principal id: {principal_id}");
     println!("Legacy account id: {account_id}");
     if let Some(sub) = opts.subaccount {
@@ -44,15 +88,27 @@ pub fn exec(auth: &AuthInfo, opts: PublicOpts) -> AnyhowResult {
         );
     }
     if opts.genesis_dfn {
-        let AuthInfo::K256Key(pk) = auth else {
+        let pk = if opts.phrase_file.is_some() {
+            let (mnemonic, passphrase) = read_phrase(&opts)?;
+            mnemonic_to_key(&mnemonic, &passphrase, derivation_path)?
+        } else if let AuthInfo::K256Key(pk) = auth {
+            pk.clone()
+        } else {
             bail!("Must supply a pem file for the DFN address");
         };
-        println!("DFN address: {}", get_dfn(pk.clone())?);
+        println!("DFN address: {}", get_dfn(pk)?);
     }
     if opts.display_on_ledger {
         #[cfg(feature = "ledger")]
         {
-            LedgerIdentity::new()?.display_pk()?;
+            let AuthInfo::Ledger {
+                derivation_path,
+                speculos,
+            } = auth
+            else {
+                bail!("--display-on-ledger requires --ledger");
+            };
+            LedgerIdentity::new(derivation_path.clone(), speculos.as_deref())?.display_pk()?;
         }
         #[cfg(not(feature = "ledger"))]
         {
@@ -66,6 +122,7 @@ pub fn exec(auth: &AuthInfo, opts: PublicOpts) -> AnyhowResult {
 fn get_public_ids(
     auth: &AuthInfo,
     opts: &PublicOpts,
+    derivation_path: &DerivationPath,
 ) -> AnyhowResult<(Principal, AccountIdentifier)> {
     match &opts.principal_id {
         Some(principal_id) => {
@@ -75,6 +132,15 @@ fn get_public_ids(
                 get_account_id(principal_id, opts.subaccount.map(|x| x.0))?,
             ))
         }
+        None if opts.phrase_file.is_some() => {
+            let (mnemonic, passphrase) = read_phrase(opts)?;
+            let principal_id =
+                phrase_principal(&mnemonic, &passphrase, derivation_path, opts.key_type)?;
+            Ok((
+                principal_id,
+                get_account_id(principal_id, opts.subaccount.map(|x| x.0))?,
+            ))
+        }
         None => {
             if let AuthInfo::NoAuth = auth {
                 Err(anyhow!(
@@ -92,8 +158,96 @@ fn get_public_ids(
 }
 
 fn get_dfn(pk: SecretKey) -> AnyhowResult<String> {
-    let pubk = pk.public_key();
-    let uncompressed = pubk.to_encoded_point(false);
-    let hash = Keccak256::digest(&uncompressed.as_bytes()[1..]);
-    Ok(hex::encode(&hash[12..]))
+    let address = EthAddress::from_public_key(&pk.public_key());
+    Ok(hex::encode(address.0))
+}
+
+/// Prints the IDs of `count` keys, varying the last component of `start`.
+fn scan(auth: &AuthInfo, opts: &PublicOpts, count: u32, start: &DerivationPath) -> AnyhowResult {
+    let first = start.iter().last().map_or(0, |child| child.index());
+    let mnemonic = opts
+        .phrase_file
+        .as_ref()
+        .map(|_| read_phrase(opts))
+        .transpose()?;
+    for index in (first..).take(count as usize) {
+        let path = with_last_index(start, index)?;
+        let principal_id = match &mnemonic {
+            Some((mnemonic, passphrase)) => {
+                phrase_principal(mnemonic, passphrase, &path, opts.key_type)?
+            }
+            None => ledger_principal(auth, &path)?,
+        };
+        if index != first {
+            println!();
+        }
+        println!("Derivation path: {path}");
+        println!("Principal id: {principal_id}");
+        println!(
+            "Legacy account id: {}",
+            get_account_id(principal_id, opts.subaccount.map(|x| x.0))?
+        );
+    }
+    Ok(())
+}
+
+/// Reads the seed phrase and the BIP-39 passphrase, which is empty if unused.
+fn read_phrase(opts: &PublicOpts) -> AnyhowResult<(Mnemonic, String)> {
+    let file = opts
+        .phrase_file
+        .as_ref()
+        .expect("phrase_file needed for read_phrase");
+    let phrase = decrypt_seed_file(read_file(file, "seed phrase")?, || {
+        ensure!(
+            stdin().is_terminal(),
+            "Decrypting a seed file requires stdin to receive terminal input."
+        );
+        Ok(Password::new()
+            .with_prompt("Seed file decryption password")
+            .interact()?)
+    })?;
+    let mnemonic = Mnemonic::from_phrase(phrase.trim(), Language::English)
+        .context("Failed to parse mnemonic")?;
+    let passphrase = match &opts.passphrase_file {
+        Some(passphrase_file) => read_file(passphrase_file, "passphrase")?
+            .trim_end_matches(['\r', '\n'])
+            .to_string(),
+        None => String::new(),
+    };
+    Ok((mnemonic, passphrase))
+}
+
+fn phrase_principal(
+    mnemonic: &Mnemonic,
+    passphrase: &str,
+    path: &DerivationPath,
+    key_type: KeyType,
+) -> AnyhowResult<Principal> {
+    let identity: Box<dyn Identity> = match key_type {
+        KeyType::Secp256k1 => Box::new(Secp256k1Identity::from_private_key(mnemonic_to_key(
+            mnemonic, passphrase, path,
+        )?)),
+        KeyType::Ed25519 => {
+            let pkcs8 = mnemonic_to_ed25519_key(mnemonic, passphrase, path)?;
+            Box::new(BasicIdentity::from_key_pair(
+                Ed25519KeyPair::from_pkcs8_maybe_unchecked(&pkcs8).map_err(|e| anyhow!("{e}"))?,
+            ))
+        }
+    };
+    identity.sender().map_err(|e| anyhow!(e))
+}
+
+#[cfg(feature = "ledger")]
+fn ledger_principal(auth: &AuthInfo, path: &DerivationPath) -> AnyhowResult<Principal> {
+    let AuthInfo::Ledger { speculos, .. } = auth else {
+        bail!("--scan requires --phrase-file or --ledger, as a PEM file contains a single key");
+    };
+    Ok(LedgerIdentity::new(path.clone(), speculos.as_deref())?
+        .public_key()?
+        .0)
+}
+
+#[cfg(not(feature = "ledger"))]
+fn ledger_principal(_: &AuthInfo, _: &DerivationPath) -> AnyhowResult<Principal> {
+    bail!("--scan requires --phrase-file, as a PEM file contains a single key");
 }
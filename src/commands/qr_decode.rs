@@ -0,0 +1,36 @@
+use std::{io::Read, path::PathBuf};
+
+use anyhow::Context;
+use clap::Parser;
+use serde_json::Value;
+
+use crate::lib::{qr, read_from_file, AnyhowResult};
+
+/// Decodes the messages printed with --qr, from the text of their QR codes or of all their frames.
+#[derive(Parser)]
+pub struct QRDecodeOpts {
+    /// File with the scanned text, one QR code per line, in any order. Use "-" for STDIN.
+    #[arg(default_value = "-")]
+    file: PathBuf,
+}
+
+/// Prints the decoded messages as one bundle that can be passed to `quill send`.
+pub fn exec(opts: QRDecodeOpts) -> AnyhowResult {
+    let text = read_from_file(&opts.file)?;
+    let payloads = qr::join(text.lines().filter(|line| !line.trim().is_empty()))?;
+    let mut bundle = vec![];
+    for payload in payloads {
+        let gzipped = base64::decode(payload).context("The QR code is not a quill message")?;
+        let mut json = String::new();
+        flate2::read::GzDecoder::new(&gzipped[..])
+            .read_to_string(&mut json)
+            .context("The QR code is not a quill message")?;
+        // each QR code holds one message, or the list of messages of a command
+        match serde_json::from_str(&json)? {
+            Value::Array(messages) => bundle.extend(messages),
+            message => bundle.push(message),
+        }
+    }
+    println!("{}", serde_json::to_string(&bundle)?);
+    Ok(())
+}
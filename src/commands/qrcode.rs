@@ -1,10 +1,43 @@
-use std::path::PathBuf;
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, IsTerminal, Write},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
 
-use crate::lib::{read_from_file, AnyhowResult};
-use anyhow::Context;
+use crate::lib::{
+    now_nanos,
+    qr::{QrEcc, QrFormat, QrOutputOpts},
+    read_from_file, AnyhowResult,
+};
+use anyhow::{bail, Context};
+use chrono::{TimeZone, Utc};
 use clap::Parser;
+use once_cell::sync::Lazy;
 use qrcodegen::{QrCode, QrCodeEcc};
 
+/// How long each frame of a multi-part QR code is shown for.
+const FRAME_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The width of the blank margin around a QR code, in modules.
+const BORDER: i32 = 4;
+
+/// The number of QR codes written to files so far, used to number them.
+static FILES_WRITTEN: AtomicUsize = AtomicUsize::new(0);
+
+/// The start of the names of the files written by this run, from the time it started, so that
+/// they don't clash with the files of earlier runs in the same directory.
+static RUN_PREFIX: Lazy<String> = Lazy::new(|| {
+    Utc.timestamp_nanos(now_nanos() as i64)
+        .format("qr-%Y%m%d-%H%M%S")
+        .to_string()
+});
+
 /// Print QR code for data e.g. principal id.
 #[derive(Parser)]
 pub struct QRCodeOpts {
@@ -18,38 +51,200 @@ pub struct QRCodeOpts {
 }
 
 /// Prints the account and the principal ids.
-pub fn exec(opts: QRCodeOpts) -> AnyhowResult {
+pub fn exec(opts: QRCodeOpts, qr: &QrOutputOpts) -> AnyhowResult {
     if let Some(file) = opts.file {
         let data = read_from_file(file)?;
-        print_qr(&data)?;
+        output_qr(&data, qr)?;
     }
     if let Some(string) = opts.string {
-        print_qr(&string)?;
+        output_qr(&string, qr)?;
     }
     Ok(())
 }
 
+/// Outputs a QR code to the console, or to an image file with --qr-format.
+pub fn output_qr(text: &str, opts: &QrOutputOpts) -> AnyhowResult {
+    if opts.format == QrFormat::Terminal {
+        print_qr(text, opts.ecc)
+    } else {
+        write_qr_files(&[text.to_string()], opts)
+    }
+}
+
 // Prints the given QrCode object to the console.
-pub fn print_qr(text: &str) -> AnyhowResult {
-    let errcorlvl: QrCodeEcc = QrCodeEcc::Medium; // Error correction level
+pub fn print_qr(text: &str, ecc: QrEcc) -> AnyhowResult {
+    print!("{}", render(&encode(text, ecc)?));
+    Ok(())
+}
 
-    // Make and print the QR Code symbol
-    let qr: QrCode = QrCode::encode_text(text, errcorlvl)
-        .context("Could not encode QR code (data too large)")?;
+/// Shows the frames of a payload too large for one QR code. On a terminal they are shown in a
+/// loop until Enter is pressed, so they can be scanned with a phone; otherwise each frame is
+/// printed once, numbered.
+pub fn print_qr_frames(frames: &[String], ecc: QrEcc) -> AnyhowResult {
+    let codes = frames
+        .iter()
+        .map(|frame| encode(frame, ecc))
+        .collect::<AnyhowResult<Vec<_>>>()?;
+    if !(io::stdout().is_terminal() && io::stdin().is_terminal()) {
+        for (i, code) in codes.iter().enumerate() {
+            println!("Frame {} of {}:", i + 1, codes.len());
+            print!("{}", render(code));
+        }
+        return Ok(());
+    }
+    let done = Arc::new(AtomicBool::new(false));
+    thread::spawn({
+        let done = done.clone();
+        move || {
+            let _ = io::stdin().read_line(&mut String::new());
+            done.store(true, Ordering::SeqCst);
+        }
+    });
+    let mut stdout = io::stdout().lock();
+    let mut height = 0;
+    for (i, code) in codes.iter().enumerate().cycle() {
+        if done.load(Ordering::SeqCst) {
+            break;
+        }
+        let frame = format!(
+            "{}Frame {} of {}, press Enter once all frames are scanned\n",
+            render(code),
+            i + 1,
+            codes.len()
+        );
+        if height > 0 {
+            // move back up over the previous frame and clear it, as frames can differ in size
+            write!(stdout, "\x1b[{height}A\x1b[J")?;
+        }
+        stdout.write_all(frame.as_bytes())?;
+        stdout.flush()?;
+        height = frame.lines().count();
+        thread::sleep(FRAME_INTERVAL);
+    }
+    Ok(())
+}
 
-    let border: i32 = 4;
-    for y in -border / 2..=qr.size() / 2 + border / 2 {
-        for x in -border..qr.size() + border {
+/// Writes the frames of a message to numbered image files in the --qr-out directory, in the
+/// format from --qr-format.
+pub fn write_qr_files(frames: &[String], opts: &QrOutputOpts) -> AnyhowResult {
+    let extension = match opts.format {
+        QrFormat::Terminal => unreachable!("QR codes for the terminal are not written to files"),
+        QrFormat::Png => "png",
+        QrFormat::Svg => "svg",
+    };
+    fs::create_dir_all(&opts.out)
+        .with_context(|| format!("Couldn't create directory {}", opts.out.display()))?;
+    let number = FILES_WRITTEN.fetch_add(1, Ordering::SeqCst) + 1;
+    let prefix = &*RUN_PREFIX;
+    let paths: Vec<_> = (1..=frames.len())
+        .map(|i| {
+            opts.out.join(if frames.len() == 1 {
+                format!("{prefix}-{number:03}.{extension}")
+            } else {
+                format!(
+                    "{prefix}-{number:03}-frame-{i:02}-of-{:02}.{extension}",
+                    frames.len()
+                )
+            })
+        })
+        .collect();
+    // never overwrite the codes of an earlier run, which could then be mixed up with these, and
+    // check before writing any of them so that a message is not left half written
+    if let Some(existing) = paths.iter().find(|path| path.exists()) {
+        bail!(
+            "{} already exists. Wait a second, or use another --qr-out directory",
+            existing.display()
+        );
+    }
+    let codes = frames
+        .iter()
+        .map(|frame| encode(frame, opts.ecc))
+        .collect::<AnyhowResult<Vec<_>>>()?;
+    for (code, path) in codes.iter().zip(&paths) {
+        let mut file = File::options()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .with_context(|| format!("Couldn't create {}", path.display()))?;
+        match opts.format {
+            QrFormat::Png => write_png(code, opts.scale, file)?,
+            _ => file.write_all(svg(code, opts.scale).as_bytes())?,
+        }
+        eprintln!("Wrote QR code to {}", path.display());
+    }
+    Ok(())
+}
+
+fn encode(text: &str, ecc: QrEcc) -> AnyhowResult<QrCode> {
+    let errcorlvl = match ecc {
+        QrEcc::Low => QrCodeEcc::Low,
+        QrEcc::Medium => QrCodeEcc::Medium,
+        QrEcc::Quartile => QrCodeEcc::Quartile,
+        QrEcc::High => QrCodeEcc::High,
+    };
+    QrCode::encode_text(text, errcorlvl).context("Could not encode QR code (data too large)")
+}
+
+/// Whether `text` fits in a single QR code at the error correction level `ecc`.
+pub fn fits(text: &str, ecc: QrEcc) -> bool {
+    encode(text, ecc).is_ok()
+}
+
+fn render(qr: &QrCode) -> String {
+    let mut out = String::new();
+    for y in -BORDER / 2..=qr.size() / 2 + BORDER / 2 {
+        for x in -BORDER..qr.size() + BORDER {
             let c = match (!qr.get_module(x, y * 2), !qr.get_module(x, y * 2 + 1)) {
                 (true, true) => '█',
                 (true, false) => '▀',
                 (false, true) => '▄',
                 (false, false) => ' ',
             };
-            print!("{c}");
+            out.push(c);
         }
-        println!();
+        out.push('\n');
     }
-    println!();
+    out.push('\n');
+    out
+}
+
+/// Writes a black-on-white grayscale PNG, with each module `scale` pixels wide.
+fn write_png(qr: &QrCode, scale: u32, file: File) -> AnyhowResult {
+    let size = (qr.size() + 2 * BORDER) as u32 * scale;
+    let mut pixels = Vec::with_capacity((size * size) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            let module = qr.get_module((x / scale) as i32 - BORDER, (y / scale) as i32 - BORDER);
+            pixels.push(if module { 0 } else { 255 });
+        }
+    }
+    let mut encoder = png::Encoder::new(BufWriter::new(file), size, size);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&pixels)?;
+    writer.finish()?;
     Ok(())
 }
+
+/// Renders a QR code as an SVG image, `scale` pixels per module at its default size.
+fn svg(qr: &QrCode, scale: u32) -> String {
+    let mut path = String::new();
+    for y in 0..qr.size() {
+        for x in 0..qr.size() {
+            if qr.get_module(x, y) {
+                path += &format!("M{},{}h1v1h-1z", x + BORDER, y + BORDER);
+            }
+        }
+    }
+    let modules = qr.size() + 2 * BORDER;
+    let pixels = modules as u32 * scale;
+    format!(
+        r##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" version="1.1" viewBox="0 0 {modules} {modules}" width="{pixels}" height="{pixels}" shape-rendering="crispEdges">
+<rect width="100%" height="100%" fill="#ffffff"/>
+<path d="{path}" fill="#000000"/>
+</svg>
+"##
+    )
+}
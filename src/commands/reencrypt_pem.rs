@@ -0,0 +1,94 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{stdin, IsTerminal, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context};
+use clap::Parser;
+use dialoguer::{Password, PasswordValidator};
+use pkcs8::EncodePrivateKey;
+
+use crate::{
+    commands::generate::QuillPasswordPolicy,
+    lib::{encrypt_pkcs8_pem, AnyhowResult, AuthInfo, DEFAULT_SCRYPT_LOG_N},
+    read_file, write_file,
+};
+
+/// Encrypts a PEM file with a new password, whether it was encrypted or not.
+#[derive(Parser)]
+pub struct ReencryptPemOpts {
+    /// The path to write the encrypted PEM to, or "-" for STDOUT. This can be the same file as --pem-file.
+    output_path: PathBuf,
+
+    /// Read the new password from this file. Use "-" for STDIN.
+    #[arg(long)]
+    new_password_file: Option<PathBuf>,
+
+    /// The scrypt cost parameter, as a power of two. Higher values are slower to brute-force, and
+    /// also slower to decrypt.
+    #[arg(
+        long,
+        default_value_t = DEFAULT_SCRYPT_LOG_N,
+        value_parser = clap::value_parser!(u8).range(10..=22)
+    )]
+    scrypt_log_n: u8,
+}
+
+pub fn exec(auth: &AuthInfo, opts: ReencryptPemOpts) -> AnyhowResult {
+    let pkcs8 = match auth {
+        AuthInfo::K256Key(pk) => pk.to_pkcs8_der()?.as_bytes().to_vec(),
+        AuthInfo::Ed25519Key(pkcs8) => pkcs8.clone(),
+        _ => bail!("--pem-file was not set to a secp256k1 or Ed25519 PEM file"),
+    };
+    let password = if let Some(password_file) = &opts.new_password_file {
+        let content = read_file(password_file, "new password")?;
+        QuillPasswordPolicy.validate(&content)?;
+        content
+    } else if stdin().is_terminal() {
+        Password::new()
+            .with_prompt("New PEM encryption password")
+            .with_confirmation("Re-enter password", "Passwords did not match")
+            .validate_with(QuillPasswordPolicy)
+            .interact()?
+    } else {
+        bail!("Must use --new-password-file if stdin cannot receive terminal input.");
+    };
+    let pem = encrypt_pkcs8_pem(&pkcs8, &password, opts.scrypt_log_n)?;
+    if opts.output_path == Path::new("-") {
+        write_file(&opts.output_path, "PEM", pem.as_bytes())?;
+    } else {
+        // write to a temporary file first, so that a failed write cannot destroy the original key
+        let mut tmp_path = opts.output_path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        write_private_file(Path::new(&tmp_path), pem.as_bytes())?;
+        // keep the permissions of the file being replaced, e.g. if it is readable by a group
+        if let Ok(metadata) = fs::metadata(&opts.output_path) {
+            fs::set_permissions(&tmp_path, metadata.permissions())
+                .context("Couldn't set the permissions of the PEM file")?;
+        }
+        fs::rename(&tmp_path, &opts.output_path)
+            .with_context(|| format!("Couldn't replace PEM file {}", opts.output_path.display()))?;
+    }
+    eprintln!("Wrote encrypted PEM file to {}", opts.output_path.display());
+    Ok(())
+}
+
+/// Writes `content` to a new file at `path` that only the current user can read.
+fn write_private_file(path: &Path, content: &[u8]) -> AnyhowResult {
+    // a leftover file from an interrupted run would keep its permissions
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(e).context("Couldn't remove the temporary PEM file")
+        }
+        _ => {}
+    }
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(content))
+        .context("Couldn't write PEM file")
+}
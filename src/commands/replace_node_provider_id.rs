@@ -1,8 +1,11 @@
 use std::str::FromStr;
 
 use crate::{
-    lib::signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
-    lib::{registry_canister_id, AnyhowResult, AuthInfo, ROLE_NNS_REGISTRY},
+    lib::{network::Network, AnyhowResult, AuthInfo, ROLE_NNS_REGISTRY},
+    lib::{
+        signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
+        IngressExpiryOpts,
+    },
 };
 use anyhow::{anyhow, Context};
 use candid::{CandidType, Encode};
@@ -32,6 +35,8 @@ pub struct UpdateNodeOperatorConfigDirectlyPayload {
 pub fn exec(
     auth: &AuthInfo,
     opts: ReplaceNodeProviderIdOpts,
+    ingress_expiry: IngressExpiryOpts,
+    network: &Network,
 ) -> AnyhowResult<Vec<IngressWithRequestId>> {
     let node_operator_id = PrincipalId::from_str(&opts.node_operator_id)
         .map_err(|e| anyhow!(e))
@@ -55,7 +60,8 @@ pub fn exec(
     })?;
     Ok(vec![sign_ingress_with_request_status_query(
         auth,
-        registry_canister_id(),
+        ingress_expiry,
+        network.registry_canister_id(),
         ROLE_NNS_REGISTRY,
         "update_node_operator_config_directly",
         args,
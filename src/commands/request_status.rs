@@ -1,30 +1,36 @@
+use crate::commands::{OutputFormat, SendingOpts};
 use crate::lib::{
-    display_response, get_agent, get_idl_string, signing::RequestStatus, AnyhowResult, AuthInfo,
+    display_csv_response, display_json_response, display_response, get_idl_string,
+    signing::RequestStatus, AnyhowResult,
 };
 use anyhow::{anyhow, Context};
 use candid::Principal;
 use ic_agent::agent::{ReplyResponse, RequestStatusResponse};
-use ic_agent::{AgentError, RequestId};
+use ic_agent::{Agent, AgentError, RequestId};
 use std::str::FromStr;
+use std::time::Duration;
+
+/// The delay before the status of a request is first checked again, which doubles after each check
+/// up to `MAX_POLL_INTERVAL`.
+const INITIAL_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(8);
 
 pub async fn submit(
+    agent: &Agent,
     req: &RequestStatus,
     method_name: Option<String>,
     role: &str,
-    raw: bool,
-    fetch_root_key: bool,
+    sending_opts: &SendingOpts,
 ) -> AnyhowResult<String> {
     let canister_id =
         Principal::from_text(&req.canister_id).context("Couldn't parse canister id")?;
     let request_id =
         RequestId::from_str(&req.request_id).context("Invalid argument: request_id")?;
-    let agent = get_agent(&AuthInfo::NoAuth)?;
-    if fetch_root_key {
-        agent.fetch_root_key().await?;
-    }
     let envelope = hex::decode(&req.content)
         .context("Unable to decode request content (should be hexadecimal encoded)")?;
-    let ReplyResponse { arg: blob } = async {
+    let poll = async {
+        let mut interval = INITIAL_POLL_INTERVAL;
+        let mut reported = false;
         loop {
             match agent
                 .request_status_signed(&request_id, canister_id, envelope.clone())
@@ -37,7 +43,14 @@ pub async fn submit(
                 RequestStatusResponse::Unknown
                 | RequestStatusResponse::Received
                 | RequestStatusResponse::Processing => {
-                    println!("The request is being processed...");
+                    if !reported {
+                        reported = true;
+                        if sending_opts.output != OutputFormat::Text {
+                            eprintln!("The request is being processed...");
+                        } else {
+                            println!("The request is being processed...");
+                        }
+                    }
                 }
                 RequestStatusResponse::Done => {
                     return Err(anyhow!(AgentError::RequestStatusDoneNoReply(String::from(
@@ -46,16 +59,62 @@ pub async fn submit(
                 }
             };
 
-            std::thread::sleep(std::time::Duration::from_millis(500));
+            tokio::time::sleep(interval).await;
+            interval = (interval * 2).min(MAX_POLL_INTERVAL);
         }
-    }
-    .await?;
-    let method_str = method_name.unwrap_or_default();
-    if raw {
-        get_idl_string(&blob, canister_id, role, &method_str, "rets")
+    };
+    let ReplyResponse { arg: blob } = match sending_opts.timeout {
+        Some(timeout) => tokio::time::timeout(timeout, poll).await.map_err(|_| {
+            anyhow!(
+                "Timed out after {}s waiting for a response to request 0x{}. It may still be \
+                processed; run `quill send` on the same file again to check its status.",
+                timeout.as_secs(),
+                String::from(request_id)
+            )
+        })??,
+        None => poll.await?,
+    };
+    format_reply(
+        &blob,
+        canister_id,
+        &method_name.unwrap_or_default(),
+        role,
+        sending_opts,
+    )
+}
+
+/// Formats the reply to a call to `method_name` according to `--output` and `--raw`.
+pub fn format_reply(
+    blob: &[u8],
+    canister_id: Principal,
+    method_name: &str,
+    role: &str,
+    sending_opts: &SendingOpts,
+) -> AnyhowResult<String> {
+    if sending_opts.output == OutputFormat::Json {
+        display_json_response(
+            blob,
+            canister_id,
+            role,
+            method_name,
+            "rets",
+            &sending_opts.display,
+        )
+    } else if sending_opts.output == OutputFormat::Csv {
+        display_csv_response(blob, role, method_name, &sending_opts.display)
+    } else if sending_opts.raw {
+        get_idl_string(blob, canister_id, role, method_name, "rets")
     } else {
-        display_response(&blob, canister_id, role, &method_str, "rets").or_else(|e| {
-            get_idl_string(&blob, canister_id, role, &method_str, "rets").map(|m| {
+        display_response(
+            blob,
+            canister_id,
+            role,
+            method_name,
+            "rets",
+            &sending_opts.display,
+        )
+        .or_else(|e| {
+            get_idl_string(blob, canister_id, role, method_name, "rets").map(|m| {
                 format!("Error pretty-printing response: {e}. Falling back to IDL display\n{m}",)
             })
         })
@@ -1,19 +1,44 @@
 use crate::commands::request_status;
-use crate::lib::get_idl_string;
 use crate::lib::{
-    get_ic_url, read_from_file,
+    get_agent,
+    journal::{Journal, SubmissionStatus},
+    network::Network,
+    now_nanos, read_from_file,
     signing::{Ingress, IngressWithRequestId},
-    AnyhowResult, AuthInfo,
+    AnyhowResult, AuthInfo, IngressExpiryOpts,
 };
-use anyhow::{anyhow, bail};
-use candid::Principal;
+use crate::lib::{get_idl_string, get_json_string};
+use anyhow::{anyhow, bail, ensure};
+use candid::{Decode, Principal};
 use clap::Parser;
+use futures::{stream, StreamExt};
 use ic_agent::agent::CallResponse;
-use ic_agent::{Agent, AgentError};
+use ic_agent::{Agent, AgentError, RequestId};
+use ic_nns_common::pb::v1::NeuronId;
+use ic_nns_governance::pb::v1::{
+    manage_neuron::{Command, NeuronIdOrSubaccount},
+    ManageNeuron,
+};
+use std::cell::RefCell;
 use std::io::IsTerminal;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use super::SendingOpts;
+use super::{OutputFormat, SendingOpts};
+
+/// Prints progress messages, which go to stderr in JSON and CSV mode so that stdout only contains
+/// the response.
+macro_rules! progress {
+    ($opts:expr, $($arg:tt)*) => {
+        if $opts.sending_opts.output != OutputFormat::Text {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// How many independent messages of a bundle are sent at once.
+const MAX_CONCURRENT_REQUESTS: usize = 16;
 
 /// Sends a signed message or a set of messages.
 #[derive(Parser)]
@@ -21,12 +46,33 @@ pub struct SendOpts {
     /// Path to the signed message (`-` for stdin)
     file_name: Option<PathBuf>,
 
+    /// Records the status of each submitted message in this file, so that if sending is
+    /// interrupted, running the same command again skips the messages that were already sent.
+    /// Defaults to the path of the message file with `.journal` appended.
+    #[arg(long, conflicts_with = "no_journal")]
+    journal: Option<PathBuf>,
+
+    /// Does not record or check which messages were already sent.
+    #[arg(long)]
+    no_journal: bool,
+
+    /// Displays the response to a message signed by `quill neuron-report` as a report with
+    /// totals, rather than as a list of neurons. Use `--output csv` or `--output json` to export
+    /// it.
+    #[arg(long)]
+    neuron_report: bool,
+
     #[command(flatten)]
     sending_opts: SendingOpts,
 }
 
 #[tokio::main]
-pub async fn exec(opts: SendOpts, fetch_root_key: bool) -> AnyhowResult {
+pub async fn exec(mut opts: SendOpts, network: &Network) -> AnyhowResult {
+    ensure!(
+        opts.neuron_report || opts.sending_opts.output != OutputFormat::Csv,
+        "--output csv is only available for reports, such as with --neuron-report"
+    );
+    opts.sending_opts.display.neuron_report = opts.neuron_report;
     let file_name = if let Some(file_name) = &opts.file_name {
         file_name.as_path()
     } else if !std::io::stdin().is_terminal() {
@@ -35,16 +81,29 @@ pub async fn exec(opts: SendOpts, fetch_root_key: bool) -> AnyhowResult {
         bail!("File name must be provided if not being piped")
     };
     let json = read_from_file(file_name)?;
+    let journal_path = match &opts.journal {
+        _ if opts.no_journal => None,
+        Some(path) => Some(path.clone()),
+        None if file_name == Path::new("-") => None,
+        None => {
+            let mut path = file_name.as_os_str().to_owned();
+            path.push(".journal");
+            Some(PathBuf::from(path))
+        }
+    };
+    // the journal is only created once a message is about to be submitted, so never in a dry run
+    let mut journal = match journal_path.as_deref() {
+        Some(path) => Some(Journal::read(path)?),
+        None => None,
+    };
     if let Ok(val) = serde_json::from_str::<Ingress>(&json) {
-        send(&val, &opts).await?;
+        send_once(&val, &opts, network, journal.as_mut()).await?;
     } else if let Ok(vals) = serde_json::from_str::<Vec<Ingress>>(&json) {
         for msg in vals {
-            send(&msg, &opts).await?;
+            send_once(&msg, &opts, network, journal.as_mut()).await?;
         }
     } else if let Ok(vals) = serde_json::from_str::<Vec<IngressWithRequestId>>(&json) {
-        for tx in vals {
-            submit_ingress_and_check_status(&tx, &opts, fetch_root_key).await?;
-        }
+        submit_ingresses_and_check_status(&vals, &opts, network, journal.as_mut()).await?;
     } else {
         return Err(anyhow!("Invalid JSON content"));
     }
@@ -57,61 +116,409 @@ pub async fn submit_unsigned_ingress(
     method_name: &str,
     args: Vec<u8>,
     sending_opts: SendingOpts,
-    fetch_root_key: bool,
+    network: &Network,
 ) -> AnyhowResult {
+    ensure!(
+        sending_opts.output != OutputFormat::Csv,
+        "--output csv is only available for reports, such as with `quill send --neuron-report`"
+    );
+    // the message is sent right away
     let msg = crate::lib::signing::sign_ingress_with_request_status_query(
         &AuthInfo::NoAuth,
+        IngressExpiryOpts::default(),
         canister_id,
         role,
         method_name,
         args,
     )?;
-    submit_ingress_and_check_status(
-        &msg,
+    submit_ingresses_and_check_status(
+        std::slice::from_ref(&msg),
         &SendOpts {
             file_name: None,
+            journal: None,
+            no_journal: true,
+            neuron_report: false,
             sending_opts,
         },
-        fetch_root_key,
+        network,
+        None,
     )
     .await
 }
 
-async fn submit_ingress_and_check_status(
-    message: &IngressWithRequestId,
+/// Sends a message without a request status query, unless the journal shows it was already sent.
+async fn send_once(
+    message: &Ingress,
     opts: &SendOpts,
-    fetch_root_key: bool,
+    network: &Network,
+    journal: Option<&mut Journal>,
 ) -> AnyhowResult {
-    send(&message.ingress, opts).await?;
+    let Some(journal) = journal.filter(|_| message.call_type == "update") else {
+        send(message, opts, network).await?;
+        return Ok(());
+    };
+    let request_id = message.compute_request_id()?;
+    if journal.status(request_id).is_some() {
+        progress!(
+            opts,
+            "Skipping request 0x{}, which was already sent according to {}",
+            String::from(request_id),
+            journal.path().display()
+        );
+        return Ok(());
+    }
+    if !opts.sending_opts.dry_run {
+        journal.prepare();
+    }
+    let replied = send(message, opts, network).await?;
     if opts.sending_opts.dry_run {
         return Ok(());
     }
-    let (_, _, method_name, _, role) = &message.ingress.parse()?;
-    match request_status::submit(
-        &message.request_status,
-        Some(method_name.to_string()),
-        role,
-        opts.sending_opts.raw,
-        fetch_root_key,
+    let status = if replied {
+        SubmissionStatus::Replied
+    } else {
+        SubmissionStatus::Submitted
+    };
+    journal.record(request_id, status)
+}
+
+/// A message of a bundle that is to be sent.
+struct Plan<'a> {
+    message: &'a IngressWithRequestId,
+    request_id: RequestId,
+    /// Whether an earlier run that was interrupted already submitted this copy of the message, so
+    /// that only its response has to be waited for.
+    submitted: bool,
+}
+
+/// The order in which the messages of a bundle are sent: each step starts once every message of
+/// the previous step has succeeded, and consists of chains of messages, indexes into the plans,
+/// that are sent one after the other. The chains of a step are sent concurrently.
+type Schedule = Vec<Vec<Vec<usize>>>;
+
+/// Displays and confirms each message in turn, then sends them in the order of the bundle, each
+/// once the messages it may depend on have succeeded.
+async fn submit_ingresses_and_check_status(
+    messages: &[IngressWithRequestId],
+    opts: &SendOpts,
+    network: &Network,
+    mut journal: Option<&mut Journal>,
+) -> AnyhowResult {
+    let mut plans = vec![];
+    'messages: for message in messages {
+        // a copy may have been sent by an earlier run that was interrupted
+        if let Some(journal) = &journal {
+            for copy in std::iter::once(message).chain(&message.later_copies) {
+                let request_id = copy.ingress.compute_request_id()?;
+                match journal.status(request_id) {
+                    Some(SubmissionStatus::Replied | SubmissionStatus::Rejected) => {
+                        progress!(
+                            opts,
+                            "Skipping request 0x{}, which was already completed according to {}",
+                            String::from(request_id),
+                            journal.path().display()
+                        );
+                        continue 'messages;
+                    }
+                    Some(SubmissionStatus::Submitted) => {
+                        progress!(
+                            opts,
+                            "Request 0x{} was already sent, checking its status",
+                            String::from(request_id)
+                        );
+                        plans.push(Plan {
+                            message: copy,
+                            request_id,
+                            submitted: true,
+                        });
+                        continue 'messages;
+                    }
+                    None => {}
+                }
+            }
+        }
+        let message = if message.later_copies.is_empty() || opts.sending_opts.dry_run {
+            message
+        } else {
+            message.valid_copy(now_nanos())?
+        };
+        display_and_confirm(&message.ingress, opts)?;
+        plans.push(Plan {
+            message,
+            request_id: message.ingress.compute_request_id()?,
+            submitted: false,
+        });
+    }
+    let schedule = schedule(&plans, network)?;
+    if opts.sending_opts.dry_run {
+        if plans.len() > 1 {
+            print_schedule(&schedule, opts);
+        }
+        return Ok(());
+    }
+    if plans.is_empty() {
+        return Ok(());
+    }
+
+    let agent = &get_agent(&AuthInfo::NoAuth, network)?;
+    if network.fetch_root_key {
+        agent.fetch_root_key().await?;
+    }
+    if let Some(journal) = &mut journal {
+        journal.prepare();
+    }
+    let plans = &plans;
+    let journal = &RefCell::new(journal);
+    let mut errors = vec![];
+    let mut failed = false;
+    for step in &schedule {
+        if failed {
+            errors.extend(step.iter().flatten().map(|&i| not_sent(&plans[i])));
+            continue;
+        }
+        let mut results: Vec<_> = stream::iter(step)
+            .map(|chain| async move {
+                let mut results = vec![];
+                for &i in chain {
+                    let result = if results
+                        .iter()
+                        .all(|(_, result)| matches!(result, Ok(Ok(_))))
+                    {
+                        complete(agent, &plans[i], opts, journal).await
+                    } else {
+                        Err(not_sent(&plans[i]))
+                    };
+                    results.push((i, result));
+                }
+                results
+            })
+            .buffer_unordered(MAX_CONCURRENT_REQUESTS)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .flatten()
+            .collect();
+        results.sort_by_key(|(i, _)| *i);
+        for (_, result) in results {
+            let outcome = match result {
+                Ok(outcome) => outcome,
+                Err(e) => {
+                    failed = true;
+                    errors.push(e);
+                    continue;
+                }
+            };
+            match outcome {
+                Ok(result) => println!("{}", result.trim()),
+                Err(err) => {
+                    failed = true;
+                    match opts.sending_opts.output {
+                        OutputFormat::Json => {
+                            println!("{}", serde_json::json!({ "error": err.to_string() }))
+                        }
+                        OutputFormat::Csv => eprintln!("{err}"),
+                        OutputFormat::Text => println!("{err}"),
+                    }
+                }
+            }
+        }
+    }
+
+    match errors.len() {
+        0 => Ok(()),
+        1 => Err(errors.remove(0)),
+        n => {
+            for error in &errors {
+                eprintln!("Error: {error}");
+            }
+            bail!("{n} messages could not be sent")
+        }
+    }
+}
+
+/// Works out which messages may depend on each other. Messages that manage different neurons are
+/// independent, but any other message is sent after every message before it, and before every
+/// message after it, e.g. the claim of a new neuron after the transfer that funds it.
+fn schedule(plans: &[Plan], network: &Network) -> AnyhowResult<Schedule> {
+    let mut schedule = vec![];
+    let mut chains: Vec<(u64, Vec<usize>)> = vec![];
+    for (i, plan) in plans.iter().enumerate() {
+        match managed_neuron(&plan.message.ingress, network)? {
+            Some(neuron) => match chains.iter_mut().find(|(id, _)| *id == neuron) {
+                Some((_, chain)) => chain.push(i),
+                None => chains.push((neuron, vec![i])),
+            },
+            None => {
+                if !chains.is_empty() {
+                    schedule.push(chains.drain(..).map(|(_, chain)| chain).collect());
+                }
+                schedule.push(vec![vec![i]]);
+            }
+        }
+    }
+    if !chains.is_empty() {
+        schedule.push(chains.into_iter().map(|(_, chain)| chain).collect());
+    }
+    Ok(schedule)
+}
+
+/// Returns the ID of the neuron a message manages, if it changes nothing else.
+fn managed_neuron(message: &Ingress, network: &Network) -> AnyhowResult<Option<u64>> {
+    let (canister_id, method_name, arg) = message.call()?;
+    if canister_id != network.governance_canister_id() || method_name != "manage_neuron" {
+        return Ok(None);
+    }
+    let Ok(args) = Decode!(&arg, ManageNeuron) else {
+        return Ok(None);
+    };
+    // a merge also changes the source neuron
+    if matches!(args.command, Some(Command::Merge(_))) {
+        return Ok(None);
+    }
+    Ok(match (args.id, args.neuron_id_or_subaccount) {
+        (Some(NeuronId { id }), _)
+        | (None, Some(NeuronIdOrSubaccount::NeuronId(NeuronId { id }))) => Some(id),
+        _ => None,
+    })
+}
+
+/// Prints when each message would be sent.
+fn print_schedule(schedule: &Schedule, opts: &SendOpts) {
+    let mut lines = vec![];
+    let mut previous: Vec<usize> = vec![];
+    for step in schedule {
+        for chain in step {
+            for (j, &i) in chain.iter().enumerate() {
+                let after = if j > 0 {
+                    vec![chain[j - 1]]
+                } else {
+                    previous.clone()
+                };
+                let when = match &after[..] {
+                    [] => "sent first".to_string(),
+                    [one] => format!("sent after message {} succeeds", one + 1),
+                    [init @ .., last] => format!(
+                        "sent after messages {} and {} succeed",
+                        init.iter()
+                            .map(|i| (i + 1).to_string())
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                        last + 1
+                    ),
+                };
+                lines.push((i, when));
+            }
+        }
+        previous = step
+            .iter()
+            .filter_map(|chain| chain.last().copied())
+            .collect();
+    }
+    lines.sort();
+    progress!(opts, "\nSending order:");
+    for (i, when) in lines {
+        progress!(opts, "  Message {}: {when}", i + 1);
+    }
+}
+
+/// Submits a message, unless an earlier run already did, and waits for its response. Returns an
+/// error if it could not be sent, and otherwise the response or the reason it is not known.
+async fn complete(
+    agent: &Agent,
+    plan: &Plan<'_>,
+    opts: &SendOpts,
+    journal: &RefCell<Option<&mut Journal>>,
+) -> AnyhowResult<AnyhowResult<String>> {
+    let ingress = &plan.message.ingress;
+    let (_, canister_id, method_name, _, role) = ingress.parse()?;
+    if !plan.submitted {
+        match submit(agent, ingress, opts).await {
+            Ok(Some(reply)) => {
+                record(journal, plan.request_id, SubmissionStatus::Replied)?;
+                return Ok(request_status::format_reply(
+                    &reply,
+                    canister_id,
+                    &method_name,
+                    &role,
+                    &opts.sending_opts,
+                ));
+            }
+            Ok(None) => record(journal, plan.request_id, SubmissionStatus::Submitted)?,
+            Err(e) if is_certified_reject(&e) => {
+                record(journal, plan.request_id, SubmissionStatus::Rejected)?;
+                return Ok(Err(e));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    let result = request_status::submit(
+        agent,
+        &plan.message.request_status,
+        Some(method_name),
+        &role,
+        &opts.sending_opts,
     )
-    .await
-    {
-        Ok(result) => println!("{}", result.trim()),
-        Err(err) => println!("{err}"),
+    .await;
+    match &result {
+        Ok(_) => record(journal, plan.request_id, SubmissionStatus::Replied)?,
+        Err(err) if is_certified_reject(err) => {
+            record(journal, plan.request_id, SubmissionStatus::Rejected)?
+        }
+        // e.g. a network error or a timeout, so the status is still unknown
+        Err(_) => {}
+    }
+    Ok(result)
+}
+
+fn record(
+    journal: &RefCell<Option<&mut Journal>>,
+    request_id: RequestId,
+    status: SubmissionStatus,
+) -> AnyhowResult {
+    match journal.borrow_mut().as_mut() {
+        Some(journal) => journal.record(request_id, status),
+        None => Ok(()),
+    }
+}
+
+fn not_sent(plan: &Plan) -> anyhow::Error {
+    anyhow!(
+        "Request 0x{} was not sent, because a message before it did not succeed",
+        String::from(plan.request_id)
+    )
+}
+
+/// Sends a message, printing the reply if the IC responded synchronously. Returns whether it did.
+async fn send(message: &Ingress, opts: &SendOpts, network: &Network) -> AnyhowResult<bool> {
+    display_and_confirm(message, opts)?;
+    if opts.sending_opts.dry_run {
+        return Ok(false);
+    }
+    let agent = get_agent(&AuthInfo::NoAuth, network)?;
+    if network.fetch_root_key {
+        agent.fetch_root_key().await?;
+    }
+    let Some(reply) = submit(&agent, message, opts).await? else {
+        return Ok(false);
     };
-    Ok(())
+    let (_, canister_id, method_name, _, role) = message.parse()?;
+    let reply =
+        request_status::format_reply(&reply, canister_id, &method_name, &role, &opts.sending_opts)?;
+    println!("{}", reply.trim());
+    Ok(true)
 }
 
-async fn send(message: &Ingress, opts: &SendOpts) -> AnyhowResult {
-    let (sender, canister_id, method_name, args, role) = message.parse()?;
+/// Displays the message, and asks whether to send it unless `--yes` was passed.
+fn display_and_confirm(message: &Ingress, opts: &SendOpts) -> AnyhowResult {
+    let (sender, canister_id, method_name, args, _) = message.parse()?;
     let call_type = &message.call_type;
 
-    println!("Sending message with\n");
-    println!("  Call type:   {call_type}");
-    println!("  Sender:      {sender}");
-    println!("  Canister id: {canister_id}");
-    println!("  Method name: {method_name}");
-    println!("  Arguments:   {args}");
+    progress!(opts, "Sending message with\n");
+    progress!(opts, "  Call type:   {call_type}");
+    progress!(opts, "  Sender:      {sender}");
+    progress!(opts, "  Canister id: {canister_id}");
+    progress!(opts, "  Method name: {method_name}");
+    progress!(opts, "  Arguments:   {args}");
 
     if opts.sending_opts.dry_run {
         return Ok(());
@@ -123,21 +530,42 @@ async fn send(message: &Ingress, opts: &SendOpts) -> AnyhowResult {
             eprintln!("To confirm sending this message, rerun `quill send` with the `-y` flag.");
             std::process::exit(1);
         }
-        println!("\nDo you want to send this message? [y/N]");
+        progress!(opts, "\nDo you want to send this message? [y/N]");
         let mut input = String::new();
         std::io::stdin().read_line(&mut input)?;
         if !["y", "yes"].contains(&input.to_lowercase().trim()) {
             std::process::exit(0);
         }
     }
+    Ok(())
+}
 
-    let agent = Agent::builder().with_url(get_ic_url()).build().unwrap();
-
+/// Submits a message. Returns the reply to an update call if the IC responded synchronously, and
+/// `None` if its status has to be polled.
+async fn submit(
+    agent: &Agent,
+    message: &Ingress,
+    opts: &SendOpts,
+) -> AnyhowResult<Option<Vec<u8>>> {
+    let (_, canister_id, method_name, _, role) = message.parse()?;
     let content = hex::decode(&message.content)?;
 
     match message.call_type.as_str() {
         "query" => {
             let result = agent.query_signed(canister_id, content).await;
+            if opts.sending_opts.output == OutputFormat::Json {
+                let response = match result {
+                    Ok(bytes) => get_json_string(&bytes, canister_id, &role, &method_name, "rets")?,
+                    Err(AgentError::UncertifiedReject(resp)) => serde_json::json!({
+                        "error": resp.reject_message,
+                        "reject_code": format!("{:?}", resp.reject_code),
+                    })
+                    .to_string(),
+                    Err(e) => bail!(e),
+                };
+                println!("{response}");
+                return Ok(None);
+            }
             let response = match result {
                 Ok(bytes) => get_idl_string(&bytes, canister_id, &role, &method_name, "rets")?,
                 Err(AgentError::UncertifiedReject(resp)) => format!(
@@ -147,19 +575,28 @@ async fn send(message: &Ingress, opts: &SendOpts) -> AnyhowResult {
                 Err(e) => bail!(e),
             };
             println!("Response: {response}");
+            Ok(None)
         }
         "update" => {
+            // The agent uses the synchronous call endpoint if the replica supports it, and only
+            // returns the reply once it has verified the certificate it came with.
             let result = agent.update_signed(canister_id, content).await;
-            let request_id = match result {
-                Ok(CallResponse::Poll(id)) => id,
-                Ok(CallResponse::Response(_)) => {
-                    bail!("This version of quill does not support synchronous calls")
-                }
+            let request_id = message.compute_request_id()?;
+            progress!(opts, "Request ID: 0x{}", String::from(request_id));
+            match result {
+                Ok(CallResponse::Response(reply)) => Ok(Some(reply)),
+                Ok(CallResponse::Poll(_)) => Ok(None),
                 Err(e) => bail!(e),
-            };
-            println!("Request ID: 0x{}", String::from(request_id));
+            }
         }
         _ => unreachable!(),
     }
-    Ok(())
+}
+
+/// Whether the IC rejected the call, as opposed to it not being known how the call went.
+fn is_certified_reject(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<AgentError>(),
+        Some(AgentError::CertifiedReject(_))
+    )
 }
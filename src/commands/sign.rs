@@ -0,0 +1,40 @@
+use crate::lib::{
+    read_from_file,
+    signing::{sign_unsigned_message, IngressWithRequestId},
+    AnyhowResult, AuthInfo,
+};
+use anyhow::{bail, Context};
+use clap::Parser;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+
+/// Signs a set of messages produced with `--unsigned-output`, so that they can be sent with `quill send`.
+#[derive(Parser)]
+pub struct SignOpts {
+    /// Path to the unsigned messages (`-` for stdin)
+    file_name: Option<PathBuf>,
+}
+
+pub fn exec(auth: &AuthInfo, opts: SignOpts) -> AnyhowResult<Vec<IngressWithRequestId>> {
+    if matches!(auth, AuthInfo::NoAuth | AuthInfo::SenderOnly(_)) {
+        bail!("quill sign requires a key to sign with, such as --pem-file");
+    }
+    let file_name = if let Some(file_name) = &opts.file_name {
+        file_name.as_path()
+    } else if !std::io::stdin().is_terminal() {
+        "-".as_ref()
+    } else {
+        bail!("File name must be provided if not being piped")
+    };
+    let json = read_from_file(file_name)?;
+    let messages: Vec<IngressWithRequestId> =
+        serde_json::from_str(&json).context("Invalid JSON content")?;
+    messages
+        .iter()
+        .enumerate()
+        .map(|(i, msg)| {
+            sign_unsigned_message(auth, msg)
+                .with_context(|| format!("Failed to sign message #{}", i + 1))
+        })
+        .collect()
+}
@@ -13,10 +13,13 @@ use ic_sns_governance::pb::v1::{NeuronId, Subaccount};
 use icrc_ledger_types::icrc1::account::Account;
 use serde::Serialize;
 
-use crate::lib::{AnyhowResult, AuthInfo};
+use crate::lib::{network::Network, qr::QrOutputOpts, AnyhowResult, AuthInfo, IngressExpiryOpts};
 
 use super::print_vec;
 
+mod account_history;
+mod allowance;
+mod approve;
 mod balance;
 mod configure_dissolve_delay;
 mod disburse;
@@ -37,6 +40,7 @@ mod stake_maturity;
 mod stake_neuron;
 mod status;
 mod transfer;
+mod transfer_from;
 
 /// Commands for interacting with a Service Nervous System's Ledger & Governance canisters.
 ///
@@ -47,8 +51,11 @@ mod transfer;
 ///   "governance_canister_id": "rrkah-fqaaa-aaaaa-aaaaq-cai",
 ///   "ledger_canister_id": "ryjl3-tyaaa-aaaaa-aaaba-cai",
 ///   "root_canister_id": "r7inp-6aaaa-aaaaa-aaabq-cai",
-///   "swap_canister_id": "rkp4c-7iaaa-aaaaa-aaaca-cai"
+///   "swap_canister_id": "rkp4c-7iaaa-aaaaa-aaaca-cai",
+///   "index_canister_id": "qhbym-qaaaa-aaaaa-aaafq-cai"
 /// }
+///
+/// `index_canister_id` is optional, and only needed for `account-history`.
 #[derive(Parser)]
 pub struct SnsOpts {
     /// Path to a SNS canister JSON file (see `quill sns help`)
@@ -62,6 +69,9 @@ pub struct SnsOpts {
 
 #[derive(Subcommand)]
 pub enum SnsCommand {
+    AccountHistory(account_history::AccountHistoryOpts),
+    Allowance(allowance::AllowanceOpts),
+    Approve(approve::ApproveOpts),
     Balance(balance::BalanceOpts),
     ConfigureDissolveDelay(configure_dissolve_delay::ConfigureDissolveDelayOpts),
     Disburse(disburse::DisburseOpts),
@@ -82,9 +92,16 @@ pub enum SnsCommand {
     Status(status::StatusOpts),
     Pay(pay::PayOpts),
     Transfer(transfer::TransferOpts),
+    TransferFrom(transfer_from::TransferFromOpts),
 }
 
-pub fn dispatch(auth: &AuthInfo, opts: SnsOpts, qr: bool, fetch_root_key: bool) -> AnyhowResult {
+pub fn dispatch(
+    auth: &AuthInfo,
+    opts: SnsOpts,
+    ingress_expiry: IngressExpiryOpts,
+    qr: &QrOutputOpts,
+    network: &Network,
+) -> AnyhowResult {
     if opts.ledger {
         ensure!(matches!(
             opts.subcommand,
@@ -96,75 +113,90 @@ pub fn dispatch(auth: &AuthInfo, opts: SnsOpts, qr: bool, fetch_root_key: bool)
         .context("Cannot sign message without knowing the SNS canister ids, did you forget `--canister-ids-file <json-file>`?")
         .and_then(|file| Ok(serde_json::from_slice::<SnsCanisterIds>(&fs::read(file)?)?));
     match opts.subcommand {
+        SnsCommand::AccountHistory(opts) => {
+            account_history::exec(auth, &canister_ids?, opts, network)?;
+        }
+        SnsCommand::Allowance(opts) => {
+            allowance::exec(auth, &canister_ids?, opts, network)?;
+        }
+        SnsCommand::Approve(opts) => {
+            let out = approve::exec(auth, &canister_ids?, opts, ingress_expiry)?;
+            print_vec(qr, &out)?;
+        }
         SnsCommand::Balance(opts) => {
-            balance::exec(auth, &canister_ids?, opts, fetch_root_key)?;
+            balance::exec(auth, &canister_ids?, opts, network)?;
         }
         SnsCommand::ConfigureDissolveDelay(opts) => {
-            let out = configure_dissolve_delay::exec(auth, &canister_ids?, opts)?;
+            let out = configure_dissolve_delay::exec(auth, &canister_ids?, opts, ingress_expiry)?;
             print_vec(qr, &out)?;
         }
         SnsCommand::Disburse(opts) => {
-            let out = disburse::exec(auth, &canister_ids?, opts)?;
+            let out = disburse::exec(auth, &canister_ids?, opts, ingress_expiry)?;
             print_vec(qr, &out)?;
         }
         SnsCommand::DisburseMaturity(opts) => {
-            let out = disburse_maturity::exec(auth, &canister_ids?, opts)?;
+            let out = disburse_maturity::exec(auth, &canister_ids?, opts, ingress_expiry)?;
             print_vec(qr, &out)?;
         }
         SnsCommand::FollowNeuron(opts) => {
-            let out = follow_neuron::exec(auth, &canister_ids?, opts)?;
+            let out = follow_neuron::exec(auth, &canister_ids?, opts, ingress_expiry)?;
             print_vec(qr, &out)?;
         }
         SnsCommand::GetSwapRefund(opts) => {
-            let out = get_swap_refund::exec(auth, &canister_ids?, opts)?;
+            let out = get_swap_refund::exec(auth, &canister_ids?, opts, ingress_expiry)?;
             print_vec(qr, &out)?;
         }
-        SnsCommand::ListDeployedSnses(opts) => list_deployed_snses::exec(opts, fetch_root_key)?,
+        SnsCommand::ListDeployedSnses(opts) => list_deployed_snses::exec(opts, network)?,
         SnsCommand::MakeProposal(opts) => {
-            let out = make_proposal::exec(auth, &canister_ids?, opts)?;
+            let out = make_proposal::exec(auth, &canister_ids?, opts, ingress_expiry)?;
             print_vec(qr, &out)?;
         }
         SnsCommand::MakeUpgradeCanisterProposal(opts) => {
-            let out = make_upgrade_canister_proposal::exec(auth, &canister_ids?, opts)?;
+            let out =
+                make_upgrade_canister_proposal::exec(auth, &canister_ids?, opts, ingress_expiry)?;
             print_vec(qr, &out)?;
         }
         SnsCommand::NeuronId(opts) => {
             neuron_id::exec(auth, opts)?;
         }
         SnsCommand::NeuronPermission(opts) => {
-            let out = neuron_permission::exec(auth, &canister_ids?, opts)?;
+            let out = neuron_permission::exec(auth, &canister_ids?, opts, ingress_expiry)?;
             print_vec(qr, &out)?;
         }
         SnsCommand::NewSaleTicket(opts) => {
-            let out = new_sale_ticket::exec(auth, &canister_ids?, opts)?;
+            let out = new_sale_ticket::exec(auth, &canister_ids?, opts, ingress_expiry)?;
             print_vec(qr, &out)?;
         }
         SnsCommand::RegisterVote(opts) => {
-            let out = register_vote::exec(auth, &canister_ids?, opts)?;
+            let out = register_vote::exec(auth, &canister_ids?, opts, ingress_expiry)?;
             print_vec(qr, &out)?;
         }
         SnsCommand::GetSaleParticipation(opts) => {
-            get_sale_participation::exec(auth, &canister_ids?, opts, fetch_root_key)?;
+            get_sale_participation::exec(auth, &canister_ids?, opts, network)?;
         }
         SnsCommand::SplitNeuron(opts) => {
-            let out = split_neuron::exec(auth, &canister_ids?, opts)?;
+            let out = split_neuron::exec(auth, &canister_ids?, opts, ingress_expiry)?;
             print_vec(qr, &out)?;
         }
         SnsCommand::StakeMaturity(opts) => {
-            let out = stake_maturity::exec(auth, &canister_ids?, opts)?;
+            let out = stake_maturity::exec(auth, &canister_ids?, opts, ingress_expiry)?;
             print_vec(qr, &out)?;
         }
         SnsCommand::StakeNeuron(opts) => {
-            let out = stake_neuron::exec(auth, &canister_ids?, opts)?;
+            let out = stake_neuron::exec(auth, &canister_ids?, opts, ingress_expiry)?;
             print_vec(qr, &out)?;
         }
-        SnsCommand::Status(opts) => status::exec(&canister_ids?, opts, fetch_root_key)?,
+        SnsCommand::Status(opts) => status::exec(&canister_ids?, opts, network)?,
         SnsCommand::Pay(opts) => {
-            let out = pay::exec(auth, &canister_ids?, opts)?;
+            let out = pay::exec(auth, &canister_ids?, opts, ingress_expiry, network)?;
             print_vec(qr, &out)?;
         }
         SnsCommand::Transfer(opts) => {
-            let out = transfer::exec(auth, &canister_ids?, opts)?;
+            let out = transfer::exec(auth, &canister_ids?, opts, ingress_expiry)?;
+            print_vec(qr, &out)?;
+        }
+        SnsCommand::TransferFrom(opts) => {
+            let out = transfer_from::exec(auth, &canister_ids?, opts, ingress_expiry)?;
             print_vec(qr, &out)?;
         }
     }
@@ -177,6 +209,8 @@ pub struct SnsCanisterIds {
     pub ledger_canister_id: Principal,
     pub root_canister_id: Principal,
     pub swap_canister_id: Principal,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index_canister_id: Option<Principal>,
 }
 
 #[derive(Clone)]
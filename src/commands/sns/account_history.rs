@@ -0,0 +1,66 @@
+use crate::{
+    commands::{
+        account_history::GetAccountTransactionsArgs, get_account, send::submit_unsigned_ingress,
+        SendingOpts,
+    },
+    lib::{network::Network, AuthInfo, ParsedAccount, ParsedSubaccount, ROLE_ICRC1_INDEX},
+    AnyhowResult, AUTH_FLAGS,
+};
+use anyhow::Context;
+use candid::{Encode, Nat};
+use clap::Parser;
+
+use super::SnsCanisterIds;
+
+/// Queries the transaction history of an SNS ledger account, newest first, using the SNS index canister.
+///
+/// The `--of` parameter is required if a signing key is not provided. The canister IDs file must
+/// include `index_canister_id`.
+#[derive(Parser)]
+pub struct AccountHistoryOpts {
+    /// The account to query. Optional if a key is used.
+    #[arg(long, required_unless_present_any = AUTH_FLAGS)]
+    of: Option<ParsedAccount>,
+
+    /// The subaccount of the account to query.
+    #[arg(long)]
+    subaccount: Option<ParsedSubaccount>,
+
+    /// Only show transactions older than the transaction with this id.
+    #[arg(long)]
+    start: Option<u64>,
+
+    /// The maximum number of transactions to show.
+    #[arg(long, default_value_t = 20)]
+    max_results: u64,
+
+    #[command(flatten)]
+    sending_opts: SendingOpts,
+}
+
+#[tokio::main]
+pub async fn exec(
+    auth: &AuthInfo,
+    sns_canister_ids: &SnsCanisterIds,
+    opts: AccountHistoryOpts,
+    network: &Network,
+) -> AnyhowResult {
+    let index_canister_id = sns_canister_ids
+        .index_canister_id
+        .context("The SNS canister IDs file does not contain `index_canister_id`")?;
+    let account = get_account(Some(auth), opts.of, opts.subaccount)?;
+    let args = Encode!(&GetAccountTransactionsArgs {
+        account,
+        start: opts.start.map(Nat::from),
+        max_results: opts.max_results.into(),
+    })?;
+    submit_unsigned_ingress(
+        index_canister_id,
+        ROLE_ICRC1_INDEX,
+        "get_account_transactions",
+        args,
+        opts.sending_opts,
+        network,
+    )
+    .await
+}
@@ -0,0 +1,60 @@
+use crate::{
+    commands::{get_account, send::submit_unsigned_ingress, SendingOpts},
+    lib::{network::Network, AuthInfo, ParsedAccount, ParsedSubaccount, ROLE_ICRC1_LEDGER},
+    AnyhowResult, AUTH_FLAGS,
+};
+use candid::Encode;
+use clap::Parser;
+use icrc_ledger_types::icrc2::allowance::AllowanceArgs;
+
+use super::SnsCanisterIds;
+
+/// Sends a ledger allowance query call, showing how many tokens a spender may still transfer.
+///
+/// The `--of` parameter is required if a signing key is not provided.
+#[derive(Parser)]
+pub struct AllowanceOpts {
+    /// The account allowed to spend the tokens.
+    spender: ParsedAccount,
+
+    /// The subaccount of the spender.
+    #[arg(long)]
+    spender_subaccount: Option<ParsedSubaccount>,
+
+    /// The account that approved the spending. Optional if a key is used.
+    #[arg(long, required_unless_present_any = AUTH_FLAGS)]
+    of: Option<ParsedAccount>,
+
+    /// The subaccount of the account that approved the spending.
+    #[arg(long)]
+    subaccount: Option<ParsedSubaccount>,
+
+    #[command(flatten)]
+    sending_opts: SendingOpts,
+}
+
+#[tokio::main]
+pub async fn exec(
+    auth: &AuthInfo,
+    sns_canister_ids: &SnsCanisterIds,
+    opts: AllowanceOpts,
+    network: &Network,
+) -> AnyhowResult {
+    let ledger_canister_id = sns_canister_ids.ledger_canister_id;
+    let args = AllowanceArgs {
+        account: get_account(Some(auth), opts.of, opts.subaccount)?,
+        spender: get_account(None, Some(opts.spender), opts.spender_subaccount)?,
+    };
+
+    submit_unsigned_ingress(
+        ledger_canister_id,
+        ROLE_ICRC1_LEDGER,
+        "icrc2_allowance",
+        Encode!(&args)?,
+        opts.sending_opts,
+        network,
+    )
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use crate::commands::{approve::parse_expiry, get_account, transfer::parse_tokens};
+use crate::lib::{now_nanos, ParsedAccount, ROLE_ICRC1_LEDGER};
+use crate::lib::{
+    signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
+    AnyhowResult, AuthInfo, IngressExpiryOpts, ParsedSubaccount,
+};
+use candid::Encode;
+use clap::Parser;
+use icp_ledger::Tokens;
+use icrc_ledger_types::{icrc1::transfer::Memo, icrc2::approve::ApproveArgs};
+
+use super::SnsCanisterIds;
+
+/// Signs a ledger approval, allowing another account to spend tokens from one of your accounts.
+#[derive(Parser)]
+pub struct ApproveOpts {
+    /// The account allowed to spend the tokens.
+    pub spender: ParsedAccount,
+
+    /// The subaccount of the spender.
+    #[arg(long)]
+    pub spender_subaccount: Option<ParsedSubaccount>,
+
+    /// The subaccount to approve spending from.
+    #[arg(long)]
+    pub from_subaccount: Option<ParsedSubaccount>,
+
+    /// Amount of governance tokens the spender may transfer (with up to 8 decimal digits after decimal point)
+    #[arg(long, value_parser = parse_tokens)]
+    pub amount: Tokens,
+
+    /// Only replace the current allowance if it is exactly this amount.
+    #[arg(long, value_parser = parse_tokens)]
+    pub expected_allowance: Option<Tokens>,
+
+    /// When the approval expires, as an RFC 3339 timestamp (e.g. 2025-01-01T00:00:00Z).
+    #[arg(long, value_parser = parse_expiry)]
+    pub expires_at: Option<u64>,
+
+    /// An arbitrary number associated with a transaction. The default is 0
+    #[arg(long)]
+    pub memo: Option<u64>,
+
+    /// The amount that the caller pays for the transaction, default is 0.0001 tokens. Specify this amount
+    /// when using an SNS that sets its own transaction fee
+    #[arg(long, value_parser = parse_tokens)]
+    pub fee: Option<Tokens>,
+}
+
+pub fn exec(
+    auth: &AuthInfo,
+    sns_canister_ids: &SnsCanisterIds,
+    opts: ApproveOpts,
+    ingress_expiry: IngressExpiryOpts,
+) -> AnyhowResult<Vec<IngressWithRequestId>> {
+    let ledger_canister_id = sns_canister_ids.ledger_canister_id;
+    let spender = get_account(None, Some(opts.spender), opts.spender_subaccount)?;
+    let args = ApproveArgs {
+        from_subaccount: opts.from_subaccount.map(|x| x.0 .0),
+        spender,
+        amount: opts.amount.get_e8s().into(),
+        expected_allowance: opts.expected_allowance.map(|x| x.get_e8s().into()),
+        expires_at: opts.expires_at,
+        fee: opts.fee.map(|fee| fee.get_e8s().into()),
+        memo: opts.memo.map(Memo::from),
+        created_at_time: Some(now_nanos()),
+    };
+
+    let msg = sign_ingress_with_request_status_query(
+        auth,
+        ingress_expiry,
+        ledger_canister_id,
+        ROLE_ICRC1_LEDGER,
+        "icrc2_approve",
+        Encode!(&args)?,
+    )?;
+
+    Ok(vec![msg])
+}
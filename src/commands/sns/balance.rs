@@ -1,6 +1,6 @@
 use crate::{
     commands::{get_account, send::submit_unsigned_ingress, SendingOpts},
-    lib::{AuthInfo, ParsedAccount, ParsedSubaccount, ROLE_ICRC1_LEDGER},
+    lib::{network::Network, AuthInfo, ParsedAccount, ParsedSubaccount, ROLE_ICRC1_LEDGER},
     AnyhowResult, AUTH_FLAGS,
 };
 use candid::Encode;
@@ -30,7 +30,7 @@ pub async fn exec(
     auth: &AuthInfo,
     sns_canister_ids: &SnsCanisterIds,
     opts: BalanceOpts,
-    fetch_root_key: bool,
+    network: &Network,
 ) -> AnyhowResult {
     let ledger_canister_id = sns_canister_ids.ledger_canister_id;
     let account = get_account(Some(auth), opts.of, opts.subaccount)?;
@@ -41,7 +41,7 @@ pub async fn exec(
         "icrc1_balance_of",
         Encode!(&account)?,
         opts.sending_opts,
-        fetch_root_key,
+        network,
     )
     .await?;
 
@@ -1,7 +1,7 @@
 use crate::{
     lib::{
         signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
-        AuthInfo, ROLE_SNS_GOVERNANCE,
+        AuthInfo, IngressExpiryOpts, ROLE_SNS_GOVERNANCE,
     },
     AnyhowResult,
 };
@@ -52,6 +52,7 @@ pub fn exec(
     auth: &AuthInfo,
     sns_canister_ids: &SnsCanisterIds,
     opts: ConfigureDissolveDelayOpts,
+    ingress_expiry: IngressExpiryOpts,
 ) -> AnyhowResult<Vec<IngressWithRequestId>> {
     require_mutually_exclusive(
         opts.start_dissolving,
@@ -95,6 +96,7 @@ pub fn exec(
 
     let msg = sign_ingress_with_request_status_query(
         auth,
+        ingress_expiry,
         governance_canister_id,
         ROLE_SNS_GOVERNANCE,
         "manage_neuron",
@@ -9,7 +9,8 @@ use crate::{
     commands::get_account,
     lib::{
         signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
-        AnyhowResult, AuthInfo, ParsedAccount, ParsedSubaccount, ROLE_SNS_GOVERNANCE,
+        AnyhowResult, AuthInfo, IngressExpiryOpts, ParsedAccount, ParsedSubaccount,
+        ROLE_SNS_GOVERNANCE,
     },
     AUTH_FLAGS,
 };
@@ -36,6 +37,7 @@ pub fn exec(
     auth: &AuthInfo,
     canister_ids: &SnsCanisterIds,
     opts: DisburseMaturityOpts,
+    ingress_expiry: IngressExpiryOpts,
 ) -> AnyhowResult<Vec<IngressWithRequestId>> {
     let account = get_account(Some(auth), opts.to, opts.subaccount)?;
     let args = ManageNeuron {
@@ -47,6 +49,7 @@ pub fn exec(
     };
     let message = sign_ingress_with_request_status_query(
         auth,
+        ingress_expiry,
         canister_ids.governance_canister_id,
         ROLE_SNS_GOVERNANCE,
         "manage_neuron",
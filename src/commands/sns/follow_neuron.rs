@@ -7,7 +7,7 @@ use ic_sns_governance::pb::v1::{
 
 use crate::lib::{
     signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
-    AnyhowResult, AuthInfo, ROLE_SNS_GOVERNANCE,
+    AnyhowResult, AuthInfo, IngressExpiryOpts, ROLE_SNS_GOVERNANCE,
 };
 
 use super::{ParsedSnsNeuron, SnsCanisterIds};
@@ -62,6 +62,7 @@ pub fn exec(
     auth: &AuthInfo,
     canister_ids: &SnsCanisterIds,
     opts: FollowNeuronOpts,
+    ingress_expiry: IngressExpiryOpts,
 ) -> AnyhowResult<Vec<IngressWithRequestId>> {
     let function_id = if let Some(id) = opts.function_id {
         id
@@ -85,6 +86,7 @@ pub fn exec(
     };
     let message = sign_ingress_with_request_status_query(
         auth,
+        ingress_expiry,
         canister_ids.governance_canister_id,
         ROLE_SNS_GOVERNANCE,
         "manage_neuron",
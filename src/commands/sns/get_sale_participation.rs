@@ -4,7 +4,7 @@ use ic_sns_swap::pb::v1::GetBuyerStateRequest;
 
 use crate::{
     commands::{get_principal, send::submit_unsigned_ingress, SendingOpts},
-    lib::{AnyhowResult, AuthInfo, ROLE_SNS_SWAP},
+    lib::{network::Network, AnyhowResult, AuthInfo, ROLE_SNS_SWAP},
     AUTH_FLAGS,
 };
 
@@ -26,7 +26,7 @@ pub async fn exec(
     auth: &AuthInfo,
     canister_ids: &SnsCanisterIds,
     opts: GetSaleParticipationOpts,
-    fetch_root_key: bool,
+    network: &Network,
 ) -> AnyhowResult {
     let principal = if let Some(principal) = opts.principal {
         principal
@@ -42,7 +42,7 @@ pub async fn exec(
         "get_buyer_state",
         Encode!(&message)?,
         opts.sending_opts,
-        fetch_root_key,
+        network,
     )
     .await?;
     Ok(())
@@ -6,7 +6,7 @@ use crate::{
     commands::get_principal,
     lib::{
         signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
-        AnyhowResult, AuthInfo, ROLE_SNS_SWAP,
+        AnyhowResult, AuthInfo, IngressExpiryOpts, ROLE_SNS_SWAP,
     },
 };
 
@@ -27,6 +27,7 @@ pub fn exec(
     auth: &AuthInfo,
     sns_canister_ids: &SnsCanisterIds,
     opts: GetSwapRefundOpts,
+    ingress_expiry: IngressExpiryOpts,
 ) -> AnyhowResult<Vec<IngressWithRequestId>> {
     let principal = if let Some(principal) = opts.principal {
         principal
@@ -38,6 +39,7 @@ pub fn exec(
     };
     let req = sign_ingress_with_request_status_query(
         auth,
+        ingress_expiry,
         sns_canister_ids.swap_canister_id,
         ROLE_SNS_SWAP,
         "error_refund_icp",
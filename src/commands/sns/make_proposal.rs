@@ -4,7 +4,7 @@ use crate::{
     lib::{
         get_local_candid,
         signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
-        AuthInfo, ROLE_SNS_GOVERNANCE,
+        AuthInfo, IngressExpiryOpts, ROLE_SNS_GOVERNANCE,
     },
     AnyhowResult,
 };
@@ -57,6 +57,7 @@ pub fn exec(
     auth: &AuthInfo,
     sns_canister_ids: &SnsCanisterIds,
     opts: MakeProposalOpts,
+    ingress_expiry: IngressExpiryOpts,
 ) -> AnyhowResult<Vec<IngressWithRequestId>> {
     let neuron_id = opts.proposer_neuron_id.0;
     let neuron_subaccount = neuron_id.subaccount().map_err(Error::msg)?;
@@ -75,6 +76,7 @@ pub fn exec(
 
     let msg = sign_ingress_with_request_status_query(
         auth,
+        ingress_expiry,
         governance_canister_id,
         ROLE_SNS_GOVERNANCE,
         "manage_neuron",
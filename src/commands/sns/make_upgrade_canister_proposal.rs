@@ -3,7 +3,7 @@ use std::path::PathBuf;
 use crate::{
     lib::{
         signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
-        AuthInfo, ROLE_SNS_GOVERNANCE,
+        AuthInfo, IngressExpiryOpts, ROLE_SNS_GOVERNANCE,
     },
     AnyhowResult,
 };
@@ -73,6 +73,7 @@ pub fn exec(
     auth: &AuthInfo,
     sns_canister_ids: &SnsCanisterIds,
     opts: MakeUpgradeCanisterProposalOpts,
+    ingress_expiry: IngressExpiryOpts,
 ) -> AnyhowResult<Vec<IngressWithRequestId>> {
     let MakeUpgradeCanisterProposalOpts {
         proposer_neuron_id,
@@ -151,6 +152,7 @@ pub fn exec(
 
     let msg = sign_ingress_with_request_status_query(
         auth,
+        ingress_expiry,
         governance_canister_id,
         ROLE_SNS_GOVERNANCE,
         "manage_neuron",
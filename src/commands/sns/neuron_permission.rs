@@ -8,7 +8,7 @@ use ic_sns_governance::pb::v1::{
 
 use crate::lib::{
     signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
-    AnyhowResult, AuthInfo, ROLE_SNS_GOVERNANCE,
+    AnyhowResult, AuthInfo, IngressExpiryOpts, ROLE_SNS_GOVERNANCE,
 };
 
 use super::{ParsedSnsNeuron, SnsCanisterIds};
@@ -50,6 +50,7 @@ pub fn exec(
     auth: &AuthInfo,
     canister_ids: &SnsCanisterIds,
     opts: NeuronPermissionOpts,
+    ingress_expiry: IngressExpiryOpts,
 ) -> AnyhowResult<Vec<IngressWithRequestId>> {
     let neuron_subaccount = opts.neuron_id.0.subaccount().map_err(|e| anyhow!(e))?;
     let permission_list = NeuronPermissionList {
@@ -75,6 +76,7 @@ pub fn exec(
     };
     let msg = sign_ingress_with_request_status_query(
         auth,
+        ingress_expiry,
         canister_ids.governance_canister_id,
         ROLE_SNS_GOVERNANCE,
         "manage_neuron",
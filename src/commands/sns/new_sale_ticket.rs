@@ -4,7 +4,7 @@ use ic_sns_swap::pb::v1::NewSaleTicketRequest;
 
 use crate::lib::{
     signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
-    AnyhowResult, AuthInfo, ParsedSubaccount, ROLE_SNS_SWAP,
+    AnyhowResult, AuthInfo, IngressExpiryOpts, ParsedSubaccount, ROLE_SNS_SWAP,
 };
 
 use super::SnsCanisterIds;
@@ -25,6 +25,7 @@ pub fn exec(
     auth: &AuthInfo,
     sns_canister_ids: &SnsCanisterIds,
     opts: NewSaleTicketOpts,
+    ingress_expiry: IngressExpiryOpts,
 ) -> AnyhowResult<Vec<IngressWithRequestId>> {
     let request = NewSaleTicketRequest {
         amount_icp_e8s: opts.amount_icp_e8s,
@@ -32,6 +33,7 @@ pub fn exec(
     };
     let message = sign_ingress_with_request_status_query(
         auth,
+        ingress_expiry,
         sns_canister_ids.swap_canister_id,
         ROLE_SNS_SWAP,
         "new_sale_ticket",
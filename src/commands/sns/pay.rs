@@ -6,9 +6,9 @@ use icp_ledger::{AccountIdentifier, Memo, Subaccount, TimeStamp, Tokens, Transfe
 
 use crate::lib::ParsedSubaccount;
 use crate::lib::{
-    ledger_canister_id,
+    network::Network,
     signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
-    AnyhowResult, AuthInfo, ROLE_NNS_LEDGER, ROLE_SNS_SWAP,
+    AnyhowResult, AuthInfo, IngressExpiryOpts, ROLE_NNS_LEDGER, ROLE_SNS_SWAP,
 };
 
 use super::SnsCanisterIds;
@@ -50,6 +50,8 @@ pub fn exec(
     auth: &AuthInfo,
     sns_canister_ids: &SnsCanisterIds,
     opts: PayOpts,
+    ingress_expiry: IngressExpiryOpts,
+    network: &Network,
 ) -> AnyhowResult<Vec<IngressWithRequestId>> {
     let controller = crate::lib::get_principal(auth)?;
     let mut messages = vec![];
@@ -69,7 +71,8 @@ pub fn exec(
         };
         messages.push(sign_ingress_with_request_status_query(
             auth,
-            ledger_canister_id(),
+            ingress_expiry,
+            network.ledger_canister_id(),
             ROLE_NNS_LEDGER,
             "transfer",
             Encode!(&request)?,
@@ -81,6 +84,7 @@ pub fn exec(
     };
     messages.push(sign_ingress_with_request_status_query(
         auth,
+        ingress_expiry,
         sns_canister_ids.swap_canister_id,
         ROLE_SNS_SWAP,
         "refresh_buyer_tokens",
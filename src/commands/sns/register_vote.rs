@@ -1,7 +1,7 @@
 use crate::{
     lib::{
         signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
-        AuthInfo, ROLE_SNS_GOVERNANCE,
+        AuthInfo, IngressExpiryOpts, ROLE_SNS_GOVERNANCE,
     },
     AnyhowResult,
 };
@@ -36,6 +36,7 @@ pub fn exec(
     auth: &AuthInfo,
     sns_canister_ids: &SnsCanisterIds,
     opts: RegisterVoteOpts,
+    ingress_expiry: IngressExpiryOpts,
 ) -> AnyhowResult<Vec<IngressWithRequestId>> {
     let neuron_subaccount = opts.neuron_id.0.subaccount().map_err(Error::msg)?;
     let governance_canister_id = sns_canister_ids.governance_canister_id;
@@ -60,6 +61,7 @@ pub fn exec(
 
     let msg = sign_ingress_with_request_status_query(
         auth,
+        ingress_expiry,
         governance_canister_id,
         ROLE_SNS_GOVERNANCE,
         "manage_neuron",
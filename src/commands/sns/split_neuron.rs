@@ -10,7 +10,7 @@ use crate::{
     commands::transfer::parse_tokens,
     lib::{
         signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
-        AnyhowResult, AuthInfo, ROLE_SNS_GOVERNANCE,
+        AnyhowResult, AuthInfo, IngressExpiryOpts, ROLE_SNS_GOVERNANCE,
     },
 };
 
@@ -33,6 +33,7 @@ pub fn exec(
     auth: &AuthInfo,
     canister_ids: &SnsCanisterIds,
     opts: SplitNeuronOpts,
+    ingress_expiry: IngressExpiryOpts,
 ) -> AnyhowResult<Vec<IngressWithRequestId>> {
     let args = ManageNeuron {
         subaccount: opts.neuron_id.0.id,
@@ -43,6 +44,7 @@ pub fn exec(
     };
     let message = sign_ingress_with_request_status_query(
         auth,
+        ingress_expiry,
         canister_ids.governance_canister_id,
         ROLE_SNS_GOVERNANCE,
         "manage_neuron",
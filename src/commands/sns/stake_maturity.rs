@@ -8,7 +8,7 @@ use ic_sns_governance::pb::v1::{
 
 use crate::lib::{
     signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
-    AnyhowResult, AuthInfo, ROLE_SNS_GOVERNANCE,
+    AnyhowResult, AuthInfo, IngressExpiryOpts, ROLE_SNS_GOVERNANCE,
 };
 
 use super::{ParsedSnsNeuron, SnsCanisterIds};
@@ -30,6 +30,7 @@ pub fn exec(
     auth: &AuthInfo,
     sns_canister_ids: &SnsCanisterIds,
     opts: StakeMaturityOpts,
+    ingress_expiry: IngressExpiryOpts,
 ) -> AnyhowResult<Vec<IngressWithRequestId>> {
     let neuron_subaccount = opts.neuron_id.0.subaccount().map_err(Error::msg)?;
 
@@ -44,6 +45,7 @@ pub fn exec(
 
     let message = sign_ingress_with_request_status_query(
         auth,
+        ingress_expiry,
         governance_canister_id,
         ROLE_SNS_GOVERNANCE,
         "manage_neuron",
@@ -5,8 +5,8 @@ use crate::lib::signing::{
 };
 use crate::{
     lib::{
-        signing::IngressWithRequestId, AuthInfo, ParsedSubaccount, ROLE_ICRC1_LEDGER,
-        ROLE_SNS_GOVERNANCE,
+        signing::IngressWithRequestId, AuthInfo, IngressExpiryOpts, ParsedSubaccount,
+        ROLE_ICRC1_LEDGER, ROLE_SNS_GOVERNANCE,
     },
     AnyhowResult,
 };
@@ -65,6 +65,7 @@ pub fn exec(
     auth: &AuthInfo,
     sns_canister_ids: &SnsCanisterIds,
     opts: StakeNeuronOpts,
+    ingress_expiry: IngressExpiryOpts,
 ) -> AnyhowResult<Vec<IngressWithRequestId>> {
     let controller = crate::lib::get_principal(auth)?;
     let neuron_subaccount = ledger::compute_neuron_staking_subaccount(controller.into(), opts.memo);
@@ -90,6 +91,7 @@ pub fn exec(
 
         let msg = sign_ingress_with_request_status_query(
             auth,
+            ingress_expiry,
             sns_canister_ids.ledger_canister_id,
             ROLE_ICRC1_LEDGER,
             "icrc1_transfer",
@@ -111,6 +113,7 @@ pub fn exec(
 
     messages.push(sign_staking_ingress_with_request_status_query(
         auth,
+        ingress_expiry,
         governance_canister_id,
         ROLE_SNS_GOVERNANCE,
         "manage_neuron",
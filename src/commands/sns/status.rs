@@ -4,7 +4,7 @@ use ic_sns_root::GetSnsCanistersSummaryRequest;
 
 use crate::{
     commands::{send::submit_unsigned_ingress, SendingOpts},
-    lib::{AnyhowResult, ROLE_SNS_ROOT},
+    lib::{network::Network, AnyhowResult, ROLE_SNS_ROOT},
 };
 
 use super::SnsCanisterIds;
@@ -18,7 +18,7 @@ pub struct StatusOpts {
 }
 
 #[tokio::main]
-pub async fn exec(ids: &SnsCanisterIds, opts: StatusOpts, fetch_root_key: bool) -> AnyhowResult {
+pub async fn exec(ids: &SnsCanisterIds, opts: StatusOpts, network: &Network) -> AnyhowResult {
     let root_canister_id = ids.root_canister_id;
     let arg = Encode!(&GetSnsCanistersSummaryRequest {
         update_canister_list: None,
@@ -29,7 +29,7 @@ pub async fn exec(ids: &SnsCanisterIds, opts: StatusOpts, fetch_root_key: bool)
         "get_sns_canisters_summary",
         arg,
         opts.sending_opts,
-        fetch_root_key,
+        network,
     )
     .await?;
     Ok(())
@@ -3,7 +3,7 @@ use crate::commands::transfer::parse_tokens;
 use crate::lib::{now_nanos, ParsedAccount, ROLE_ICRC1_LEDGER};
 use crate::lib::{
     signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
-    AnyhowResult, AuthInfo, ParsedSubaccount,
+    AnyhowResult, AuthInfo, IngressExpiryOpts, ParsedSubaccount,
 };
 use candid::Encode;
 use clap::Parser;
@@ -44,6 +44,7 @@ pub fn exec(
     auth: &AuthInfo,
     sns_canister_ids: &SnsCanisterIds,
     opts: TransferOpts,
+    ingress_expiry: IngressExpiryOpts,
 ) -> AnyhowResult<Vec<IngressWithRequestId>> {
     let amount = opts.amount.get_e8s().into();
     let fee = opts.fee.map(|fee| fee.get_e8s().into());
@@ -61,6 +62,7 @@ pub fn exec(
 
     let msg = sign_ingress_with_request_status_query(
         auth,
+        ingress_expiry,
         ledger_canister_id,
         ROLE_ICRC1_LEDGER,
         "icrc1_transfer",
@@ -0,0 +1,78 @@
+use crate::commands::get_account;
+use crate::commands::transfer::parse_tokens;
+use crate::lib::{now_nanos, ParsedAccount, ROLE_ICRC1_LEDGER};
+use crate::lib::{
+    signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
+    AnyhowResult, AuthInfo, IngressExpiryOpts, ParsedSubaccount,
+};
+use candid::Encode;
+use clap::Parser;
+use icp_ledger::Tokens;
+use icrc_ledger_types::{icrc1::transfer::Memo, icrc2::transfer_from::TransferFromArgs};
+
+use super::SnsCanisterIds;
+
+/// Signs a ledger transfer out of an account that approved you as a spender.
+#[derive(Parser)]
+pub struct TransferFromOpts {
+    /// The account to transfer from.
+    pub from: ParsedAccount,
+
+    /// The subaccount to transfer from.
+    #[arg(long)]
+    pub from_subaccount: Option<ParsedSubaccount>,
+
+    /// The destination account. Defaults to your own account.
+    #[arg(long)]
+    pub to: Option<ParsedAccount>,
+
+    /// The subaccount of the destination account.
+    #[arg(long)]
+    pub to_subaccount: Option<ParsedSubaccount>,
+
+    /// Your subaccount that was approved as the spender.
+    #[arg(long)]
+    pub spender_subaccount: Option<ParsedSubaccount>,
+
+    /// Amount of governance tokens to transfer (with up to 8 decimal digits after decimal point)
+    #[arg(long, value_parser = parse_tokens)]
+    pub amount: Tokens,
+
+    /// An arbitrary number associated with a transaction. The default is 0
+    #[arg(long)]
+    pub memo: Option<u64>,
+
+    /// The amount that the caller pays for the transaction, default is 0.0001 tokens. Specify this amount
+    /// when using an SNS that sets its own transaction fee
+    #[arg(long, value_parser = parse_tokens)]
+    pub fee: Option<Tokens>,
+}
+
+pub fn exec(
+    auth: &AuthInfo,
+    sns_canister_ids: &SnsCanisterIds,
+    opts: TransferFromOpts,
+    ingress_expiry: IngressExpiryOpts,
+) -> AnyhowResult<Vec<IngressWithRequestId>> {
+    let ledger_canister_id = sns_canister_ids.ledger_canister_id;
+    let args = TransferFromArgs {
+        spender_subaccount: opts.spender_subaccount.map(|x| x.0 .0),
+        from: get_account(None, Some(opts.from), opts.from_subaccount)?,
+        to: get_account(Some(auth), opts.to, opts.to_subaccount)?,
+        amount: opts.amount.get_e8s().into(),
+        fee: opts.fee.map(|fee| fee.get_e8s().into()),
+        memo: opts.memo.map(Memo::from),
+        created_at_time: Some(now_nanos()),
+    };
+
+    let msg = sign_ingress_with_request_status_query(
+        auth,
+        ingress_expiry,
+        ledger_canister_id,
+        ROLE_ICRC1_LEDGER,
+        "icrc2_transfer_from",
+        Encode!(&args)?,
+    )?;
+
+    Ok(vec![msg])
+}
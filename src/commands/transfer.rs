@@ -1,7 +1,7 @@
 use crate::lib::{
-    ledger_canister_id,
+    network::Network,
     signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
-    AnyhowResult, AuthInfo,
+    AnyhowResult, AuthInfo, IngressExpiryOpts,
 };
 use crate::lib::{
     now_nanos, ParsedNnsAccount, ParsedSubaccount, ROLE_ICRC1_LEDGER, ROLE_NNS_LEDGER,
@@ -35,7 +35,12 @@ pub struct TransferOpts {
     pub from_subaccount: Option<ParsedSubaccount>,
 }
 
-pub fn exec(auth: &AuthInfo, opts: TransferOpts) -> AnyhowResult<Vec<IngressWithRequestId>> {
+pub fn exec(
+    auth: &AuthInfo,
+    opts: TransferOpts,
+    ingress_expiry: IngressExpiryOpts,
+    network: &Network,
+) -> AnyhowResult<Vec<IngressWithRequestId>> {
     let amount = opts.amount;
     let fee = opts.fee.unwrap_or(DEFAULT_TRANSFER_FEE);
     let memo = Memo(opts.memo.unwrap_or(0));
@@ -53,7 +58,8 @@ pub fn exec(auth: &AuthInfo, opts: TransferOpts) -> AnyhowResult<Vec<IngressWith
 
             let msg = sign_ingress_with_request_status_query(
                 auth,
-                ledger_canister_id(),
+                ingress_expiry,
+                network.ledger_canister_id(),
                 ROLE_NNS_LEDGER,
                 "transfer",
                 args,
@@ -71,7 +77,8 @@ pub fn exec(auth: &AuthInfo, opts: TransferOpts) -> AnyhowResult<Vec<IngressWith
             })?;
             let msg = sign_ingress_with_request_status_query(
                 auth,
-                ledger_canister_id(),
+                ingress_expiry,
+                network.ledger_canister_id(),
                 ROLE_ICRC1_LEDGER,
                 "icrc1_transfer",
                 args,
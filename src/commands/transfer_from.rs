@@ -0,0 +1,72 @@
+use crate::commands::{get_account, transfer::parse_tokens};
+use crate::lib::{
+    network::Network,
+    now_nanos,
+    signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
+    AnyhowResult, AuthInfo, IngressExpiryOpts, ParsedAccount, ParsedSubaccount, ROLE_ICRC1_LEDGER,
+};
+use candid::Encode;
+use clap::Parser;
+use icp_ledger::Tokens;
+use icrc_ledger_types::{icrc1::transfer::Memo, icrc2::transfer_from::TransferFromArgs};
+
+/// Signs a message transferring ICP out of an account that approved you as a spender.
+#[derive(Parser)]
+pub struct TransferFromOpts {
+    /// The account to transfer ICP from.
+    pub from: ParsedAccount,
+
+    /// The subaccount to transfer ICP from.
+    #[arg(long)]
+    pub from_subaccount: Option<ParsedSubaccount>,
+
+    /// The account to transfer ICP to. Defaults to your own account.
+    #[arg(long)]
+    pub to: Option<ParsedAccount>,
+
+    /// The subaccount to transfer ICP to.
+    #[arg(long)]
+    pub to_subaccount: Option<ParsedSubaccount>,
+
+    /// Your subaccount that was approved as the spender.
+    #[arg(long)]
+    pub spender_subaccount: Option<ParsedSubaccount>,
+
+    /// Amount of ICPs to transfer (with up to 8 decimal digits after comma).
+    #[arg(long, value_parser = parse_tokens)]
+    pub amount: Tokens,
+
+    /// Reference number, default is none.
+    #[arg(long)]
+    pub memo: Option<u64>,
+
+    /// Transaction fee, default is 0.0001 ICP.
+    #[arg(long, value_parser = parse_tokens)]
+    pub fee: Option<Tokens>,
+}
+
+pub fn exec(
+    auth: &AuthInfo,
+    opts: TransferFromOpts,
+    ingress_expiry: IngressExpiryOpts,
+    network: &Network,
+) -> AnyhowResult<Vec<IngressWithRequestId>> {
+    let args = TransferFromArgs {
+        spender_subaccount: opts.spender_subaccount.map(|x| x.0 .0),
+        from: get_account(None, Some(opts.from), opts.from_subaccount)?,
+        to: get_account(Some(auth), opts.to, opts.to_subaccount)?,
+        amount: opts.amount.get_e8s().into(),
+        fee: opts.fee.map(|x| x.get_e8s().into()),
+        memo: opts.memo.map(Memo::from),
+        created_at_time: Some(now_nanos()),
+    };
+    let msg = sign_ingress_with_request_status_query(
+        auth,
+        ingress_expiry,
+        network.ledger_canister_id(),
+        ROLE_ICRC1_LEDGER,
+        "icrc2_transfer_from",
+        Encode!(&args)?,
+    )?;
+    Ok(vec![msg])
+}
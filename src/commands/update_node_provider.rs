@@ -1,6 +1,9 @@
 use crate::{
-    lib::signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
-    lib::{governance_canister_id, AnyhowResult, AuthInfo, ROLE_NNS_GOVERNANCE},
+    lib::{network::Network, AnyhowResult, AuthInfo, ROLE_NNS_GOVERNANCE},
+    lib::{
+        signing::{sign_ingress_with_request_status_query, IngressWithRequestId},
+        IngressExpiryOpts,
+    },
 };
 use anyhow::{anyhow, Context};
 use candid::{CandidType, Encode};
@@ -26,6 +29,8 @@ pub struct UpdateNodeProviderOpts {
 pub fn exec(
     auth: &AuthInfo,
     opts: UpdateNodeProviderOpts,
+    ingress_expiry: IngressExpiryOpts,
+    network: &Network,
 ) -> AnyhowResult<Vec<IngressWithRequestId>> {
     let reward_account = icp_ledger::AccountIdentifier::from_hex(&opts.reward_account)
         .map_err(|e| anyhow!(e))
@@ -37,7 +42,8 @@ pub fn exec(
     })?;
     Ok(vec![sign_ingress_with_request_status_query(
         auth,
-        governance_canister_id(),
+        ingress_expiry,
+        network.governance_canister_id(),
         ROLE_NNS_GOVERNANCE,
         "update_node_provider",
         args,
@@ -0,0 +1,59 @@
+use crate::lib::{
+    read_from_file,
+    signing::{Ingress, IngressWithRequestId},
+    AnyhowResult,
+};
+use anyhow::{anyhow, bail, Context};
+use clap::Parser;
+use ic_agent::RequestId;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+
+/// Verifies the signatures and request IDs of a signed message or a set of messages, without sending them.
+#[derive(Parser)]
+pub struct VerifyOpts {
+    /// Path to the signed message (`-` for stdin)
+    file_name: Option<PathBuf>,
+}
+
+pub fn exec(opts: VerifyOpts) -> AnyhowResult {
+    let file_name = if let Some(file_name) = &opts.file_name {
+        file_name.as_path()
+    } else if !std::io::stdin().is_terminal() {
+        "-".as_ref()
+    } else {
+        bail!("File name must be provided if not being piped")
+    };
+    let json = read_from_file(file_name)?;
+    if let Ok(val) = serde_json::from_str::<Ingress>(&json) {
+        let request_id = val.verify()?;
+        print_verified(&val, request_id)?;
+    } else if let Ok(vals) = serde_json::from_str::<Vec<Ingress>>(&json) {
+        for (i, msg) in vals.iter().enumerate() {
+            let request_id = msg
+                .verify()
+                .with_context(|| format!("Message #{} failed verification", i + 1))?;
+            print_verified(msg, request_id)?;
+        }
+    } else if let Ok(vals) = serde_json::from_str::<Vec<IngressWithRequestId>>(&json) {
+        for (i, tx) in vals.iter().enumerate() {
+            let request_id = tx
+                .verify()
+                .with_context(|| format!("Message #{} failed verification", i + 1))?;
+            print_verified(&tx.ingress, request_id)?;
+        }
+    } else {
+        return Err(anyhow!("Invalid JSON content"));
+    }
+    Ok(())
+}
+
+fn print_verified(message: &Ingress, request_id: RequestId) -> AnyhowResult {
+    let (sender, canister_id, method_name, _, _) = message.parse()?;
+    println!("Verified message with\n");
+    println!("  Request ID:  0x{}", String::from(request_id));
+    println!("  Sender:      {sender}");
+    println!("  Canister id: {canister_id}");
+    println!("  Method name: {method_name}\n");
+    Ok(())
+}
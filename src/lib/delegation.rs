@@ -0,0 +1,125 @@
+//! Delegation chains, in the JSON format of agent-js's `DelegationChain.toJSON()`.
+
+use anyhow::{ensure, Context};
+use candid::Principal;
+use ic_agent::identity::{Delegation, SignedDelegation};
+use serde::Deserialize;
+
+use super::{now_nanos, AnyhowResult};
+
+/// A chain of delegations from a root key, usually an Internet Identity, to a session key.
+///
+/// For example,
+/// {
+///   "delegations": [
+///     {
+///       "delegation": {
+///         "expiration": "38eecfcf56a60000",
+///         "pubkey": "3056301006072a8648ce3d0201...",
+///         "targets": ["00000000000000010101"]
+///       },
+///       "signature": "21b3738a2b26594621a41504b3..."
+///     }
+///   ],
+///   "publicKey": "302a300506032b6570032100..."
+/// }
+///
+/// All byte strings are hex-encoded, as are the expiration (in nanoseconds since the epoch) and
+/// the optional canister targets (as principal bytes).
+#[derive(Debug, Clone)]
+pub struct DelegationChain {
+    /// The DER-encoded public key the chain starts from, which the principal is derived from.
+    pub public_key: Vec<u8>,
+    pub delegations: Vec<SignedDelegation>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonDelegationChain {
+    delegations: Vec<JsonSignedDelegation>,
+    public_key: String,
+}
+
+#[derive(Deserialize)]
+struct JsonSignedDelegation {
+    delegation: JsonDelegation,
+    signature: String,
+}
+
+#[derive(Deserialize)]
+struct JsonDelegation {
+    pubkey: String,
+    expiration: String,
+    #[serde(default)]
+    targets: Option<Vec<String>>,
+}
+
+impl DelegationChain {
+    /// Parses a delegation chain exported by agent-js.
+    pub fn from_json(json: &str) -> AnyhowResult<Self> {
+        let chain: JsonDelegationChain =
+            serde_json::from_str(json).context("Invalid delegation chain")?;
+        ensure!(
+            !chain.delegations.is_empty(),
+            "Delegation chain contains no delegations"
+        );
+        let delegations = chain
+            .delegations
+            .into_iter()
+            .map(|signed| {
+                let delegation = signed.delegation;
+                let targets = delegation
+                    .targets
+                    .map(|targets| {
+                        targets
+                            .iter()
+                            .map(|target| Ok(Principal::from_slice(&hex::decode(target)?)))
+                            .collect::<AnyhowResult<Vec<_>>>()
+                    })
+                    .transpose()
+                    .context("Invalid delegation target")?;
+                Ok(SignedDelegation {
+                    delegation: Delegation {
+                        pubkey: hex::decode(&delegation.pubkey)
+                            .context("Invalid delegation public key")?,
+                        expiration: u64::from_str_radix(&delegation.expiration, 16)
+                            .context("Invalid delegation expiration")?,
+                        targets,
+                    },
+                    signature: hex::decode(&signed.signature)
+                        .context("Invalid delegation signature")?,
+                })
+            })
+            .collect::<AnyhowResult<_>>()?;
+        Ok(Self {
+            public_key: hex::decode(&chain.public_key)
+                .context("Invalid delegation chain public key")?,
+            delegations,
+        })
+    }
+
+    /// Checks that the chain delegates to `session_key` (DER-encoded) and has not expired. The
+    /// signatures are only checked by the IC.
+    pub fn validate(&self, session_key: &[u8]) -> AnyhowResult {
+        let last = &self
+            .delegations
+            .last()
+            .expect("validated in from_json")
+            .delegation;
+        ensure!(
+            last.pubkey == session_key,
+            "The delegation chain is for a different session key than the one provided"
+        );
+        let expiration = self
+            .delegations
+            .iter()
+            .map(|signed| signed.delegation.expiration)
+            .min()
+            .expect("validated in from_json");
+        ensure!(
+            expiration >= now_nanos(),
+            "The delegation chain has expired"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,186 @@
+//! Identities managed by dfx, in `~/.config/dfx/identity/<name>`.
+
+use std::{env, path::PathBuf};
+
+use anyhow::{anyhow, bail, Context};
+use argon2::{
+    password_hash::{PasswordHasher, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
+use k256::elliptic_curve::zeroize::Zeroizing;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM};
+use serde::Deserialize;
+
+use super::AnyhowResult;
+
+/// The name of dfx's built-in identity without a key.
+pub const ANONYMOUS: &str = "anonymous";
+
+#[cfg(feature = "keyring")]
+const KEYRING_SERVICE_NAME: &str = "internet_computer_identities";
+#[cfg(feature = "keyring")]
+const KEYRING_IDENTITY_PREFIX: &str = "internet_computer_identity_";
+
+/// The contents of an identity's `identity.json`. Identities with none of these are stored in
+/// `identity.pem` in plaintext.
+#[derive(Debug, Default, Deserialize)]
+struct IdentityConfiguration {
+    #[serde(default)]
+    hsm: Option<HardwareIdentityConfiguration>,
+    #[serde(default)]
+    encryption: Option<EncryptionConfiguration>,
+    #[serde(default)]
+    keyring_identity_suffix: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HardwareIdentityConfiguration {
+    #[serde(default)]
+    pkcs11_lib_path: Option<PathBuf>,
+    key_id: String,
+}
+
+/// The parameters of `identity.pem.encrypted`, which is encrypted with AES-256-GCM, using a key
+/// derived from the password with Argon2id.
+#[derive(Debug, Deserialize)]
+struct EncryptionConfiguration {
+    /// The base64-encoded Argon2 salt.
+    pw_salt: String,
+    /// The 96-bit AES-GCM nonce.
+    file_nonce: Vec<u8>,
+}
+
+/// Where the key of a dfx identity is.
+pub enum DfxIdentity {
+    /// The anonymous identity, which has no key.
+    Anonymous,
+    /// A PEM-encoded key, decrypted if necessary. `source` describes where it was read from.
+    Pem {
+        pem: Zeroizing<String>,
+        source: String,
+    },
+    /// A key in a PKCS#11 module, which dfx always looks for in the first slot.
+    Hsm {
+        pkcs11_lib_path: Option<PathBuf>,
+        key_id: String,
+    },
+}
+
+/// Returns the directory dfx keeps its configuration in, which can be overridden with
+/// `DFX_CONFIG_ROOT`.
+pub fn config_dir() -> AnyhowResult<PathBuf> {
+    let root = match env::var_os("DFX_CONFIG_ROOT") {
+        Some(root) => PathBuf::from(root),
+        None => dirs::home_dir().context("Could not find the user's home directory")?,
+    };
+    Ok(root.join(".config").join("dfx"))
+}
+
+/// Loads the dfx identity with the given name. `password` is only called for encrypted identities.
+pub fn load_identity(
+    name: &str,
+    password: impl FnOnce() -> AnyhowResult<String>,
+) -> AnyhowResult<DfxIdentity> {
+    if name == ANONYMOUS {
+        return Ok(DfxIdentity::Anonymous);
+    }
+    let identities = config_dir()?.join("identity");
+    let dir = identities.join(name);
+    if !dir.is_dir() {
+        bail!("No dfx identity named '{name}' in {}", identities.display());
+    }
+    let config_path = dir.join("identity.json");
+    let config: IdentityConfiguration = match std::fs::read(&config_path) {
+        Ok(contents) => serde_json::from_slice(&contents)
+            .with_context(|| format!("Invalid dfx identity config {}", config_path.display()))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => IdentityConfiguration::default(),
+        Err(e) => {
+            return Err(e).with_context(|| {
+                format!(
+                    "Couldn't read dfx identity config {}",
+                    config_path.display()
+                )
+            })
+        }
+    };
+    if let Some(hsm) = config.hsm {
+        return Ok(DfxIdentity::Hsm {
+            pkcs11_lib_path: hsm.pkcs11_lib_path,
+            key_id: hsm.key_id,
+        });
+    }
+    if let Some(suffix) = &config.keyring_identity_suffix {
+        return Ok(DfxIdentity::Pem {
+            pem: load_pem_from_keyring(suffix)?,
+            source: format!("the keyring entry of dfx identity '{name}'"),
+        });
+    }
+    let (path, pem) = if let Some(encryption) = &config.encryption {
+        let path = dir.join("identity.pem.encrypted");
+        let encrypted = std::fs::read(&path)
+            .with_context(|| format!("Couldn't read dfx identity file {}", path.display()))?;
+        let pem = decrypt_pem(encrypted, encryption, &password()?)
+            .with_context(|| format!("Could not decrypt file {}", path.display()))?;
+        (path, pem)
+    } else {
+        let path = dir.join("identity.pem");
+        let pem = std::fs::read_to_string(&path)
+            .with_context(|| format!("Couldn't read dfx identity file {}", path.display()))?;
+        (path, Zeroizing::new(pem))
+    };
+    Ok(DfxIdentity::Pem {
+        pem,
+        source: format!("file {}", path.display()),
+    })
+}
+
+/// Decrypts `identity.pem.encrypted` the same way dfx does.
+fn decrypt_pem(
+    encrypted: Vec<u8>,
+    config: &EncryptionConfiguration,
+    password: &str,
+) -> AnyhowResult<Zeroizing<String>> {
+    let params = Params::new(64000, 3, 1, Some(32)).expect("valid Argon2 params");
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+    let salt = SaltString::from_b64(&config.pw_salt).map_err(|e| anyhow!("Invalid salt: {e}"))?;
+    let hash = argon2
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| anyhow!("Couldn't hash password: {e}"))?;
+    let hash = hash.hash.expect("Argon2 always outputs a hash");
+    let key = LessSafeKey::new(
+        UnboundKey::new(&AES_256_GCM, hash.as_bytes()).expect("Argon2 output is 32 bytes"),
+    );
+    let nonce = Nonce::try_assume_unique_for_key(&config.file_nonce)
+        .map_err(|_| anyhow!("Invalid nonce length {}", config.file_nonce.len()))?;
+    // decrypted in place, so the buffer is zeroized once done
+    let mut buffer = Zeroizing::new(encrypted);
+    let pem = key
+        .open_in_place(nonce, Aad::empty(), &mut buffer)
+        .map_err(|_| anyhow!("Incorrect password"))?;
+    Ok(Zeroizing::new(
+        String::from_utf8(pem.to_vec()).context("Decrypted PEM is not text")?,
+    ))
+}
+
+#[cfg(feature = "keyring")]
+fn load_pem_from_keyring(suffix: &str) -> AnyhowResult<Zeroizing<String>> {
+    let entry = keyring::Entry::new(
+        KEYRING_SERVICE_NAME,
+        &format!("{KEYRING_IDENTITY_PREFIX}{suffix}"),
+    )?;
+    let encoded = Zeroizing::new(
+        entry
+            .get_password()
+            .context("Couldn't read the key from the system keyring")?,
+    );
+    // dfx stores the PEM file hex-encoded
+    let pem = Zeroizing::new(hex::decode(&*encoded).context("Invalid keyring entry")?);
+    Ok(Zeroizing::new(
+        String::from_utf8(pem.to_vec()).context("Invalid keyring entry")?,
+    ))
+}
+
+#[cfg(not(feature = "keyring"))]
+fn load_pem_from_keyring(_: &str) -> AnyhowResult<Zeroizing<String>> {
+    bail!("This build of quill does not support keyring-backed dfx identities.")
+}
@@ -0,0 +1,96 @@
+//! Ethereum addresses, for ckETH withdrawals and Genesis DFN addresses.
+
+use std::{fmt, str::FromStr};
+
+use anyhow::{bail, ensure, Context};
+use k256::{elliptic_curve::sec1::ToEncodedPoint, PublicKey};
+use sha3::{Digest, Keccak256};
+
+/// A 20-byte Ethereum address.
+///
+/// Parsing accepts all-lowercase and all-uppercase addresses, and checks the [EIP-55] checksum of
+/// mixed-case addresses. Displaying always produces the checksummed form.
+///
+/// [EIP-55]: https://eips.ethereum.org/EIPS/eip-55
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EthAddress(pub [u8; 20]);
+
+impl EthAddress {
+    /// The address controlled by a secp256k1 key: the last 20 bytes of the Keccak-256 hash of the
+    /// uncompressed public key.
+    pub fn from_public_key(public_key: &PublicKey) -> Self {
+        let uncompressed = public_key.to_encoded_point(false);
+        let hash = Keccak256::digest(&uncompressed.as_bytes()[1..]);
+        Self(hash[12..].try_into().unwrap())
+    }
+
+    fn checksummed(&self) -> String {
+        let lower = hex::encode(self.0);
+        let hash = Keccak256::digest(lower.as_bytes());
+        lower
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let nibble = (hash[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0xf;
+                if nibble >= 8 {
+                    c.to_ascii_uppercase()
+                } else {
+                    c
+                }
+            })
+            .collect()
+    }
+}
+
+impl FromStr for EthAddress {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some(digits) = s.strip_prefix("0x") else {
+            bail!("Ethereum addresses must start with 0x");
+        };
+        ensure!(
+            digits.len() == 40,
+            "Ethereum addresses must be 40 hex digits long"
+        );
+        let bytes = hex::decode(digits).context("Ethereum addresses must be hexadecimal")?;
+        let address = Self(bytes.try_into().unwrap());
+        let is_mixed_case = digits.chars().any(|c| c.is_ascii_lowercase())
+            && digits.chars().any(|c| c.is_ascii_uppercase());
+        if is_mixed_case {
+            ensure!(
+                address.checksummed() == digits,
+                "Ethereum address {s} has an invalid checksum"
+            );
+        }
+        Ok(address)
+    }
+}
+
+impl fmt::Display for EthAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{}", self.checksummed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EthAddress;
+    use std::str::FromStr;
+
+    #[test]
+    fn checksum() {
+        for address in [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            assert_eq!(EthAddress::from_str(address).unwrap().to_string(), address);
+            let lower = address.to_lowercase();
+            assert_eq!(EthAddress::from_str(&lower).unwrap().to_string(), address);
+        }
+        assert!(EthAddress::from_str("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD").is_err());
+        assert!(EthAddress::from_str("5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").is_err());
+        assert!(EthAddress::from_str("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeA").is_err());
+    }
+}
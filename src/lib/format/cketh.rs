@@ -0,0 +1,256 @@
+use candid::{CandidType, Decode, Deserialize, Nat, Principal};
+
+use crate::lib::{units_to_tokens, AnyhowResult};
+
+// The ckETH minter's candid types, from candid/cketh_minter.did.
+
+#[derive(CandidType, Deserialize)]
+struct RetrieveEthRequest {
+    block_index: Nat,
+}
+
+#[derive(CandidType, Deserialize)]
+enum WithdrawalError {
+    AmountTooLow { min_withdrawal_amount: Nat },
+    InsufficientFunds { balance: Nat },
+    InsufficientAllowance { allowance: Nat },
+    RecipientAddressBlocked { address: String },
+    TemporarilyUnavailable(String),
+}
+
+#[derive(CandidType, Deserialize)]
+struct RetrieveErc20Request {
+    cketh_block_index: Nat,
+    ckerc20_block_index: Nat,
+}
+
+#[derive(CandidType, Deserialize)]
+struct Erc20Token {
+    ckerc20_token_symbol: String,
+    erc20_contract_address: String,
+    ledger_canister_id: Principal,
+}
+
+// The ledger IDs included in these errors are left out, as they are not displayed.
+#[derive(CandidType, Deserialize)]
+enum LedgerError {
+    InsufficientFunds {
+        balance: Nat,
+        failed_burn_amount: Nat,
+        token_symbol: String,
+    },
+    AmountTooLow {
+        minimum_burn_amount: Nat,
+        failed_burn_amount: Nat,
+        token_symbol: String,
+    },
+    InsufficientAllowance {
+        allowance: Nat,
+        failed_burn_amount: Nat,
+        token_symbol: String,
+    },
+    TemporarilyUnavailable(String),
+}
+
+#[derive(CandidType, Deserialize)]
+enum WithdrawErc20Error {
+    TokenNotSupported {
+        supported_tokens: Vec<Erc20Token>,
+    },
+    RecipientAddressBlocked {
+        address: String,
+    },
+    CkEthLedgerError {
+        error: LedgerError,
+    },
+    CkErc20LedgerError {
+        cketh_block_index: Nat,
+        error: LedgerError,
+    },
+    TemporarilyUnavailable(String),
+}
+
+#[derive(CandidType, Deserialize)]
+struct EthTransaction {
+    transaction_hash: String,
+}
+
+#[derive(CandidType, Deserialize)]
+enum TxFinalizedStatus {
+    Success {
+        transaction_hash: String,
+        effective_transaction_fee: Option<Nat>,
+    },
+    PendingReimbursement(EthTransaction),
+    Reimbursed {
+        transaction_hash: String,
+        reimbursed_amount: Nat,
+        reimbursed_in_block: Nat,
+    },
+}
+
+#[derive(CandidType, Deserialize)]
+enum RetrieveEthStatus {
+    NotFound,
+    Pending,
+    TxCreated,
+    TxSigned(EthTransaction),
+    TxSent(EthTransaction),
+    TxFinalized(TxFinalizedStatus),
+}
+
+const ETH_DECIMALS: u8 = 18;
+
+fn eth(wei: Nat) -> String {
+    format!("{} ETH", units_to_tokens(wei, ETH_DECIMALS))
+}
+
+pub fn display_withdraw_eth(blob: &[u8]) -> AnyhowResult<String> {
+    let result = Decode!(blob, Result<RetrieveEthRequest, WithdrawalError>)?;
+    let fmt = match result {
+        Ok(request) => format!(
+            "Begun withdrawal at block index {}. Use `quill cketh retrieve-eth-status {}` to follow it.",
+            request.block_index.0, request.block_index.0
+        ),
+        Err(e) => match e {
+            WithdrawalError::AmountTooLow {
+                min_withdrawal_amount,
+            } => format!(
+                "ckETH error: amount too low to withdraw (min: {})",
+                eth(min_withdrawal_amount)
+            ),
+            WithdrawalError::InsufficientFunds { balance } => format!(
+                "ckETH error: the account does not have enough ckETH (balance: {})",
+                eth(balance)
+            ),
+            WithdrawalError::InsufficientAllowance { allowance } => format!(
+                "ckETH error: the minter is not approved to burn enough ckETH (allowance: {})",
+                eth(allowance)
+            ),
+            WithdrawalError::RecipientAddressBlocked { address } => {
+                format!("ckETH error: the address {address} is blocked")
+            }
+            WithdrawalError::TemporarilyUnavailable(e) => {
+                format!("ckETH error: temporarily unavailable: {e}. Try again later.")
+            }
+        },
+    };
+    Ok(fmt)
+}
+
+pub fn display_withdraw_erc20(blob: &[u8]) -> AnyhowResult<String> {
+    let result = Decode!(blob, Result<RetrieveErc20Request, WithdrawErc20Error>)?;
+    let fmt = match result {
+        Ok(request) => format!(
+            "Begun withdrawal at block index {} (ckETH fee burned at block index {}). Use `quill cketh retrieve-eth-status {}` to follow it.",
+            request.ckerc20_block_index.0, request.cketh_block_index.0, request.cketh_block_index.0
+        ),
+        Err(e) => match e {
+            WithdrawErc20Error::TokenNotSupported { supported_tokens } => {
+                let supported = supported_tokens
+                    .iter()
+                    .map(|token| {
+                        format!(
+                            "{} (ledger {}, ERC-20 contract {})",
+                            token.ckerc20_token_symbol,
+                            token.ledger_canister_id,
+                            token.erc20_contract_address
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("ckERC20 error: token not supported. Supported tokens: {supported}")
+            }
+            WithdrawErc20Error::RecipientAddressBlocked { address } => {
+                format!("ckERC20 error: the address {address} is blocked")
+            }
+            WithdrawErc20Error::CkEthLedgerError { error } => {
+                format!(
+                    "ckERC20 error: could not burn the ckETH fee: {}",
+                    display_ledger_error(error)
+                )
+            }
+            WithdrawErc20Error::CkErc20LedgerError {
+                cketh_block_index,
+                error,
+            } => format!(
+                "ckERC20 error: could not burn the tokens: {}. The ckETH fee was burned at block index {} and will be reimbursed.",
+                display_ledger_error(error),
+                cketh_block_index.0
+            ),
+            WithdrawErc20Error::TemporarilyUnavailable(e) => {
+                format!("ckERC20 error: temporarily unavailable: {e}. Try again later.")
+            }
+        },
+    };
+    Ok(fmt)
+}
+
+// Amounts are in the token's smallest unit, as the minter does not report its decimals.
+fn display_ledger_error(error: LedgerError) -> String {
+    match error {
+        LedgerError::InsufficientFunds {
+            balance,
+            failed_burn_amount,
+            token_symbol,
+        } => format!(
+            "insufficient {token_symbol} (balance: {}, needed: {})",
+            balance.0, failed_burn_amount.0
+        ),
+        LedgerError::AmountTooLow {
+            minimum_burn_amount,
+            failed_burn_amount,
+            token_symbol,
+        } => format!(
+            "{token_symbol} amount {} is below the minimum of {}",
+            failed_burn_amount.0, minimum_burn_amount.0
+        ),
+        LedgerError::InsufficientAllowance {
+            allowance,
+            failed_burn_amount,
+            token_symbol,
+        } => format!(
+            "the minter is not approved to burn enough {token_symbol} (allowance: {}, needed: {})",
+            allowance.0, failed_burn_amount.0
+        ),
+        LedgerError::TemporarilyUnavailable(e) => format!("ledger temporarily unavailable: {e}"),
+    }
+}
+
+pub fn display_retrieve_eth_status(blob: &[u8]) -> AnyhowResult<String> {
+    let status = Decode!(blob, RetrieveEthStatus)?;
+    let fmt = match status {
+        RetrieveEthStatus::NotFound => "Withdrawal not found".to_string(),
+        RetrieveEthStatus::Pending => "Withdrawal pending".to_string(),
+        RetrieveEthStatus::TxCreated => "Ethereum transaction created".to_string(),
+        RetrieveEthStatus::TxSigned(tx) => {
+            format!("Ethereum transaction signed: {}", tx.transaction_hash)
+        }
+        RetrieveEthStatus::TxSent(tx) => {
+            format!("Ethereum transaction sent: {}", tx.transaction_hash)
+        }
+        RetrieveEthStatus::TxFinalized(TxFinalizedStatus::Success {
+            transaction_hash,
+            effective_transaction_fee,
+        }) => {
+            let mut fmt = format!("Withdrawal complete: {transaction_hash}");
+            if let Some(fee) = effective_transaction_fee {
+                fmt.push_str(&format!(" (transaction fee: {})", eth(fee)));
+            }
+            fmt
+        }
+        RetrieveEthStatus::TxFinalized(TxFinalizedStatus::PendingReimbursement(tx)) => format!(
+            "Ethereum transaction {} failed, reimbursement pending",
+            tx.transaction_hash
+        ),
+        RetrieveEthStatus::TxFinalized(TxFinalizedStatus::Reimbursed {
+            transaction_hash,
+            reimbursed_amount,
+            reimbursed_in_block,
+        }) => format!(
+            "Ethereum transaction {transaction_hash} failed, reimbursed {} at block index {}",
+            reimbursed_amount.0, reimbursed_in_block.0
+        ),
+    };
+    Ok(fmt)
+}
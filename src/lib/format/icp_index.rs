@@ -0,0 +1,178 @@
+use candid::{CandidType, Decode, Deserialize};
+use std::fmt::Write;
+
+use crate::lib::{e8s_to_tokens, format::format_timestamp_nanoseconds, AnyhowResult};
+
+#[derive(CandidType, Deserialize)]
+struct GetAccountIdentifierTransactionsResponse {
+    balance: u64,
+    transactions: Vec<TransactionWithId>,
+    oldest_tx_id: Option<u64>,
+}
+
+#[derive(CandidType, Deserialize)]
+struct GetAccountIdentifierTransactionsError {
+    message: String,
+}
+
+#[derive(CandidType, Deserialize)]
+struct TransactionWithId {
+    id: u64,
+    transaction: Transaction,
+}
+
+#[derive(CandidType, Deserialize)]
+struct Transaction {
+    memo: u64,
+    icrc1_memo: Option<Vec<u8>>,
+    operation: Operation,
+    timestamp: Option<TimeStamp>,
+    created_at_time: Option<TimeStamp>,
+}
+
+#[derive(CandidType, Deserialize)]
+struct TimeStamp {
+    timestamp_nanos: u64,
+}
+
+#[derive(CandidType, Deserialize)]
+struct Tokens {
+    e8s: u64,
+}
+
+#[derive(CandidType, Deserialize)]
+enum Operation {
+    Approve {
+        fee: Tokens,
+        from: String,
+        allowance: Tokens,
+        expected_allowance: Option<Tokens>,
+        expires_at: Option<TimeStamp>,
+        spender: String,
+    },
+    Burn {
+        from: String,
+        amount: Tokens,
+        spender: Option<String>,
+    },
+    Mint {
+        to: String,
+        amount: Tokens,
+    },
+    Transfer {
+        to: String,
+        fee: Tokens,
+        from: String,
+        amount: Tokens,
+        spender: Option<String>,
+    },
+}
+
+pub fn display_account_transactions(blob: &[u8]) -> AnyhowResult<String> {
+    let result = Decode!(
+        blob,
+        Result<GetAccountIdentifierTransactionsResponse, GetAccountIdentifierTransactionsError>
+    )?;
+    let response = match result {
+        Ok(response) => response,
+        Err(e) => return Ok(format!("Index error: {}", e.message)),
+    };
+    let mut fmt = format!("Balance: {} ICP\n", e8s_to_tokens(response.balance.into()));
+    if response.transactions.is_empty() {
+        fmt.push_str("No transactions found");
+        return Ok(fmt);
+    }
+    for TransactionWithId { id, transaction } in &response.transactions {
+        let time = transaction
+            .timestamp
+            .as_ref()
+            .or(transaction.created_at_time.as_ref())
+            .map_or_else(
+                || "unknown time".to_string(),
+                |ts| format_timestamp_nanoseconds(ts.timestamp_nanos),
+            );
+        writeln!(fmt, "\nTransaction {id} at {time}")?;
+        match &transaction.operation {
+            Operation::Transfer {
+                to,
+                fee,
+                from,
+                amount,
+                spender,
+            } => {
+                writeln!(
+                    fmt,
+                    "  Transfer of {} ICP from {from} to {to}",
+                    e8s_to_tokens(amount.e8s.into())
+                )?;
+                if let Some(spender) = spender {
+                    writeln!(fmt, "  Spent by {spender}")?;
+                }
+                writeln!(fmt, "  Fee: {} ICP", e8s_to_tokens(fee.e8s.into()))?;
+            }
+            Operation::Mint { to, amount } => writeln!(
+                fmt,
+                "  Mint of {} ICP to {to}",
+                e8s_to_tokens(amount.e8s.into())
+            )?,
+            Operation::Burn {
+                from,
+                amount,
+                spender,
+            } => {
+                writeln!(
+                    fmt,
+                    "  Burn of {} ICP from {from}",
+                    e8s_to_tokens(amount.e8s.into())
+                )?;
+                if let Some(spender) = spender {
+                    writeln!(fmt, "  Spent by {spender}")?;
+                }
+            }
+            Operation::Approve {
+                fee,
+                from,
+                allowance,
+                expected_allowance,
+                expires_at,
+                spender,
+            } => {
+                writeln!(
+                    fmt,
+                    "  Approval for {spender} to spend {} ICP from {from}",
+                    e8s_to_tokens(allowance.e8s.into())
+                )?;
+                if let Some(expected) = expected_allowance {
+                    writeln!(
+                        fmt,
+                        "  Expected previous allowance: {} ICP",
+                        e8s_to_tokens(expected.e8s.into())
+                    )?;
+                }
+                if let Some(expires_at) = expires_at {
+                    writeln!(
+                        fmt,
+                        "  Expires at {}",
+                        format_timestamp_nanoseconds(expires_at.timestamp_nanos)
+                    )?;
+                }
+                writeln!(fmt, "  Fee: {} ICP", e8s_to_tokens(fee.e8s.into()))?;
+            }
+        }
+        if let Some(memo) = &transaction.icrc1_memo {
+            writeln!(fmt, "  Memo: 0x{}", hex::encode(memo))?;
+        } else {
+            writeln!(fmt, "  Memo: {}", transaction.memo)?;
+        }
+    }
+    if let (Some(last), Some(oldest)) = (response.transactions.last(), response.oldest_tx_id) {
+        if last.id > oldest {
+            write!(
+                fmt,
+                "\nThere are older transactions. To see them, use --start {}",
+                last.id
+            )?;
+        }
+    }
+    Ok(fmt)
+}
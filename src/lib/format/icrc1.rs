@@ -1,17 +1,236 @@
+use std::fmt::Write;
+
 use candid::{Decode, Nat};
-use icrc_ledger_types::icrc1::transfer::TransferError;
+use icrc_ledger_types::{
+    icrc::generic_metadata_value::MetadataValue,
+    icrc1::transfer::TransferError,
+    icrc2::{allowance::Allowance, approve::ApproveError, transfer_from::TransferFromError},
+};
+
+use crate::lib::{e8s_to_tokens, units_to_tokens, AnyhowResult};
+
+use super::format_timestamp_nanoseconds;
 
-use crate::lib::{e8s_to_tokens, AnyhowResult};
+/// The decimals and symbol of a ledger's token, as read from its `icrc1_decimals` and
+/// `icrc1_symbol` by the `quill icrc1` commands.
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub decimals: u8,
+    pub symbol: String,
+}
 
-pub fn display_transfer(blob: &[u8]) -> AnyhowResult<String> {
+impl Token {
+    pub(crate) fn amount(&self, units: Nat) -> String {
+        let tokens = units_to_tokens(units, self.decimals);
+        if self.symbol.is_empty() {
+            tokens.to_string()
+        } else {
+            format!("{tokens} {}", self.symbol)
+        }
+    }
+}
+
+pub fn display_transfer(blob: &[u8], token: Option<&Token>) -> AnyhowResult<String> {
     let result = Decode!(blob, Result<Nat, TransferError>)?;
     match result {
         Ok(index) => Ok(format!("Transfer sent at block index {index}")),
-        Err(e) => Ok(format!("Transfer error: {e}")),
+        Err(e) => {
+            let in_tokens = token.and_then(|token| match &e {
+                TransferError::BadFee { expected_fee } => Some(format!(
+                    "expected fee: {}",
+                    token.amount(expected_fee.clone())
+                )),
+                TransferError::BadBurn { min_burn_amount } => Some(format!(
+                    "minimum burn amount: {}",
+                    token.amount(min_burn_amount.clone())
+                )),
+                TransferError::InsufficientFunds { balance } => {
+                    Some(format!("balance: {}", token.amount(balance.clone())))
+                }
+                _ => None,
+            });
+            match in_tokens {
+                Some(in_tokens) => Ok(format!("Transfer error: {e} ({in_tokens})")),
+                None => Ok(format!("Transfer error: {e}")),
+            }
+        }
     }
 }
 
-pub fn display_balance(blob: &[u8]) -> AnyhowResult<String> {
+pub fn display_balance(blob: &[u8], token: Option<&Token>) -> AnyhowResult<String> {
     let balance = Decode!(blob, Nat)?;
-    Ok(format!("Balance: {}", e8s_to_tokens(balance))) // we do not use any ICRC1 calls to ledgers with digits other than 8
+    match token {
+        Some(token) => Ok(format!("Balance: {}", token.amount(balance))),
+        // the ICP, SNS and ckBTC ledgers all have 8 decimals
+        None => Ok(format!("Balance: {}", e8s_to_tokens(balance))),
+    }
+}
+
+pub fn display_approve(blob: &[u8]) -> AnyhowResult<String> {
+    let result = Decode!(blob, Result<Nat, ApproveError>)?;
+    let fmt = match result {
+        Ok(index) => format!("Approval set at block index {index}"),
+        Err(e) => {
+            let reason = match e {
+                ApproveError::BadFee { expected_fee } => {
+                    format!("Bad fee, expected {}", e8s_to_tokens(expected_fee))
+                }
+                ApproveError::InsufficientFunds { balance } => format!(
+                    "Insufficient funds to pay the fee, balance is {}",
+                    e8s_to_tokens(balance)
+                ),
+                ApproveError::AllowanceChanged { current_allowance } => format!(
+                    "The current allowance is {}, not the expected allowance",
+                    e8s_to_tokens(current_allowance)
+                ),
+                ApproveError::Expired { ledger_time } => format!(
+                    "The expiry time is in the past (ledger time is {})",
+                    format_timestamp_nanoseconds(ledger_time)
+                ),
+                ApproveError::TooOld => "The message is too old".to_string(),
+                ApproveError::CreatedInFuture { ledger_time } => format!(
+                    "The message was created in the future (ledger time is {})",
+                    format_timestamp_nanoseconds(ledger_time)
+                ),
+                ApproveError::Duplicate { duplicate_of } => {
+                    format!("Duplicate of the approval at block index {duplicate_of}")
+                }
+                ApproveError::TemporarilyUnavailable => {
+                    "The ledger is temporarily unavailable".to_string()
+                }
+                ApproveError::GenericError {
+                    error_code,
+                    message,
+                } => format!("{message} (error code {error_code})"),
+            };
+            format!("Approval error: {reason}")
+        }
+    };
+    Ok(fmt)
+}
+
+pub fn display_allowance(blob: &[u8]) -> AnyhowResult<String> {
+    let allowance = Decode!(blob, Allowance)?;
+    let mut fmt = format!("Allowance: {}", e8s_to_tokens(allowance.allowance));
+    if let Some(expires_at) = allowance.expires_at {
+        fmt.push_str(&format!(
+            "\nExpires at: {}",
+            format_timestamp_nanoseconds(expires_at)
+        ));
+    }
+    Ok(fmt)
+}
+
+pub fn display_transfer_from(blob: &[u8]) -> AnyhowResult<String> {
+    let result = Decode!(blob, Result<Nat, TransferFromError>)?;
+    let fmt = match result {
+        Ok(index) => format!("Transfer sent at block index {index}"),
+        Err(e) => {
+            let reason = match e {
+                TransferFromError::BadFee { expected_fee } => {
+                    format!("Bad fee, expected {}", e8s_to_tokens(expected_fee))
+                }
+                TransferFromError::BadBurn { min_burn_amount } => format!(
+                    "Bad burn, minimum burn amount is {}",
+                    e8s_to_tokens(min_burn_amount)
+                ),
+                TransferFromError::InsufficientFunds { balance } => {
+                    format!("Insufficient funds, balance is {}", e8s_to_tokens(balance))
+                }
+                TransferFromError::InsufficientAllowance { allowance } => format!(
+                    "Insufficient allowance, allowance is {}",
+                    e8s_to_tokens(allowance)
+                ),
+                TransferFromError::TooOld => "The message is too old".to_string(),
+                TransferFromError::CreatedInFuture { ledger_time } => format!(
+                    "The message was created in the future (ledger time is {})",
+                    format_timestamp_nanoseconds(ledger_time)
+                ),
+                TransferFromError::Duplicate { duplicate_of } => {
+                    format!("Duplicate of the transfer at block index {duplicate_of}")
+                }
+                TransferFromError::TemporarilyUnavailable => {
+                    "The ledger is temporarily unavailable".to_string()
+                }
+                TransferFromError::GenericError {
+                    error_code,
+                    message,
+                } => format!("{message} (error code {error_code})"),
+            };
+            format!("Transfer error: {reason}")
+        }
+    };
+    Ok(fmt)
+}
+
+pub fn display_metadata(blob: &[u8]) -> AnyhowResult<String> {
+    let metadata = Decode!(blob, Vec<(String, MetadataValue)>)?;
+    let decimals = metadata
+        .iter()
+        .find_map(|(key, value)| match (key.as_str(), value) {
+            ("icrc1:decimals", MetadataValue::Nat(n)) => u8::try_from(&n.0).ok(),
+            _ => None,
+        })
+        .unwrap_or(8);
+    let symbol = metadata
+        .iter()
+        .find_map(|(key, value)| match (key.as_str(), value) {
+            ("icrc1:symbol", MetadataValue::Text(symbol)) => Some(symbol.as_str()),
+            _ => None,
+        })
+        .unwrap_or_default();
+    let mut fmt = String::new();
+    for (key, value) in &metadata {
+        let value = match (key.as_str(), value) {
+            ("icrc1:fee", MetadataValue::Nat(fee)) => {
+                format!("{} {symbol}", units_to_tokens(fee.clone(), decimals))
+            }
+            (_, MetadataValue::Nat(n)) => n.0.to_string(),
+            (_, MetadataValue::Int(i)) => i.0.to_string(),
+            (_, MetadataValue::Text(text)) => text.clone(),
+            (_, MetadataValue::Blob(bytes)) => hex::encode(bytes),
+        };
+        writeln!(fmt, "{key}: {value}")?;
+    }
+    Ok(fmt)
+}
+
+#[test]
+fn transfer_errors_in_tokens() {
+    use candid::Encode;
+
+    let token = Token {
+        decimals: 18,
+        symbol: "ckETH".to_string(),
+    };
+    let bad_fee = TransferError::BadFee {
+        expected_fee: Nat::from(2_000_000_000_000_u64),
+    };
+    let blob = Encode!(&Result::<Nat, TransferError>::Err(bad_fee.clone())).unwrap();
+    assert_eq!(
+        display_transfer(&blob, Some(&token)).unwrap(),
+        format!("Transfer error: {bad_fee} (expected fee: 0.000002 ckETH)")
+    );
+    // without a token, the error is displayed as the ledger types do
+    assert_eq!(
+        display_transfer(&blob, None).unwrap(),
+        format!("Transfer error: {bad_fee}")
+    );
+    let insufficient = TransferError::InsufficientFunds {
+        balance: Nat::from(50_000_000_000_000_000_u64),
+    };
+    let blob = Encode!(&Result::<Nat, TransferError>::Err(insufficient.clone())).unwrap();
+    assert_eq!(
+        display_transfer(&blob, Some(&token)).unwrap(),
+        format!("Transfer error: {insufficient} (balance: 0.05 ckETH)")
+    );
+    let balance = Encode!(&Nat::from(50_000_000_000_000_000_u64)).unwrap();
+    assert_eq!(
+        display_balance(&balance, Some(&token)).unwrap(),
+        "Balance: 0.05 ckETH"
+    );
+    assert_eq!(
+        display_balance(&balance, None).unwrap(),
+        "Balance: 500000000"
+    );
 }
@@ -0,0 +1,126 @@
+use candid::{CandidType, Decode, Deserialize, Nat};
+use icrc_ledger_types::icrc3::transactions::Transaction;
+use std::fmt::Write;
+
+use crate::lib::{
+    e8s_to_tokens,
+    format::{format_timestamp_nanoseconds, icrc1::Token},
+    AnyhowResult,
+};
+
+#[derive(CandidType, Deserialize)]
+struct GetTransactions {
+    balance: Nat,
+    transactions: Vec<TransactionWithId>,
+    oldest_tx_id: Option<Nat>,
+}
+
+#[derive(CandidType, Deserialize)]
+struct GetTransactionsErr {
+    message: String,
+}
+
+#[derive(CandidType, Deserialize)]
+struct TransactionWithId {
+    id: Nat,
+    transaction: Transaction,
+}
+
+fn amount(units: &Nat, token: Option<&Token>) -> String {
+    match token {
+        Some(token) => token.amount(units.clone()),
+        // the SNS and ckBTC ledgers both have 8 decimals
+        None => e8s_to_tokens(units.clone()).to_string(),
+    }
+}
+
+pub fn display_account_transactions(blob: &[u8], token: Option<&Token>) -> AnyhowResult<String> {
+    let result = Decode!(blob, Result<GetTransactions, GetTransactionsErr>)?;
+    let response = match result {
+        Ok(response) => response,
+        Err(e) => return Ok(format!("Index error: {}", e.message)),
+    };
+    let mut fmt = format!("Balance: {}\n", amount(&response.balance, token));
+    if response.transactions.is_empty() {
+        fmt.push_str("No transactions found");
+        return Ok(fmt);
+    }
+    for TransactionWithId { id, transaction } in &response.transactions {
+        writeln!(
+            fmt,
+            "\nTransaction {} at {}",
+            id.0,
+            format_timestamp_nanoseconds(transaction.timestamp)
+        )?;
+        let memo = if let Some(transfer) = &transaction.transfer {
+            writeln!(
+                fmt,
+                "  Transfer of {} from {} to {}",
+                amount(&transfer.amount, token),
+                transfer.from,
+                transfer.to
+            )?;
+            if let Some(spender) = &transfer.spender {
+                writeln!(fmt, "  Spent by {spender}")?;
+            }
+            if let Some(fee) = &transfer.fee {
+                writeln!(fmt, "  Fee: {}", amount(fee, token))?;
+            }
+            transfer.memo.as_ref()
+        } else if let Some(mint) = &transaction.mint {
+            writeln!(
+                fmt,
+                "  Mint of {} to {}",
+                amount(&mint.amount, token),
+                mint.to
+            )?;
+            mint.memo.as_ref()
+        } else if let Some(burn) = &transaction.burn {
+            writeln!(
+                fmt,
+                "  Burn of {} from {}",
+                amount(&burn.amount, token),
+                burn.from
+            )?;
+            if let Some(spender) = &burn.spender {
+                writeln!(fmt, "  Spent by {spender}")?;
+            }
+            burn.memo.as_ref()
+        } else if let Some(approve) = &transaction.approve {
+            writeln!(
+                fmt,
+                "  Approval for {} to spend {} from {}",
+                approve.spender,
+                amount(&approve.amount, token),
+                approve.from
+            )?;
+            if let Some(expires_at) = approve.expires_at {
+                writeln!(
+                    fmt,
+                    "  Expires at {}",
+                    format_timestamp_nanoseconds(expires_at)
+                )?;
+            }
+            if let Some(fee) = &approve.fee {
+                writeln!(fmt, "  Fee: {}", amount(fee, token))?;
+            }
+            approve.memo.as_ref()
+        } else {
+            writeln!(fmt, "  Unknown operation `{}`", transaction.kind)?;
+            None
+        };
+        if let Some(memo) = memo {
+            writeln!(fmt, "  Memo: 0x{}", hex::encode(&memo.0))?;
+        }
+    }
+    if let (Some(last), Some(oldest)) = (response.transactions.last(), &response.oldest_tx_id) {
+        if &last.id > oldest {
+            write!(
+                fmt,
+                "\nThere are older transactions. To see them, use --start {}",
+                last.id.0
+            )?;
+        }
+    }
+    Ok(fmt)
+}
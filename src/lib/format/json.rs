@@ -0,0 +1,130 @@
+use candid::{
+    types::{
+        value::{IDLField, IDLValue, VariantValue},
+        Label,
+    },
+    IDLArgs,
+};
+use serde_json::{json, Map, Value};
+
+/// Converts decoded candid values to JSON.
+///
+/// A single value is returned as-is, and multiple values as an array. Records become objects (or arrays, for tuples),
+/// variants become single-key objects, and `opt` becomes `null` or the value. `nat` and `int`, which have no size
+/// limit, and `nat64` and `int64`, which do not fit in a JSON number without losing precision, become strings, as do
+/// blobs (hex-encoded) and principals.
+pub fn idl_args_to_json(args: &IDLArgs) -> Value {
+    match &args.args[..] {
+        [single] => idl_value_to_json(single),
+        many => Value::Array(many.iter().map(idl_value_to_json).collect()),
+    }
+}
+
+fn idl_value_to_json(value: &IDLValue) -> Value {
+    match value {
+        IDLValue::Bool(b) => json!(b),
+        IDLValue::Null | IDLValue::None | IDLValue::Reserved => Value::Null,
+        IDLValue::Text(s) => json!(s),
+        IDLValue::Number(n) => json!(n),
+        IDLValue::Float64(f) => json!(f),
+        IDLValue::Float32(f) => json!(f),
+        IDLValue::Opt(v) => idl_value_to_json(v),
+        IDLValue::Vec(vs) => Value::Array(vs.iter().map(idl_value_to_json).collect()),
+        IDLValue::Record(fields) => record_to_json(fields),
+        IDLValue::Variant(VariantValue(field, _)) => {
+            let mut map = Map::new();
+            map.insert(label_to_key(&field.id), idl_value_to_json(&field.val));
+            Value::Object(map)
+        }
+        IDLValue::Blob(bytes) => json!(hex::encode(bytes)),
+        IDLValue::Principal(p) | IDLValue::Service(p) => json!(p.to_text()),
+        IDLValue::Func(p, method) => json!({ "principal": p.to_text(), "method": method }),
+        IDLValue::Int(i) => json!(i.0.to_string()),
+        IDLValue::Nat(n) => json!(n.0.to_string()),
+        IDLValue::Nat8(n) => json!(n),
+        IDLValue::Nat16(n) => json!(n),
+        IDLValue::Nat32(n) => json!(n),
+        IDLValue::Nat64(n) => json!(n.to_string()),
+        IDLValue::Int8(n) => json!(n),
+        IDLValue::Int16(n) => json!(n),
+        IDLValue::Int32(n) => json!(n),
+        IDLValue::Int64(n) => json!(n.to_string()),
+    }
+}
+
+fn record_to_json(fields: &[IDLField]) -> Value {
+    let is_tuple = !fields.is_empty()
+        && fields
+            .iter()
+            .enumerate()
+            .all(|(i, field)| matches!(field.id, Label::Unnamed(n) if n as usize == i));
+    if is_tuple {
+        Value::Array(fields.iter().map(|f| idl_value_to_json(&f.val)).collect())
+    } else {
+        Value::Object(
+            fields
+                .iter()
+                .map(|f| (label_to_key(&f.id), idl_value_to_json(&f.val)))
+                .collect(),
+        )
+    }
+}
+
+fn label_to_key(label: &Label) -> String {
+    match label {
+        Label::Named(name) => name.clone(),
+        Label::Id(id) | Label::Unnamed(id) => id.to_string(),
+    }
+}
+
+#[test]
+fn json_conversion() {
+    use candid::{CandidType, Encode, Nat, Principal};
+
+    #[derive(CandidType)]
+    enum Status {
+        Done { block: Nat },
+    }
+    #[derive(CandidType)]
+    struct Reply {
+        owner: Principal,
+        amount: Nat,
+        fee: Option<u64>,
+        memo: Option<Vec<u8>>,
+        status: Status,
+        pair: (u8, String),
+    }
+    let blob = Encode!(&Reply {
+        owner: Principal::anonymous(),
+        amount: Nat::from(12_345_678_901_234_567_890_u128),
+        fee: Some(10_000),
+        memo: None,
+        status: Status::Done {
+            block: 7_u32.into()
+        },
+        pair: (1, "a".into()),
+    })
+    .unwrap();
+    let args = IDLArgs::from_bytes(&blob).unwrap();
+    let json = idl_args_to_json(&args);
+    // without type information, field names are hashed
+    assert_eq!(json.as_object().unwrap().len(), 6);
+
+    let args = IDLArgs::from_bytes_with_types(
+        &blob,
+        &candid::TypeEnv::new(),
+        &[<Reply as CandidType>::ty()],
+    )
+    .unwrap();
+    assert_eq!(
+        idl_args_to_json(&args),
+        json!({
+            "owner": "2vxsx-fae",
+            "amount": "12345678901234567890",
+            "fee": "10000",
+            "memo": null,
+            "status": { "Done": { "block": "7" } },
+            "pair": [1, "a"],
+        })
+    );
+}
@@ -8,9 +8,13 @@ use num_bigint::ToBigInt;
 use super::ParsedAccount;
 
 pub mod ckbtc;
+pub mod cketh;
 pub mod gtc;
+pub mod icp_index;
 pub mod icp_ledger;
 pub mod icrc1;
+pub mod icrc1_index;
+pub mod json;
 pub mod nns_governance;
 pub mod registry;
 pub mod sns_governance;
@@ -26,6 +30,12 @@ pub fn format_timestamp_seconds(seconds: u64) -> String {
     format_datetime(Utc.timestamp_opt(seconds.try_into().unwrap(), 0).unwrap())
 }
 
+pub fn format_timestamp_rfc3339(seconds: u64) -> String {
+    Utc.timestamp_opt(seconds.try_into().unwrap(), 0)
+        .unwrap()
+        .to_rfc3339()
+}
+
 pub fn format_timestamp_nanoseconds(nanoseconds: u64) -> String {
     format_datetime(Utc.timestamp_nanos(nanoseconds.try_into().unwrap()))
 }
@@ -32,16 +32,21 @@ use ic_nns_governance_api::{
     proposal::Action,
     reward_node_provider::{RewardMode, RewardToAccount},
     CanisterSettings, ClaimOrRefreshNeuronFromAccountResponse, GovernanceError,
-    ListNeuronsResponse, ListProposalInfoResponse, ManageNeuronResponse, NeuronInfo, NeuronState,
-    ProposalInfo, Vote,
+    ListNeuronsResponse, ListProposalInfoResponse, ManageNeuronResponse, Neuron, NeuronInfo,
+    NeuronState, ProposalInfo, Vote,
 };
 use indicatif::HumanBytes;
 use itertools::Itertools;
 
+use serde_json::json;
+
 use crate::lib::{
     e8s_to_tokens,
-    format::{format_duration_seconds, format_t_cycles, format_timestamp_seconds, icrc1_account},
-    get_default_role, get_idl_string, AnyhowResult,
+    format::{
+        format_duration_seconds, format_t_cycles, format_timestamp_rfc3339,
+        format_timestamp_seconds, icrc1_account,
+    },
+    get_default_role, get_idl_string, now_nanos, AnyhowResult,
 };
 
 fn nns_function_canister_and_method(
@@ -381,6 +386,300 @@ pub fn display_list_neurons(blob: &[u8]) -> AnyhowResult<String> {
     Ok(fmt)
 }
 
+/// The time after the voting power of a neuron was last refreshed (by voting, setting following,
+/// or refreshing it explicitly) after which its voting power starts to decay. Its following is
+/// cleared a month after that.
+const VOTING_POWER_REFRESH_PERIOD_SECONDS: u64 = 6 * 2_629_800;
+
+/// A neuron's line in `quill neuron-report`.
+struct NeuronReportRow {
+    id: Option<u64>,
+    stake_e8s: u64,
+    maturity_e8s: u64,
+    staked_maturity_e8s: u64,
+    state: NeuronState,
+    dissolve_delay_seconds: u64,
+    age_seconds: u64,
+    voting_power: u64,
+    followees: Vec<(Topic, Vec<u64>)>,
+    refresh_deadline_seconds: Option<u64>,
+}
+
+impl NeuronReportRow {
+    /// The dissolve delay, age and voting power are taken from the neuron's info, as computed by
+    /// the governance canister, and otherwise derived from the neuron.
+    fn new(neuron: Neuron, info: Option<&NeuronInfo>, now_seconds: u64) -> Self {
+        let dissolve_delay_seconds = match (info, neuron.dissolve_state) {
+            (Some(info), _) => info.dissolve_delay_seconds,
+            (None, Some(DissolveState::DissolveDelaySeconds(s))) => s,
+            (None, Some(DissolveState::WhenDissolvedTimestampSeconds(s))) => {
+                s.saturating_sub(now_seconds)
+            }
+            (None, None) => 0,
+        };
+        let age_seconds = match info {
+            Some(info) => info.age_seconds,
+            None if neuron.aging_since_timestamp_seconds == u64::MAX => 0,
+            None => now_seconds.saturating_sub(neuron.aging_since_timestamp_seconds),
+        };
+        let voting_power = match info {
+            Some(info) => info.deciding_voting_power.unwrap_or(info.voting_power),
+            None => neuron.deciding_voting_power.unwrap_or_default(),
+        };
+        let followees = neuron
+            .followees
+            .iter()
+            .map(|(&topic, followees)| {
+                (
+                    Topic::try_from(topic).unwrap_or(Topic::Unspecified),
+                    followees.followees.iter().map(|id| id.id).collect(),
+                )
+            })
+            .sorted_by_key(|(topic, _)| *topic as i32)
+            .collect();
+        Self {
+            id: neuron.id.map(|id| id.id),
+            stake_e8s: neuron
+                .cached_neuron_stake_e8s
+                .saturating_sub(neuron.neuron_fees_e8s),
+            maturity_e8s: neuron.maturity_e8s_equivalent,
+            staked_maturity_e8s: neuron.staked_maturity_e8s_equivalent.unwrap_or_default(),
+            state: neuron.state(now_seconds),
+            dissolve_delay_seconds,
+            age_seconds,
+            voting_power,
+            followees,
+            refresh_deadline_seconds: neuron
+                .voting_power_refreshed_timestamp_seconds
+                .map(|ts| ts + VOTING_POWER_REFRESH_PERIOD_SECONDS),
+        }
+    }
+
+    fn id(&self) -> String {
+        self.id
+            .map_or_else(|| "(unknown)".to_string(), |id| id.to_string())
+    }
+
+    fn display_followees(&self) -> String {
+        self.followees
+            .iter()
+            .format_with("; ", |(topic, ids), f| {
+                f(&format_args!("{topic:?}: {}", ids.iter().format(" ")))
+            })
+            .to_string()
+    }
+}
+
+fn neuron_report_rows(response: ListNeuronsResponse, now_seconds: u64) -> Vec<NeuronReportRow> {
+    let infos = response.neuron_infos;
+    response
+        .full_neurons
+        .into_iter()
+        .map(|neuron| {
+            let info = neuron.id.as_ref().and_then(|id| infos.get(&id.id));
+            NeuronReportRow::new(neuron, info, now_seconds)
+        })
+        .sorted_by_key(|row| row.id)
+        .collect()
+}
+
+/// Decodes the response to `quill neuron-report`, which only asks for the first page of neurons.
+fn decode_neuron_report(blob: &[u8]) -> AnyhowResult<ListNeuronsResponse> {
+    let response = Decode!(blob, ListNeuronsResponse)?;
+    if let Some(pages) = response.total_pages_available.filter(|&pages| pages > 1) {
+        bail!(
+            "The neurons span {pages} pages, and the report would only cover the first. \
+            Use `quill list-neurons` with the IDs of the neurons instead."
+        );
+    }
+    Ok(response)
+}
+
+/// Sums the stake, maturity, staked maturity, and voting power of the neurons.
+fn neuron_report_totals(rows: &[NeuronReportRow]) -> [u64; 4] {
+    rows.iter().fold([0; 4], |totals, row| {
+        [
+            totals[0] + row.stake_e8s,
+            totals[1] + row.maturity_e8s,
+            totals[2] + row.staked_maturity_e8s,
+            totals[3] + row.voting_power,
+        ]
+    })
+}
+
+/// Rounds the duration down to days, so that it fits in a table.
+fn format_duration_days(seconds: u64) -> String {
+    const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+    if seconds < SECONDS_PER_DAY {
+        "0 days".to_string()
+    } else {
+        format_duration_seconds(seconds - seconds % SECONDS_PER_DAY)
+    }
+}
+
+pub fn display_neuron_report(blob: &[u8]) -> AnyhowResult<String> {
+    let response = decode_neuron_report(blob)?;
+    neuron_report_text(response, now_nanos() / 1_000_000_000)
+}
+
+fn neuron_report_text(response: ListNeuronsResponse, now_seconds: u64) -> AnyhowResult<String> {
+    let rows = neuron_report_rows(response, now_seconds);
+    if rows.is_empty() {
+        return Ok("No neurons found".to_string());
+    }
+    let [stake, maturity, staked_maturity, voting_power] = neuron_report_totals(&rows);
+    let header = [
+        "Neuron",
+        "Stake (ICP)",
+        "Maturity",
+        "Staked maturity",
+        "State",
+        "Dissolve delay",
+        "Age",
+        "Voting power",
+        "Refresh by",
+    ]
+    .map(String::from);
+    let mut lines = vec![header];
+    for row in &rows {
+        lines.push([
+            row.id(),
+            e8s_to_tokens(row.stake_e8s.into()).to_string(),
+            e8s_to_tokens(row.maturity_e8s.into()).to_string(),
+            e8s_to_tokens(row.staked_maturity_e8s.into()).to_string(),
+            format!("{:?}", row.state),
+            format_duration_days(row.dissolve_delay_seconds),
+            format_duration_days(row.age_seconds),
+            e8s_to_tokens(row.voting_power.into()).to_string(),
+            row.refresh_deadline_seconds
+                .map_or_else(|| "-".to_string(), format_timestamp_seconds),
+        ]);
+    }
+    lines.push([
+        "Total".to_string(),
+        e8s_to_tokens(stake.into()).to_string(),
+        e8s_to_tokens(maturity.into()).to_string(),
+        e8s_to_tokens(staked_maturity.into()).to_string(),
+        String::new(),
+        String::new(),
+        String::new(),
+        e8s_to_tokens(voting_power.into()).to_string(),
+        String::new(),
+    ]);
+    let widths: Vec<usize> = (0..lines[0].len())
+        .map(|col| lines.iter().map(|line| line[col].len()).max().unwrap_or(0))
+        .collect();
+    let mut fmt = String::new();
+    for line in &lines {
+        let line = line
+            .iter()
+            .zip(&widths)
+            .format_with("  ", |(cell, &width), f| f(&format_args!("{cell:width$}")));
+        writeln!(fmt, "{}", line.to_string().trim_end())?;
+    }
+    let followed = rows.iter().filter(|row| !row.followees.is_empty());
+    for (i, row) in followed.enumerate() {
+        if i == 0 {
+            writeln!(fmt, "\nFollowees:")?;
+        }
+        writeln!(fmt, "  Neuron {}: {}", row.id(), row.display_followees())?;
+    }
+    Ok(fmt)
+}
+
+pub fn display_neuron_report_csv(blob: &[u8]) -> AnyhowResult<String> {
+    let response = decode_neuron_report(blob)?;
+    neuron_report_csv(response, now_nanos() / 1_000_000_000)
+}
+
+fn neuron_report_csv(response: ListNeuronsResponse, now_seconds: u64) -> AnyhowResult<String> {
+    fn escape(field: &str) -> String {
+        if field.contains([',', '"', '\n']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    }
+    let rows = neuron_report_rows(response, now_seconds);
+    let [stake, maturity, staked_maturity, voting_power] = neuron_report_totals(&rows);
+    let mut fmt = String::from(
+        "neuron_id,stake_icp,maturity_icp,staked_maturity_icp,state,dissolve_delay_seconds,\
+        age_seconds,voting_power,refresh_deadline,followees\n",
+    );
+    for row in &rows {
+        writeln!(
+            fmt,
+            "{},{},{},{},{:?},{},{},{},{},{}",
+            row.id.map(|id| id.to_string()).unwrap_or_default(),
+            e8s_to_tokens(row.stake_e8s.into()),
+            e8s_to_tokens(row.maturity_e8s.into()),
+            e8s_to_tokens(row.staked_maturity_e8s.into()),
+            row.state,
+            row.dissolve_delay_seconds,
+            row.age_seconds,
+            e8s_to_tokens(row.voting_power.into()),
+            row.refresh_deadline_seconds
+                .map(format_timestamp_rfc3339)
+                .unwrap_or_default(),
+            escape(&row.display_followees()),
+        )?;
+    }
+    writeln!(
+        fmt,
+        "Total,{},{},{},,,,{},,",
+        e8s_to_tokens(stake.into()),
+        e8s_to_tokens(maturity.into()),
+        e8s_to_tokens(staked_maturity.into()),
+        e8s_to_tokens(voting_power.into()),
+    )?;
+    Ok(fmt)
+}
+
+pub fn display_neuron_report_json(blob: &[u8]) -> AnyhowResult<String> {
+    let response = decode_neuron_report(blob)?;
+    neuron_report_json(response, now_nanos() / 1_000_000_000)
+}
+
+fn neuron_report_json(response: ListNeuronsResponse, now_seconds: u64) -> AnyhowResult<String> {
+    let rows = neuron_report_rows(response, now_seconds);
+    let [stake, maturity, staked_maturity, voting_power] = neuron_report_totals(&rows);
+    let neurons = rows
+        .iter()
+        .map(|row| {
+            json!({
+                // neuron IDs are too large for a JSON number to hold exactly
+                "neuron_id": row.id.map(|id| id.to_string()),
+                "stake_icp": e8s_to_tokens(row.stake_e8s.into()).to_string(),
+                "maturity_icp": e8s_to_tokens(row.maturity_e8s.into()).to_string(),
+                "staked_maturity_icp": e8s_to_tokens(row.staked_maturity_e8s.into()).to_string(),
+                "state": format!("{:?}", row.state),
+                "dissolve_delay_seconds": row.dissolve_delay_seconds,
+                "age_seconds": row.age_seconds,
+                "voting_power": e8s_to_tokens(row.voting_power.into()).to_string(),
+                "refresh_deadline_seconds": row.refresh_deadline_seconds,
+                "followees": row
+                    .followees
+                    .iter()
+                    .map(|(topic, ids)| {
+                        let ids = ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+                        (format!("{topic:?}"), json!(ids))
+                    })
+                    .collect::<serde_json::Map<_, _>>(),
+            })
+        })
+        .collect::<Vec<_>>();
+    let report = json!({
+        "neurons": neurons,
+        "totals": {
+            "stake_icp": e8s_to_tokens(stake.into()).to_string(),
+            "maturity_icp": e8s_to_tokens(maturity.into()).to_string(),
+            "staked_maturity_icp": e8s_to_tokens(staked_maturity.into()).to_string(),
+            "voting_power": e8s_to_tokens(voting_power.into()).to_string(),
+        },
+    });
+    Ok(serde_json::to_string_pretty(&report)?)
+}
+
 pub fn display_manage_neuron(blob: &[u8]) -> AnyhowResult<String> {
     let response = Decode!(blob, ManageNeuronResponse)?;
     let cmd = response.command.context("command was null")?;
@@ -1271,3 +1570,52 @@ fn display_canister_settings(settings: CanisterSettings) -> AnyhowResult<String>
         Ok(fmt)
     }
 }
+
+#[test]
+fn neuron_report_csv_rows() {
+    use ic_nns_common::pb::v1::NeuronId;
+
+    let now_seconds = 1_700_000_000;
+    let neuron = |id, stake_e8s| Neuron {
+        id: Some(NeuronId { id }),
+        cached_neuron_stake_e8s: stake_e8s,
+        maturity_e8s_equivalent: 50_000_000,
+        aging_since_timestamp_seconds: now_seconds - 86_400,
+        dissolve_state: Some(DissolveState::DissolveDelaySeconds(15_778_800)),
+        voting_power_refreshed_timestamp_seconds: Some(now_seconds),
+        ..Default::default()
+    };
+    // the voting power, age and dissolve delay are taken from the neuron's info where there is one
+    let info = NeuronInfo {
+        dissolve_delay_seconds: 15_778_800,
+        age_seconds: 172_800,
+        deciding_voting_power: Some(450_000_000),
+        ..Default::default()
+    };
+    let response = ListNeuronsResponse {
+        full_neurons: vec![neuron(2, 300_000_000), neuron(1, 100_000_000)],
+        neuron_infos: [(2, info)].into_iter().collect(),
+        ..Default::default()
+    };
+    let csv = neuron_report_csv(response, now_seconds).unwrap();
+    let lines = csv.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[1].starts_with("1,1,0.5,0,NotDissolving,15778800,86400,0,"));
+    assert!(lines[2].starts_with("2,3,0.5,0,NotDissolving,15778800,172800,4.5,"));
+    assert!(lines[3].starts_with("Total,4,1,0,,,,4.5,"));
+}
+
+#[test]
+fn neuron_report_pages() {
+    let response = |total_pages_available| ListNeuronsResponse {
+        total_pages_available,
+        ..Default::default()
+    };
+    let blob = candid::Encode!(&response(Some(1))).unwrap();
+    assert_eq!(display_neuron_report(&blob).unwrap(), "No neurons found");
+    let blob = candid::Encode!(&response(Some(3))).unwrap();
+    assert!(display_neuron_report_csv(&blob)
+        .unwrap_err()
+        .to_string()
+        .starts_with("The neurons span 3 pages"));
+}
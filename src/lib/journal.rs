@@ -0,0 +1,188 @@
+//! The journal `quill send` keeps of the messages it has submitted, so that an interrupted send can
+//! be run again without submitting anything twice.
+
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context};
+use ic_agent::RequestId;
+use serde::{Deserialize, Serialize};
+
+use super::AnyhowResult;
+
+/// How far along a message is. Only the latest status of each request is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubmissionStatus {
+    /// The IC accepted the message, but its result is not known yet.
+    Submitted,
+    Replied,
+    Rejected,
+}
+
+/// One line of the journal file.
+#[derive(Serialize, Deserialize)]
+struct JournalEntry {
+    request_id: String,
+    status: SubmissionStatus,
+}
+
+/// An append-only file of JSON lines recording the status of each submitted request.
+pub struct Journal {
+    path: PathBuf,
+    /// Opened by [`Journal::prepare`], so that the file is only created once a message is about to
+    /// be submitted.
+    file: Option<File>,
+    /// Whether the file could not be opened, in which case nothing is written to it.
+    unwritable: bool,
+    /// Whether the last line of the file was cut short, e.g. because quill was killed while writing it.
+    cut_short: bool,
+    statuses: HashMap<String, SubmissionStatus>,
+}
+
+impl Journal {
+    /// Reads the journal at `path`, if it exists, without creating or changing it.
+    pub fn read(path: &Path) -> AnyhowResult<Self> {
+        let contents = read_contents(path)?.unwrap_or_default();
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: None,
+            unwritable: false,
+            cut_short: !contents.is_empty() && !contents.ends_with('\n'),
+            statuses: parse(path, &contents)?,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Opens the journal for writing, creating it if it does not exist. If that fails, e.g. on
+    /// read-only media, prints a warning and carries on without writing to it.
+    pub fn prepare(&mut self) {
+        if self.file.is_some() || self.unwritable {
+            return;
+        }
+        match self.open_file() {
+            Ok(file) => self.file = Some(file),
+            Err(e) => {
+                self.unwritable = true;
+                eprintln!(
+                    "Warning: {e:#}. Sending without a journal, so if sending is interrupted, \
+                    running the same command again may send messages twice."
+                );
+            }
+        }
+    }
+
+    fn open_file(&self) -> AnyhowResult<File> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Couldn't open journal {}", self.path.display()))?;
+        if self.cut_short {
+            // start a new line after the one that was cut short
+            file.write_all(b"\n")
+                .with_context(|| format!("Couldn't write to journal {}", self.path.display()))?;
+        }
+        Ok(file)
+    }
+
+    /// Returns the latest recorded status of the request, if it was ever submitted.
+    pub fn status(&self, request_id: RequestId) -> Option<SubmissionStatus> {
+        self.statuses.get(&String::from(request_id)).copied()
+    }
+
+    /// Records a new status for the request, and makes sure it is on disk before returning.
+    pub fn record(&mut self, request_id: RequestId, status: SubmissionStatus) -> AnyhowResult {
+        let entry = JournalEntry {
+            request_id: String::from(request_id),
+            status,
+        };
+        self.prepare();
+        if let Some(file) = &mut self.file {
+            let line = format!("{}\n", serde_json::to_string(&entry)?);
+            file.write_all(line.as_bytes())
+                .and_then(|_| file.sync_data())
+                .with_context(|| format!("Couldn't write to journal {}", self.path.display()))?;
+        }
+        self.statuses.insert(entry.request_id, status);
+        Ok(())
+    }
+}
+
+fn read_contents(path: &Path) -> AnyhowResult<Option<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Couldn't read journal {}", path.display())),
+    }
+}
+
+/// Returns the latest status of each request in the journal's contents.
+fn parse(path: &Path, contents: &str) -> AnyhowResult<HashMap<String, SubmissionStatus>> {
+    let mut statuses = HashMap::new();
+    let lines = contents.lines().filter(|line| !line.trim().is_empty());
+    let count = lines.clone().count();
+    for (i, line) in lines.enumerate() {
+        match serde_json::from_str::<JournalEntry>(line) {
+            Ok(entry) => {
+                statuses.insert(entry.request_id, entry.status);
+            }
+            // the last line may have been cut short if quill was killed while writing it
+            Err(_) if i == count - 1 => {}
+            Err(e) => bail!("Invalid journal {}: {e}", path.display()),
+        }
+    }
+    Ok(statuses)
+}
+
+#[test]
+fn journal_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("message.json.journal");
+    let first = RequestId::new(&[1; 32]);
+    let second = RequestId::new(&[2; 32]);
+    let mut journal = Journal::read(&path).unwrap();
+    assert_eq!(journal.status(first), None);
+    // the file is only created once something is about to be written
+    assert!(!path.exists());
+    journal.record(first, SubmissionStatus::Submitted).unwrap();
+    journal.record(second, SubmissionStatus::Submitted).unwrap();
+    journal.record(first, SubmissionStatus::Replied).unwrap();
+    // as if quill was killed in the middle of a write
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap()
+        .write_all(br#"{"request_id":"03"#)
+        .unwrap();
+    let mut journal = Journal::read(&path).unwrap();
+    assert_eq!(journal.status(first), Some(SubmissionStatus::Replied));
+    assert_eq!(journal.status(second), Some(SubmissionStatus::Submitted));
+    journal.record(second, SubmissionStatus::Rejected).unwrap();
+    let journal = Journal::read(&path).unwrap();
+    assert_eq!(journal.status(second), Some(SubmissionStatus::Rejected));
+}
+
+#[test]
+fn journal_unwritable() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("missing").join("message.json.journal");
+    let mut journal = Journal::read(&path).unwrap();
+    journal.prepare();
+    let request_id = RequestId::new(&[1; 32]);
+    journal
+        .record(request_id, SubmissionStatus::Submitted)
+        .unwrap();
+    assert_eq!(
+        journal.status(request_id),
+        Some(SubmissionStatus::Submitted)
+    );
+    assert!(!path.exists());
+}
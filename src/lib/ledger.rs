@@ -1,11 +1,14 @@
 use std::{
     cell::Cell,
-    fmt,
+    env, fmt,
+    io::{Read, Write},
+    net::TcpStream,
+    ops::Deref,
     sync::{Arc, Mutex, Weak},
     time::Duration,
 };
 
-use anyhow::{ensure, Context};
+use anyhow::{anyhow, ensure, Context};
 use bip32::DerivationPath;
 use candid::Principal;
 use hidapi::HidApi;
@@ -19,10 +22,7 @@ use pkcs8::EncodePublicKey;
 use serde::Serialize;
 use serde_cbor::Serializer;
 
-use super::{
-    derivation_path, genesis_token_canister_id, governance_canister_id, ledger_canister_id,
-    AnyhowResult,
-};
+use super::{genesis_token_canister_id, governance_canister_id, ledger_canister_id, AnyhowResult};
 
 const CLA: u8 = 0x11;
 const GET_VERSION: u8 = 0x00;
@@ -57,26 +57,88 @@ thread_local! {
 }
 
 struct LedgerIdentityInner {
-    transport: Mutex<TransportNativeHID>,
+    transport: Mutex<Transport>,
+}
+
+/// How APDUs are exchanged with the IC app.
+enum Transport {
+    Hid(TransportNativeHID),
+    /// The APDU port of the Speculos emulator, which runs the app without a physical device.
+    Tcp(TransportTcp),
+}
+
+impl Transport {
+    fn exchange<I: Deref<Target = [u8]>>(
+        &self,
+        command: &APDUCommand<I>,
+    ) -> AnyhowResult<APDUAnswer<Vec<u8>>> {
+        match self {
+            Self::Hid(transport) => Ok(transport.exchange(command)?),
+            Self::Tcp(transport) => transport.exchange(command),
+        }
+    }
+}
+
+struct TransportTcp {
+    stream: TcpStream,
+}
+
+impl TransportTcp {
+    fn connect(address: &str) -> AnyhowResult<Self> {
+        let stream = TcpStream::connect(address)
+            .with_context(|| format!("Couldn't connect to Speculos at {address}"))?;
+        Ok(Self { stream })
+    }
+
+    fn exchange<I: Deref<Target = [u8]>>(
+        &self,
+        command: &APDUCommand<I>,
+    ) -> AnyhowResult<APDUAnswer<Vec<u8>>> {
+        // Speculos prefixes each APDU with its length, and each response with the length of its
+        // data, excluding the status word.
+        let apdu = command.serialize();
+        let mut stream = &self.stream;
+        stream.write_all(&(apdu.len() as u32).to_be_bytes())?;
+        stream.write_all(&apdu)?;
+        let mut len = [0; 4];
+        stream.read_exact(&mut len)?;
+        let mut answer = vec![0; u32::from_be_bytes(len) as usize + 2];
+        stream.read_exact(&mut answer)?;
+        APDUAnswer::from_answer(answer).map_err(|_| anyhow!("Malformed response from Speculos"))
+    }
 }
 
 /// An [`Identity`] backed by a Ledger device.
 pub struct LedgerIdentity {
     inner: Arc<LedgerIdentityInner>,
+    /// The path of the key to sign with.
+    path: DerivationPath,
 }
 
 impl LedgerIdentity {
-    /// Creates a new ledger-device-backed identity.
-    pub fn new() -> AnyhowResult<Self> {
+    /// Creates a new ledger-device-backed identity, signing with the key at `path`. With a
+    /// `speculos` address (e.g. `127.0.0.1:9999`), or `QUILL_LEDGER_SPECULOS` if there is none, it
+    /// talks to the Speculos emulator instead of a device.
+    pub fn new(path: DerivationPath, speculos: Option<&str>) -> AnyhowResult<Self> {
         let mut global = GLOBAL_HANDLE.lock().unwrap();
         if let Some(existing) = global.upgrade() {
-            Ok(Self { inner: existing })
+            Ok(Self {
+                inner: existing,
+                path,
+            })
         } else {
+            let speculos = speculos
+                .map(String::from)
+                .or_else(|| env::var("QUILL_LEDGER_SPECULOS").ok());
+            let transport = match speculos {
+                Some(address) => Transport::Tcp(TransportTcp::connect(&address)?),
+                None => Transport::Hid(TransportNativeHID::new(&HidApi::new().unwrap())?),
+            };
             let inner = Arc::new(LedgerIdentityInner {
-                transport: Mutex::new(TransportNativeHID::new(&HidApi::new().unwrap())?),
+                transport: Mutex::new(transport),
             });
             *global = Arc::downgrade(&inner);
-            Ok(Self { inner })
+            Ok(Self { inner, path })
         }
     }
     /// Within the provided scope, transfers will be marked as 'staking' transactions.
@@ -95,7 +157,6 @@ impl LedgerIdentity {
         })
     }
     /// Gets the version of the IC app.
-    #[allow(unused)]
     pub fn version(&self) -> AnyhowResult<LedgerVersion> {
         get_version(&self.inner.transport.lock().unwrap())
     }
@@ -104,22 +165,20 @@ impl LedgerIdentity {
         let spinner = ProgressBar::new_spinner();
         spinner.set_message("Confirm principal on Ledger device...");
         spinner.enable_steady_tick(Duration::from_millis(100));
-        display_pk(&self.inner.transport.lock().unwrap(), &derivation_path())?;
+        display_pk(&self.inner.transport.lock().unwrap(), &self.path)?;
         spinner.finish_and_clear();
         Ok(())
     }
     /// Gets the public key from the ledger that [`sender`](Self::sender) will return a principal derived from.
     #[allow(unused)]
     pub fn public_key(&self) -> AnyhowResult<(Principal, Vec<u8>)> {
-        get_identity(&self.inner.transport.lock().unwrap(), &derivation_path())
-            .map_err(anyhow::Error::msg)
+        get_identity(&self.inner.transport.lock().unwrap(), &self.path).map_err(anyhow::Error::msg)
     }
 }
 
 impl Identity for LedgerIdentity {
     fn sender(&self) -> Result<Principal, String> {
-        let (principal, _) =
-            get_identity(&self.inner.transport.lock().unwrap(), &derivation_path())?;
+        let (principal, _) = get_identity(&self.inner.transport.lock().unwrap(), &self.path)?;
         Ok(principal)
     }
     /// Sign a request ID from a content map.
@@ -127,10 +186,10 @@ impl Identity for LedgerIdentity {
     /// The behavior of this function is affected by whether it is in a [`with_staking`](Self::with_staking) scope or not.
     #[allow(clippy::bool_to_int_with_if)]
     fn sign(&self, content: &EnvelopeContent) -> Result<Signature, String> {
-        let path = derivation_path();
+        let path = &self.path;
         let next_stake = NEXT_STAKE.with(|next_stake| next_stake.replace(false));
         let transport = self.inner.transport.lock().unwrap();
-        let (_, pk) = get_identity(&transport, &path)?;
+        let (_, pk) = get_identity(&transport, path)?;
         // The IC ledger app expects to receive the entire envelope, sans signature.
         #[derive(Serialize)]
         struct Envelope<'a> {
@@ -155,7 +214,7 @@ impl Identity for LedgerIdentity {
             &blob,
             // See with_staking
             if next_stake { TX_STAKING } else { TX_NORMAL },
-            &path,
+            path,
             content,
         )?;
         spinner.finish_with_message(format!("Confirmed {message} on Ledger device"));
@@ -167,7 +226,7 @@ impl Identity for LedgerIdentity {
     }
 
     fn public_key(&self) -> Option<Vec<u8>> {
-        let (_, pk) = get_identity(&self.inner.transport.lock().unwrap(), &derivation_path())
+        let (_, pk) = get_identity(&self.inner.transport.lock().unwrap(), &self.path)
             .map_err(|e| e.to_string())
             .ok()?;
         Some(pk)
@@ -182,7 +241,7 @@ fn serialize_path(path: &DerivationPath) -> Vec<u8> {
 }
 
 fn get_identity(
-    transport: &TransportNativeHID,
+    transport: &Transport,
     path: &DerivationPath,
 ) -> Result<(Principal, Vec<u8>), String> {
     let command = APDUCommand {
@@ -275,7 +334,7 @@ pub fn supported_transaction(canister_id: &Principal, method_name: &str) -> bool
 }
 
 fn sign_blob(
-    transport: &TransportNativeHID,
+    transport: &Transport,
     blob: &[u8],
     txtype: u8,
     path: &DerivationPath,
@@ -306,7 +365,7 @@ fn sign_blob(
 }
 
 fn sign_chunk(
-    transport: &TransportNativeHID,
+    transport: &Transport,
     kind: u8,
     chunk: &[u8],
     txtype: u8,
@@ -335,7 +394,7 @@ fn sign_chunk(
     }
 }
 
-fn get_version(transport: &TransportNativeHID) -> AnyhowResult<LedgerVersion> {
+fn get_version(transport: &Transport) -> AnyhowResult<LedgerVersion> {
     let command = APDUCommand {
         cla: CLA,
         ins: GET_VERSION,
@@ -356,7 +415,7 @@ fn get_version(transport: &TransportNativeHID) -> AnyhowResult<LedgerVersion> {
     })
 }
 
-fn display_pk(transport: &TransportNativeHID, path: &DerivationPath) -> AnyhowResult<()> {
+fn display_pk(transport: &Transport, path: &DerivationPath) -> AnyhowResult<()> {
     let command = APDUCommand {
         cla: CLA,
         ins: GET_ADDR_SECP256K1,
@@ -384,3 +443,30 @@ impl fmt::Display for LedgerVersion {
         write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
     }
 }
+
+#[test]
+fn speculos_transport() {
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let speculos = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0; 9];
+        stream.read_exact(&mut request).unwrap();
+        assert_eq!(request, [0, 0, 0, 5, CLA, GET_VERSION, 0, 0, 0]);
+        stream
+            .write_all(&[0, 0, 0, 4, 0, 2, 4, 9, 0x90, 0x00])
+            .unwrap();
+        stream.read_exact(&mut request).unwrap();
+        // CLA not supported, as when the IC app is not open
+        stream.write_all(&[0, 0, 0, 0, 0x6E, 0x00]).unwrap();
+    });
+    let transport = Transport::Tcp(TransportTcp::connect(&address).unwrap());
+    assert_eq!(get_version(&transport).unwrap().to_string(), "2.4.9");
+    assert_eq!(
+        get_version(&transport).unwrap_err().to_string(),
+        "Error fetching version from Ledger: IC app not open on device"
+    );
+    speculos.join().unwrap();
+}
@@ -2,18 +2,19 @@
 
 use anyhow::{anyhow, bail, ensure, Context};
 use bigdecimal::BigDecimal;
-use bip32::DerivationPath;
+use bip32::{ChildNumber, DerivationPath};
 use bip39::{Mnemonic, Seed};
 use candid::{
     types::{Function, TypeInner},
-    Nat, Principal, TypeEnv,
+    IDLArgs, Nat, Principal, TypeEnv,
 };
 use candid_parser::{typing::check_prog, IDLProg};
 use crc32fast::Hasher;
 use data_encoding::BASE32_NOPAD;
 use ic_agent::{
-    identity::{AnonymousIdentity, BasicIdentity, Secp256k1Identity},
-    Agent, Identity,
+    agent::EnvelopeContent,
+    identity::{AnonymousIdentity, BasicIdentity, DelegatedIdentity, Secp256k1Identity},
+    Agent, Identity, Signature,
 };
 use ic_base_types::PrincipalId;
 #[cfg(feature = "hsm")]
@@ -24,9 +25,17 @@ use ic_nns_constants::{
 };
 use icp_ledger::{AccountIdentifier, Subaccount};
 use icrc_ledger_types::icrc1::account::Account;
+use k256::elliptic_curve::zeroize::Zeroizing;
 use k256::SecretKey;
-use pkcs8::pkcs5::{pbes2::Parameters, scrypt::Params};
-use ring::signature::Ed25519KeyPair;
+use num_bigint::Sign;
+use pkcs8::{
+    der::Encode,
+    pkcs5::{pbes2::Parameters, scrypt::Params},
+    AlgorithmIdentifierRef, EncryptedPrivateKeyInfo, ObjectIdentifier, PrivateKeyInfo,
+};
+use rand::{thread_rng, RngCore};
+use ring::{hmac, signature::Ed25519KeyPair};
+use sec1::{pem::PemLabel, LineEnding};
 
 use std::{
     env,
@@ -41,6 +50,7 @@ use std::{cell::RefCell, path::PathBuf};
 
 #[cfg(feature = "ledger")]
 use self::ledger::LedgerIdentity;
+use self::{delegation::DelegationChain, network::Network};
 
 pub const IC_URL: &str = "https://ic0.app";
 
@@ -48,13 +58,48 @@ pub fn get_ic_url() -> String {
     env::var("IC_URL").unwrap_or_else(|_| IC_URL.to_string())
 }
 
+pub mod delegation;
+pub mod dfx;
+pub mod eth;
 pub mod format;
+pub mod journal;
 #[cfg(feature = "ledger")]
 pub mod ledger;
+pub mod network;
+pub mod qr;
 pub mod signing;
+pub mod slip39;
 
 pub type AnyhowResult<T = ()> = anyhow::Result<T>;
 
+/// The default time after which signed messages expire. This is also the furthest in the future
+/// that the IC accepts an expiry time to be when a message is submitted.
+pub const DEFAULT_INGRESS_EXPIRY: Duration = Duration::from_secs(5 * 60);
+
+/// The longest time after which signed messages can be set to expire, including their later
+/// copies. As the IC only accepts a message within five minutes of its expiry, a longer time would
+/// only be a mistake, such as a unit left off.
+pub const MAX_INGRESS_EXPIRY: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Settings controlling the expiry of signed messages, from `--ingress-expiry` and
+/// `--ingress-expiry-copies`.
+#[derive(Debug, Clone, Copy)]
+pub struct IngressExpiryOpts {
+    /// Time from signing after which the (first copy of each) message expires.
+    pub ingress_expiry: Duration,
+    /// Number of copies of each message to sign, with staggered expiry times.
+    pub copies: u32,
+}
+
+impl Default for IngressExpiryOpts {
+    fn default() -> Self {
+        Self {
+            ingress_expiry: DEFAULT_INGRESS_EXPIRY,
+            copies: 1,
+        }
+    }
+}
+
 #[cfg(feature = "hsm")]
 #[derive(Debug)]
 pub struct HSMInfo {
@@ -94,6 +139,23 @@ impl HSMInfo {
             pin: RefCell::new(None),
         })
     }
+
+    /// Returns the PIN from `QUILL_HSM_PIN`, or else asks for it once and remembers it.
+    pub fn pin(&self) -> Result<String, String> {
+        let user_set_pin = { self.pin.borrow().clone() };
+        match user_set_pin {
+            None => match read_pkcs11_pin_env_var() {
+                Ok(Some(pin)) => Ok(pin),
+                Ok(None) => {
+                    let pin = ask_pkcs11_pin_via_tty()?;
+                    *self.pin.borrow_mut() = Some(pin.clone());
+                    Ok(pin)
+                }
+                Err(e) => Err(e),
+            },
+            Some(pin) => Ok(pin),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -110,7 +172,23 @@ pub enum AuthInfo {
     Pkcs11Hsm(HSMInfo),
     /// Ledger Nano with the Internet Computer app installed
     #[cfg(feature = "ledger")]
-    Ledger,
+    Ledger {
+        /// The path of the key to sign with, selected with --derivation-index or
+        /// --derivation-path.
+        derivation_path: DerivationPath,
+        /// The address of the Speculos emulator to talk to instead of a device, from
+        /// --ledger-speculos.
+        speculos: Option<String>,
+    },
+    /// A session key, signing as the principal of the delegation chain provided via
+    /// --delegation-file.
+    Delegated {
+        session_key: Box<AuthInfo>,
+        chain: DelegationChain,
+    },
+    /// Only the principal of the signer, provided via --sender or taken from the key with
+    /// --unsigned-output; messages are constructed unsigned, to be signed with `quill sign`.
+    SenderOnly(Principal),
 }
 
 pub fn ledger_canister_id() -> Principal {
@@ -141,6 +219,18 @@ pub fn ckbtc_canister_id(testnet: bool) -> Principal {
     }
 }
 
+pub fn ckbtc_index_canister_id(testnet: bool) -> Principal {
+    if testnet {
+        Principal::from_text("mm444-5iaaa-aaaar-qaabq-cai").unwrap()
+    } else {
+        Principal::from_text("n5wcd-faaaa-aaaar-qaaea-cai").unwrap()
+    }
+}
+
+pub fn icp_index_canister_id() -> Principal {
+    Principal::from_text("qhbym-qaaaa-aaaaa-aaafq-cai").unwrap()
+}
+
 pub fn ckbtc_minter_canister_id(testnet: bool) -> Principal {
     if testnet {
         Principal::from_text("ml52i-qqaaa-aaaar-qaaba-cai").unwrap()
@@ -149,13 +239,32 @@ pub fn ckbtc_minter_canister_id(testnet: bool) -> Principal {
     }
 }
 
+pub fn cketh_canister_id(testnet: bool) -> Principal {
+    if testnet {
+        Principal::from_text("apia6-jaaaa-aaaar-qabma-cai").unwrap()
+    } else {
+        Principal::from_text("ss2fx-dyaaa-aaaar-qacoq-cai").unwrap()
+    }
+}
+
+pub fn cketh_minter_canister_id(testnet: bool) -> Principal {
+    if testnet {
+        Principal::from_text("jzenf-aiaaa-aaaar-qaa7q-cai").unwrap()
+    } else {
+        Principal::from_text("sv3dd-oaaaa-aaaar-qacoa-cai").unwrap()
+    }
+}
+
 pub const ROLE_NNS_GOVERNANCE: &str = "nns:governance";
 pub const ROLE_NNS_LEDGER: &str = "nns:ledger";
+pub const ROLE_NNS_INDEX: &str = "nns:index";
 pub const ROLE_NNS_GTC: &str = "nns:gtc";
 pub const ROLE_NNS_REGISTRY: &str = "nns:registry";
 pub const ROLE_SNS_WASM: &str = "nns:sns-wasm";
 pub const ROLE_ICRC1_LEDGER: &str = "icrc1:ledger";
+pub const ROLE_ICRC1_INDEX: &str = "icrc1:index";
 pub const ROLE_CKBTC_MINTER: &str = "ckbtc:minter";
+pub const ROLE_CKETH_MINTER: &str = "cketh:minter";
 pub const ROLE_SNS_GOVERNANCE: &str = "sns:governance";
 pub const ROLE_SNS_ROOT: &str = "sns:root";
 pub const ROLE_SNS_SWAP: &str = "sns:swap";
@@ -165,16 +274,28 @@ pub fn get_default_role(canister_id: Principal) -> Option<&'static str> {
         Some(ROLE_NNS_GOVERNANCE)
     } else if canister_id == ledger_canister_id() {
         Some(ROLE_NNS_LEDGER)
+    } else if canister_id == icp_index_canister_id() {
+        Some(ROLE_NNS_INDEX)
     } else if canister_id == genesis_token_canister_id() {
         Some(ROLE_NNS_GTC)
     } else if canister_id == registry_canister_id() {
         Some(ROLE_NNS_REGISTRY)
     } else if canister_id == ckbtc_canister_id(false) || canister_id == ckbtc_canister_id(true) {
         Some(ROLE_ICRC1_LEDGER)
+    } else if canister_id == ckbtc_index_canister_id(false)
+        || canister_id == ckbtc_index_canister_id(true)
+    {
+        Some(ROLE_ICRC1_INDEX)
     } else if canister_id == ckbtc_minter_canister_id(false)
         || canister_id == ckbtc_minter_canister_id(true)
     {
         Some(ROLE_CKBTC_MINTER)
+    } else if canister_id == cketh_canister_id(false) || canister_id == cketh_canister_id(true) {
+        Some(ROLE_ICRC1_LEDGER)
+    } else if canister_id == cketh_minter_canister_id(false)
+        || canister_id == cketh_minter_canister_id(true)
+    {
+        Some(ROLE_CKETH_MINTER)
     } else {
         None
     }
@@ -184,10 +305,13 @@ pub fn get_local_candid(canister_id: Principal, role: &str) -> AnyhowResult<&'st
     Ok(match role {
         ROLE_NNS_GOVERNANCE => include_str!("../../candid/governance.did"),
         ROLE_NNS_LEDGER => include_str!("../../candid/ledger.did"),
+        ROLE_NNS_INDEX => include_str!("../../candid/icp-index.did"),
         ROLE_NNS_GTC => include_str!("../../candid/gtc.did"),
         ROLE_NNS_REGISTRY => include_str!("../../candid/registry.did"),
         ROLE_ICRC1_LEDGER => include_str!("../../candid/icrc1.did"),
+        ROLE_ICRC1_INDEX => include_str!("../../candid/icrc1-index.did"),
         ROLE_CKBTC_MINTER => include_str!("../../candid/ckbtc_minter.did"),
+        ROLE_CKETH_MINTER => include_str!("../../candid/cketh_minter.did"),
         ROLE_SNS_WASM => include_str!("../../candid/snsw.did"),
         ROLE_SNS_GOVERNANCE => include_str!("../../candid/sns-governance.did"),
         ROLE_SNS_ROOT => include_str!("../../candid/sns-root.did"),
@@ -198,39 +322,48 @@ Unknown recipient '{role}' in message!
 Recipient: {canister_id}
 Should be one of: 
 - NNS Ledger: {ledger}
+- NNS Ledger index: {icp_index}
 - Governance: {governance}
 - Genesis: {genesis}
 - Registry: {registry}
 - ckBTC minter: {ckbtc_minter}
 - ckBTC ledger: {ckbtc}
+- ckBTC index: {ckbtc_index}
+- ckETH minter: {cketh_minter}
+- ckETH ledger: {cketh}
 - SNS-WASM: {sns_wasm}
 - SNS Governance
 - SNS Ledger
+- SNS Index
 - SNS Root
 - SNS Swap",
             ledger = ledger_canister_id(),
+            icp_index = icp_index_canister_id(),
             governance = governance_canister_id(),
             genesis = genesis_token_canister_id(),
             registry = registry_canister_id(),
             ckbtc_minter = ckbtc_minter_canister_id(false),
             ckbtc = ckbtc_canister_id(false),
+            ckbtc_index = ckbtc_index_canister_id(false),
+            cketh_minter = cketh_minter_canister_id(false),
+            cketh = cketh_canister_id(false),
             sns_wasm = sns_wasm_canister_id(),
         ),
     })
 }
 
-pub fn get_idl_string(
+fn decode_idl_args(
     blob: &[u8],
     canister_id: Principal,
     role: &str,
     method_name: &str,
     part: &str,
-) -> AnyhowResult<String> {
+) -> AnyhowResult<IDLArgs> {
     let spec = get_local_candid(canister_id, role)?;
     let method_type = get_candid_type(spec, method_name);
     let result = match method_type {
-        None => candid::IDLArgs::from_bytes(blob),
-        Some((env, func)) => candid::IDLArgs::from_bytes_with_types(
+        None => IDLArgs::from_bytes(blob),
+        Some((env, func)) => IDLArgs::from_bytes_with_types(
             blob,
             &env,
             if part == "args" {
@@ -240,7 +373,74 @@ pub fn get_idl_string(
             },
         ),
     };
-    Ok(format!("{}", result?))
+    Ok(result?)
+}
+
+pub fn get_idl_string(
+    blob: &[u8],
+    canister_id: Principal,
+    role: &str,
+    method_name: &str,
+    part: &str,
+) -> AnyhowResult<String> {
+    let args = decode_idl_args(blob, canister_id, role, method_name, part)?;
+    Ok(format!("{args}"))
+}
+
+/// Returns the JSON encoding of a candid value, as decoded with the types from the local candid files.
+pub fn get_json_string(
+    blob: &[u8],
+    canister_id: Principal,
+    role: &str,
+    method_name: &str,
+    part: &str,
+) -> AnyhowResult<String> {
+    let args = decode_idl_args(blob, canister_id, role, method_name, part)?;
+    Ok(serde_json::to_string_pretty(
+        &format::json::idl_args_to_json(&args),
+    )?)
+}
+
+/// Returns the JSON encoding of a response, in the shape of the report if one was asked for.
+pub fn display_json_response(
+    blob: &[u8],
+    canister_id: Principal,
+    role: &str,
+    method_name: &str,
+    part: &str,
+    opts: &DisplayOpts,
+) -> AnyhowResult<String> {
+    match (role, method_name) {
+        (ROLE_NNS_GOVERNANCE, "list_neurons") if opts.neuron_report => {
+            format::nns_governance::display_neuron_report_json(blob)
+        }
+        _ => get_json_string(blob, canister_id, role, method_name, part),
+    }
+}
+
+/// Returns the CSV encoding of a report.
+pub fn display_csv_response(
+    blob: &[u8],
+    role: &str,
+    method_name: &str,
+    opts: &DisplayOpts,
+) -> AnyhowResult<String> {
+    match (role, method_name) {
+        (ROLE_NNS_GOVERNANCE, "list_neurons") if opts.neuron_report => {
+            format::nns_governance::display_neuron_report_csv(blob)
+        }
+        _ => bail!("There is no CSV display for the response to {method_name}"),
+    }
+}
+
+/// What responses are displayed with, besides the method they are the response to.
+#[derive(Debug, Default, Clone)]
+pub struct DisplayOpts {
+    /// The token of the ICRC-1 ledger the call went to. Without it, amounts are displayed with 8
+    /// decimals.
+    pub token: Option<format::icrc1::Token>,
+    /// Whether a `list_neurons` response is displayed as a report with totals.
+    pub neuron_report: bool,
 }
 
 /// Returns pretty-printed encoding of a candid value.
@@ -250,6 +450,7 @@ pub fn display_response(
     role: &str,
     method_name: &str,
     part: &str,
+    opts: &DisplayOpts,
 ) -> AnyhowResult<String> {
     match role {
         ROLE_NNS_GOVERNANCE => match method_name {
@@ -258,6 +459,9 @@ pub fn display_response(
             "get_neuron_ids" => format::nns_governance::display_neuron_ids(blob),
             "update_node_provider" => format::nns_governance::display_update_node_provider(blob),
             "list_proposals" => format::nns_governance::display_list_proposals(blob),
+            "list_neurons" if opts.neuron_report => {
+                format::nns_governance::display_neuron_report(blob)
+            }
             "list_neurons" => format::nns_governance::display_list_neurons(blob),
             "get_proposal_info" => format::nns_governance::display_get_proposal(blob),
             "claim_gtc_neurons" => format::nns_governance::display_claim_gtc_neurons(blob),
@@ -278,9 +482,25 @@ pub fn display_response(
             }
             _ => get_idl_string(blob, canister_id, role, method_name, part),
         },
+        ROLE_NNS_INDEX => match method_name {
+            "get_account_identifier_transactions" | "get_account_transactions" => {
+                format::icp_index::display_account_transactions(blob)
+            }
+            _ => get_idl_string(blob, canister_id, role, method_name, part),
+        },
         ROLE_ICRC1_LEDGER => match method_name {
-            "icrc1_transfer" => format::icrc1::display_transfer(blob),
-            "icrc1_balance_of" => format::icrc1::display_balance(blob),
+            "icrc1_transfer" => format::icrc1::display_transfer(blob, opts.token.as_ref()),
+            "icrc1_balance_of" => format::icrc1::display_balance(blob, opts.token.as_ref()),
+            "icrc2_approve" => format::icrc1::display_approve(blob),
+            "icrc2_allowance" => format::icrc1::display_allowance(blob),
+            "icrc2_transfer_from" => format::icrc1::display_transfer_from(blob),
+            "icrc1_metadata" => format::icrc1::display_metadata(blob),
+            _ => get_idl_string(blob, canister_id, role, method_name, part),
+        },
+        ROLE_ICRC1_INDEX => match method_name {
+            "get_account_transactions" => {
+                format::icrc1_index::display_account_transactions(blob, opts.token.as_ref())
+            }
             _ => get_idl_string(blob, canister_id, role, method_name, part),
         },
         ROLE_SNS_SWAP => match method_name {
@@ -297,6 +517,12 @@ pub fn display_response(
             "retrieve_btc_status_v2" => format::ckbtc::display_retrieve_btc_status_v2(blob),
             _ => get_idl_string(blob, canister_id, role, method_name, part),
         },
+        ROLE_CKETH_MINTER => match method_name {
+            "withdraw_eth" => format::cketh::display_withdraw_eth(blob),
+            "withdraw_erc20" => format::cketh::display_withdraw_erc20(blob),
+            "retrieve_eth_status" => format::cketh::display_retrieve_eth_status(blob),
+            _ => get_idl_string(blob, canister_id, role, method_name, part),
+        },
         ROLE_NNS_GTC => match method_name {
             "claim_neurons" => format::gtc::format_claim_neurons(blob),
             _ => get_idl_string(blob, canister_id, role, method_name, part),
@@ -360,13 +586,25 @@ pub fn read_from_file(path: impl AsRef<Path>) -> AnyhowResult<String> {
     Ok(content)
 }
 
-/// Returns an agent with an identity derived from a private key if it was
+/// Returns an agent talking to `network`, with an identity derived from a private key if it was
 /// provided.
-pub fn get_agent(auth: &AuthInfo) -> AnyhowResult<Agent> {
-    let timeout = Duration::from_secs(60 * 5);
+pub fn get_agent(auth: &AuthInfo, network: &Network) -> AnyhowResult<Agent> {
+    let agent = build_agent(auth, network.url(), DEFAULT_INGRESS_EXPIRY)?;
+    if let Some(root_key) = network.root_key() {
+        agent.set_root_key(root_key);
+    }
+    Ok(agent)
+}
+
+/// Returns an agent for signing messages offline, which expire after `ingress_expiry`.
+pub fn get_agent_with_expiry(auth: &AuthInfo, ingress_expiry: Duration) -> AnyhowResult<Agent> {
+    build_agent(auth, get_ic_url(), ingress_expiry)
+}
+
+fn build_agent(auth: &AuthInfo, url: String, ingress_expiry: Duration) -> AnyhowResult<Agent> {
     let builder = Agent::builder()
-        .with_url(get_ic_url())
-        .with_ingress_expiry(Some(timeout));
+        .with_url(url)
+        .with_ingress_expiry(Some(ingress_expiry));
 
     let identity = get_identity(auth)?;
     builder
@@ -398,31 +636,45 @@ pub fn get_identity(auth: &AuthInfo) -> AnyhowResult<Box<dyn Identity>> {
         AuthInfo::NoAuth => Ok(Box::new(AnonymousIdentity) as _),
         AuthInfo::K256Key(pk) => Ok(Box::new(Secp256k1Identity::from_private_key(pk.clone()))),
         AuthInfo::Ed25519Key(kp) => Ok(Box::new(BasicIdentity::from_key_pair(
-            Ed25519KeyPair::from_pkcs8(kp).expect("Ed25519 key previously validated"),
+            Ed25519KeyPair::from_pkcs8_maybe_unchecked(kp)
+                .expect("Ed25519 key previously validated"),
         ))),
         #[cfg(feature = "hsm")]
         AuthInfo::Pkcs11Hsm(info) => {
-            let pin_fn = || {
-                let user_set_pin = { info.pin.borrow().clone() };
-                match user_set_pin {
-                    None => match read_pkcs11_pin_env_var() {
-                        Ok(Some(pin)) => Ok(pin),
-                        Ok(None) => {
-                            let pin = ask_pkcs11_pin_via_tty()?;
-                            *info.pin.borrow_mut() = Some(pin.clone());
-                            Ok(pin)
-                        }
-                        Err(e) => Err(e),
-                    },
-                    Some(pin) => Ok(pin),
-                }
-            };
-            let identity = HardwareIdentity::new(&info.libpath, info.slot, &info.ident, pin_fn)
-                .context("Unable to use your hardware key")?;
+            let identity =
+                HardwareIdentity::new(&info.libpath, info.slot, &info.ident, || info.pin())
+                    .context("Unable to use your hardware key")?;
             Ok(Box::new(identity) as _)
         }
         #[cfg(feature = "ledger")]
-        AuthInfo::Ledger => Ok(Box::new(LedgerIdentity::new()?)),
+        AuthInfo::Ledger {
+            derivation_path,
+            speculos,
+        } => Ok(Box::new(LedgerIdentity::new(
+            derivation_path.clone(),
+            speculos.as_deref(),
+        )?)),
+        AuthInfo::Delegated { session_key, chain } => Ok(Box::new(DelegatedIdentity::new(
+            chain.public_key.clone(),
+            get_identity(session_key)?,
+            chain.delegations.clone(),
+        ))),
+        AuthInfo::SenderOnly(principal) => Ok(Box::new(SenderOnlyIdentity(*principal))),
+    }
+}
+
+/// An identity that knows its principal but not its key, for constructing unsigned messages.
+struct SenderOnlyIdentity(Principal);
+
+impl Identity for SenderOnlyIdentity {
+    fn sender(&self) -> Result<Principal, String> {
+        Ok(self.0)
+    }
+    fn public_key(&self) -> Option<Vec<u8>> {
+        None
+    }
+    fn sign(&self, _: &EnvelopeContent) -> Result<Signature, String> {
+        Err("Messages constructed with --sender must be signed with `quill sign`".to_string())
     }
 }
 
@@ -441,10 +693,15 @@ pub fn get_account_id(
     Ok(AccountIdentifier::new(base_types_principal, subaccount))
 }
 
-/// Converts menmonic to PEM format
-pub fn mnemonic_to_key(mnemonic: &Mnemonic) -> AnyhowResult<SecretKey> {
-    let seed = Seed::new(mnemonic, "");
-    let ext = bip32::XPrv::derive_from_path(seed, &derivation_path())
+/// Converts menmonic to PEM format, deriving the key at the given path. `passphrase` is the optional
+/// BIP-39 passphrase, empty if unused.
+pub fn mnemonic_to_key(
+    mnemonic: &Mnemonic,
+    passphrase: &str,
+    path: &DerivationPath,
+) -> AnyhowResult<SecretKey> {
+    let seed = Seed::new(mnemonic, passphrase);
+    let ext = bip32::XPrv::derive_from_path(seed, path)
         .map_err(|err| anyhow!("{err:?}"))
         .context("Failed to derive BIP32 extended private key")?;
     let secret = ext.private_key();
@@ -452,11 +709,82 @@ pub fn mnemonic_to_key(mnemonic: &Mnemonic) -> AnyhowResult<SecretKey> {
     Ok(secret_key)
 }
 
+/// Derives an Ed25519 key from a mnemonic using SLIP-10, returning it in PKCS#8 format. SLIP-10 only
+/// supports hardened derivation for Ed25519, so every component of `path` is hardened.
+pub fn mnemonic_to_ed25519_key(
+    mnemonic: &Mnemonic,
+    passphrase: &str,
+    path: &DerivationPath,
+) -> AnyhowResult<Vec<u8>> {
+    let seed = Seed::new(mnemonic, passphrase);
+    let secret = slip10_ed25519(seed.as_bytes(), path);
+    let key_pair = Ed25519KeyPair::from_seed_unchecked(&secret)
+        .map_err(|err| anyhow!("{err}"))
+        .context("Failed to derive Ed25519 key")?;
+    // CurvePrivateKey ::= OCTET STRING
+    let mut private_key = vec![0x04, 0x20];
+    private_key.extend_from_slice(&secret);
+    let pki = PrivateKeyInfo {
+        algorithm: AlgorithmIdentifierRef {
+            oid: ED25519_OID,
+            parameters: None,
+        },
+        private_key: &private_key,
+        public_key: Some(key_pair.public_key().as_ref()),
+    };
+    Ok(pki.to_der()?)
+}
+
+fn slip10_ed25519(seed: &[u8], path: &DerivationPath) -> [u8; 32] {
+    let mut node = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA512, b"ed25519 seed"), seed);
+    for child in path.iter() {
+        let (key, chain_code) = node.as_ref().split_at(32);
+        let mut data = vec![0];
+        data.extend_from_slice(key);
+        data.extend_from_slice(&(child.index() | ChildNumber::HARDENED_FLAG).to_be_bytes());
+        node = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA512, chain_code), &data);
+    }
+    node.as_ref()[..32].try_into().unwrap()
+}
+
+/// The object identifier of Ed25519 keys in PKCS#8.
+pub const ED25519_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+
 const DERIVATION_PATH: &str = "m/44'/223'/0'/0/0";
-fn derivation_path() -> DerivationPath {
+
+/// Returns the path that keys are derived at from seed phrases and Ledger devices unless another
+/// is selected, `m/44'/223'/0'/0/0`.
+pub fn default_derivation_path() -> DerivationPath {
     DERIVATION_PATH.parse().unwrap()
 }
 
+/// Returns the default derivation path with its last component replaced by `index`.
+pub fn derivation_path_for_index(index: u32) -> AnyhowResult<DerivationPath> {
+    with_last_index(&default_derivation_path(), index)
+}
+
+/// Returns `path` with the index of its last component replaced by `index`.
+pub fn with_last_index(path: &DerivationPath, index: u32) -> AnyhowResult<DerivationPath> {
+    let last = path
+        .iter()
+        .last()
+        .context("Derivation path must not be empty")?;
+    let child = ChildNumber::new(index, last.is_hardened())
+        .map_err(|_| anyhow!("Derivation index {index} is too large"))?;
+    let mut path = path.parent().expect("non-empty path has a parent");
+    path.push(child);
+    Ok(path)
+}
+
+/// Parses a BIP-32 derivation path, e.g. `m/44'/223'/0'/0/0`.
+pub fn parse_derivation_path(s: &str) -> AnyhowResult<DerivationPath> {
+    let path: DerivationPath = s
+        .parse()
+        .map_err(|_| anyhow!("Invalid derivation path, expected e.g. m/44'/223'/0'/0/0"))?;
+    ensure!(!path.is_empty(), "Derivation path must not be empty");
+    Ok(path)
+}
+
 #[derive(Copy, Clone)]
 pub struct ParsedSubaccount(pub Subaccount);
 
@@ -614,22 +942,166 @@ pub fn now_nanos() -> u64 {
     }
 }
 
+/// Parses a duration such as `90s`, `5m`, `2h` or `1d`. A bare number is interpreted as seconds.
+pub fn parse_duration(s: &str) -> AnyhowResult<Duration> {
+    let (number, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(idx) => s.split_at(idx),
+        None => (s, "s"),
+    };
+    let number: u64 = number
+        .parse()
+        .with_context(|| format!("Invalid duration '{s}'"))?;
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => bail!("Invalid duration '{s}': unit must be one of s, m, h, d"),
+    };
+    ensure!(
+        number > 0,
+        "Invalid duration '{s}': must be greater than zero"
+    );
+    let seconds = number
+        .checked_mul(multiplier)
+        .with_context(|| format!("Invalid duration '{s}': too long"))?;
+    Ok(Duration::from_secs(seconds))
+}
+
+/// Parses the time after which signed messages expire, which is at most [`MAX_INGRESS_EXPIRY`].
+pub fn parse_ingress_expiry(s: &str) -> AnyhowResult<Duration> {
+    let expiry = parse_duration(s)?;
+    ensure!(
+        expiry <= MAX_INGRESS_EXPIRY,
+        "Ingress expiry '{s}' is longer than the maximum of {} days",
+        MAX_INGRESS_EXPIRY.as_secs() / (24 * 60 * 60)
+    );
+    Ok(expiry)
+}
+
 pub fn e8s_to_tokens(e8s: Nat) -> BigDecimal {
-    BigDecimal::new(e8s.0.into(), 8).normalized()
+    units_to_tokens(e8s, 8)
+}
+
+/// Converts an amount in a ledger's smallest unit to tokens, for a ledger with `decimals` decimal places.
+pub fn units_to_tokens(units: Nat, decimals: u8) -> BigDecimal {
+    BigDecimal::new(units.0.into(), decimals.into()).normalized()
+}
+
+/// Converts an amount of tokens to a ledger's smallest unit, for a ledger with `decimals` decimal places.
+pub fn tokens_to_units(tokens: &BigDecimal, decimals: u8) -> AnyhowResult<Nat> {
+    // trailing zeros, as in 1.50, don't count towards the decimal places
+    let tokens = tokens.normalized();
+    ensure!(
+        tokens.fractional_digit_count() <= decimals.into(),
+        "This token can only be specified to {decimals} decimal places"
+    );
+    let (mantissa, _) = tokens
+        .with_scale(decimals.into())
+        .into_bigint_and_exponent();
+    let (sign, mantissa) = mantissa.into_parts();
+    ensure!(sign != Sign::Minus, "Token amounts cannot be negative");
+    Ok(mantissa.into())
 }
 
-pub fn key_encryption_params<'a>(salt: &'a [u8; 16], iv: &'a [u8; 16]) -> Parameters<'a> {
-    let scrypt_params = Params::new(17, 8, 1, 32).expect("valid scrypt Params consts");
+/// The default scrypt cost parameter for encrypting keys, as a power of two.
+pub const DEFAULT_SCRYPT_LOG_N: u8 = 17;
+
+pub fn key_encryption_params<'a>(
+    salt: &'a [u8; 16],
+    iv: &'a [u8; 16],
+    log_n: u8,
+) -> Parameters<'a> {
+    let scrypt_params = Params::new(log_n, 8, 1, 32).expect("valid scrypt Params");
     Parameters::scrypt_aes256cbc(scrypt_params, salt, iv)
         .expect("valid PKCS5 encryption parameters")
 }
 
+/// Encrypts a PKCS#8 private key with `password`, using scrypt with a cost of 2^`log_n` and
+/// AES-256-CBC, and returns it in PEM format.
+pub fn encrypt_pkcs8_pem(
+    pkcs8: &[u8],
+    password: &str,
+    log_n: u8,
+) -> AnyhowResult<Zeroizing<String>> {
+    let pki = PrivateKeyInfo::try_from(pkcs8)?;
+    let mut rng = thread_rng();
+    let mut salt = [0u8; 16];
+    rng.fill_bytes(&mut salt);
+    let mut iv = [0u8; 16];
+    rng.fill_bytes(&mut iv);
+    let doc = pki.encrypt_with_params(key_encryption_params(&salt, &iv, log_n), password)?;
+    Ok(doc.to_pem(EncryptedPrivateKeyInfo::PEM_LABEL, LineEnding::default())?)
+}
+
+/// The PEM label of seed files encrypted by `quill generate`.
+pub const ENCRYPTED_SEED_LABEL: &str = "ENCRYPTED SEED PHRASE";
+
+/// Encrypts a seed phrase with `password` the same way `encrypt_pkcs8_pem` encrypts keys, and
+/// returns it in PEM format.
+pub fn encrypt_seed_phrase(phrase: &str, password: &str, log_n: u8) -> AnyhowResult<String> {
+    let mut rng = thread_rng();
+    let mut salt = [0u8; 16];
+    rng.fill_bytes(&mut salt);
+    let mut iv = [0u8; 16];
+    rng.fill_bytes(&mut iv);
+    let params = key_encryption_params(&salt, &iv, log_n);
+    let encrypted_data = params
+        .encrypt(password, phrase.as_bytes())
+        .map_err(pkcs8::Error::from)?;
+    // the structure is not specific to private keys, and records the parameters alongside the data
+    let info = EncryptedPrivateKeyInfo {
+        encryption_algorithm: params.into(),
+        encrypted_data: &encrypted_data,
+    };
+    Ok(
+        pkcs8::der::pem::encode_string(
+            ENCRYPTED_SEED_LABEL,
+            LineEnding::default(),
+            &info.to_der()?,
+        )
+        .map_err(pkcs8::der::Error::from)?,
+    )
+}
+
+/// Returns the seed phrase in the contents of a seed file, decrypting it if it was written by
+/// `encrypt_seed_phrase`. `password` is only called for encrypted files.
+pub fn decrypt_seed_file(
+    contents: String,
+    password: impl FnOnce() -> AnyhowResult<String>,
+) -> AnyhowResult<Zeroizing<String>> {
+    let contents = Zeroizing::new(contents);
+    let pem = match pem::parse(contents.as_bytes()) {
+        Ok(pem) if pem.tag() == ENCRYPTED_SEED_LABEL => pem,
+        Ok(pem) => bail!(
+            "Expected a seed file, found a PEM file of type {}",
+            pem.tag()
+        ),
+        Err(_) => return Ok(contents),
+    };
+    let info = EncryptedPrivateKeyInfo::try_from(pem.contents())
+        .context("Malformed encrypted seed file")?;
+    // a wrong password occasionally yields valid padding, but not valid text as well
+    info.encryption_algorithm
+        .decrypt(password()?, info.encrypted_data)
+        .ok()
+        .and_then(|phrase| String::from_utf8(phrase).ok())
+        .map(Zeroizing::new)
+        .context("Failed to decrypt seed file. Is the password correct?")
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{ParsedAccount, ParsedSubaccount};
-    use candid::Principal;
+    use super::{
+        decrypt_seed_file, derivation_path_for_index, encrypt_seed_phrase, parse_derivation_path,
+        parse_duration, slip10_ed25519, tokens_to_units, units_to_tokens, with_last_index,
+        ParsedAccount, ParsedSubaccount,
+    };
+    use bigdecimal::BigDecimal;
+    use candid::{Nat, Principal};
     use pretty_assertions::assert_eq;
     use std::str::FromStr;
+    use std::time::Duration;
 
     #[test]
     fn account() {
@@ -743,4 +1215,91 @@ mod tests {
             *b"\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\x01\x02"
         );
     }
+
+    #[test]
+    fn duration() {
+        assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("5m").unwrap(), Duration::from_secs(300));
+        assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(7200));
+        assert_eq!(parse_duration("1d").unwrap(), Duration::from_secs(86400));
+        assert!(parse_duration("5 minutes").is_err());
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("0").is_err());
+        assert!(parse_duration("0m").is_err());
+        assert!(parse_duration("18446744073709551615d").is_err());
+        assert!(parse_duration("99999999999999999999").is_err());
+        assert_eq!(parse_ingress_expiry("30d").unwrap(), MAX_INGRESS_EXPIRY);
+        assert!(parse_ingress_expiry("31d").is_err());
+        assert!(parse_ingress_expiry("0s").is_err());
+    }
+
+    #[test]
+    fn token_units() {
+        let tokens = BigDecimal::from_str("1.5").unwrap();
+        assert_eq!(tokens_to_units(&tokens, 6).unwrap(), 1_500_000_u64);
+        assert_eq!(tokens_to_units(&tokens, 1).unwrap(), 15_u64);
+        assert!(tokens_to_units(&tokens, 0).is_err());
+        let trailing_zeros = BigDecimal::from_str("1.50").unwrap();
+        assert_eq!(tokens_to_units(&trailing_zeros, 1).unwrap(), 15_u64);
+        let whole = BigDecimal::from_str("100").unwrap();
+        assert_eq!(tokens_to_units(&whole, 0).unwrap(), 100_u64);
+        assert!(tokens_to_units(&BigDecimal::from_str("-1").unwrap(), 8).is_err());
+        assert_eq!(
+            units_to_tokens(Nat::from(1_230_000_000_000_000_000_u64), 18).to_string(),
+            "1.23"
+        );
+    }
+
+    #[test]
+    fn derivation_paths() {
+        assert_eq!(
+            derivation_path_for_index(3).unwrap().to_string(),
+            "m/44'/223'/0'/0/3"
+        );
+        let path = parse_derivation_path("m/44'/223'/1'/0/0").unwrap();
+        assert_eq!(
+            with_last_index(&path, 7).unwrap().to_string(),
+            "m/44'/223'/1'/0/7"
+        );
+        let hardened = parse_derivation_path("m/44'/223'/0'").unwrap();
+        assert_eq!(
+            with_last_index(&hardened, 2).unwrap().to_string(),
+            "m/44'/223'/2'"
+        );
+        assert!(derivation_path_for_index(1 << 31).is_err());
+        assert!(parse_derivation_path("m").is_err());
+        assert!(parse_derivation_path("44'/223'").is_err());
+    }
+
+    #[test]
+    fn slip10() {
+        // test vector 1 from SLIP-0010
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        assert_eq!(
+            hex::encode(slip10_ed25519(&seed, &"m".parse().unwrap())),
+            "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"
+        );
+        assert_eq!(
+            hex::encode(slip10_ed25519(
+                &seed,
+                &parse_derivation_path("m/0").unwrap()
+            )),
+            "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3"
+        );
+    }
+
+    #[test]
+    fn seed_encryption() {
+        let phrase = "tornado allow zero warm have deer wool finish tiger ski dynamic strong";
+        let encrypted = encrypt_seed_phrase(phrase, "correct horse", 10).unwrap();
+        assert!(!encrypted.contains("tornado"));
+        let decrypted =
+            decrypt_seed_file(encrypted.clone(), || Ok("correct horse".into())).unwrap();
+        assert_eq!(decrypted.as_str(), phrase);
+        assert!(decrypt_seed_file(encrypted, || Ok("horse correct".into())).is_err());
+        // plaintext seed files are returned without asking for a password
+        let plaintext = decrypt_seed_file(phrase.into(), || panic!("password requested")).unwrap();
+        assert_eq!(plaintext.as_str(), phrase);
+    }
 }
@@ -0,0 +1,221 @@
+//! Named network profiles, read from `networks.json` in quill's config directory.
+
+use std::{collections::BTreeMap, env, path::PathBuf};
+
+use anyhow::{anyhow, Context};
+use candid::Principal;
+use serde::Deserialize;
+
+use super::{
+    ckbtc_canister_id, ckbtc_index_canister_id, ckbtc_minter_canister_id, cketh_canister_id,
+    cketh_minter_canister_id, genesis_token_canister_id, get_ic_url, governance_canister_id,
+    icp_index_canister_id, ledger_canister_id, registry_canister_id, sns_wasm_canister_id,
+    AnyhowResult,
+};
+
+/// The name of the network used when no profile is selected.
+pub const MAINNET: &str = "ic";
+
+/// A network that quill can send messages to.
+///
+/// For example,
+/// {
+///   "local": {
+///     "url": "http://127.0.0.1:4943",
+///     "fetch_root_key": true,
+///     "canister_ids": {
+///       "ledger": "ryjl3-tyaaa-aaaaa-aaaba-cai",
+///       "governance": "rrkah-fqaaa-aaaaa-aaaaq-cai"
+///     }
+///   }
+/// }
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkProfile {
+    /// The URL of a boundary node or replica.
+    pub url: String,
+    /// The hex-encoded DER root key of the network, if it is not the IC mainnet.
+    #[serde(default)]
+    pub root_key: Option<String>,
+    /// Fetch the root key from the network instead. DO NOT USE WITH ANY REAL INFORMATION.
+    #[serde(default)]
+    pub fetch_root_key: bool,
+    /// Canister IDs to use instead of the mainnet ones.
+    #[serde(default)]
+    pub canister_ids: CanisterIdOverrides,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CanisterIdOverrides {
+    pub ledger: Option<Principal>,
+    pub governance: Option<Principal>,
+    pub gtc: Option<Principal>,
+    pub registry: Option<Principal>,
+    pub sns_wasm: Option<Principal>,
+    pub icp_index: Option<Principal>,
+    /// Used whether or not `--testnet` is passed.
+    pub ckbtc_ledger: Option<Principal>,
+    /// Used whether or not `--testnet` is passed.
+    pub ckbtc_minter: Option<Principal>,
+    /// Used whether or not `--testnet` is passed.
+    pub ckbtc_index: Option<Principal>,
+    /// Used whether or not `--testnet` is passed.
+    pub cketh_ledger: Option<Principal>,
+    /// Used whether or not `--testnet` is passed.
+    pub cketh_minter: Option<Principal>,
+}
+
+/// The network selected with `--network`, along with `--fetch-root-key`.
+#[derive(Debug)]
+pub struct Network {
+    profile: Option<NetworkProfile>,
+    /// Fetch the root key from the network. DO NOT USE WITH ANY REAL INFORMATION.
+    pub fetch_root_key: bool,
+}
+
+/// Returns the path of the network configuration file, which can be overridden with `QUILL_NETWORKS_FILE`.
+pub fn networks_file() -> AnyhowResult<PathBuf> {
+    if let Ok(path) = env::var("QUILL_NETWORKS_FILE") {
+        return Ok(path.into());
+    }
+    let config_dir = dirs::config_dir().context("Could not find the user's config directory")?;
+    Ok(config_dir.join("quill").join("networks.json"))
+}
+
+impl Network {
+    /// Loads the network profile with the given name, or the IC mainnet if `name` is `None`.
+    pub fn load(name: Option<&str>, fetch_root_key: bool) -> AnyhowResult<Self> {
+        let Some(name) = name else {
+            return Ok(Self {
+                profile: None,
+                fetch_root_key,
+            });
+        };
+        let path = networks_file()?;
+        let profile = match std::fs::read(&path) {
+            Ok(contents) => {
+                let mut networks: BTreeMap<String, NetworkProfile> =
+                    serde_json::from_slice(&contents)
+                        .with_context(|| format!("Invalid network config {}", path.display()))?;
+                networks.remove(name)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Couldn't read network config {}", path.display()))
+            }
+        };
+        let profile = match profile {
+            Some(profile) => profile,
+            None if name == MAINNET => {
+                return Ok(Self {
+                    profile: None,
+                    fetch_root_key,
+                })
+            }
+            None => {
+                return Err(anyhow!(
+                    "Unknown network '{name}'. Networks are defined in {}",
+                    path.display()
+                ))
+            }
+        };
+        if let Some(root_key) = &profile.root_key {
+            hex::decode(root_key)
+                .with_context(|| format!("Invalid root key for network '{name}'"))?;
+        }
+        Ok(Self {
+            fetch_root_key: fetch_root_key || profile.fetch_root_key,
+            profile: Some(profile),
+        })
+    }
+
+    /// Returns the URL of the network. Without a profile, the `IC_URL` environment variable is
+    /// used if set.
+    pub fn url(&self) -> String {
+        match &self.profile {
+            Some(profile) => profile.url.clone(),
+            None => get_ic_url(),
+        }
+    }
+
+    /// Returns the root key of the network, if it is not the IC mainnet.
+    pub fn root_key(&self) -> Option<Vec<u8>> {
+        self.profile
+            .as_ref()?
+            .root_key
+            .as_ref()
+            .map(|key| hex::decode(key).expect("root key validated in Network::load"))
+    }
+
+    fn canister_ids(&self) -> Option<&CanisterIdOverrides> {
+        self.profile.as_ref().map(|profile| &profile.canister_ids)
+    }
+
+    pub fn ledger_canister_id(&self) -> Principal {
+        self.canister_ids()
+            .and_then(|ids| ids.ledger)
+            .unwrap_or_else(ledger_canister_id)
+    }
+
+    pub fn governance_canister_id(&self) -> Principal {
+        self.canister_ids()
+            .and_then(|ids| ids.governance)
+            .unwrap_or_else(governance_canister_id)
+    }
+
+    pub fn genesis_token_canister_id(&self) -> Principal {
+        self.canister_ids()
+            .and_then(|ids| ids.gtc)
+            .unwrap_or_else(genesis_token_canister_id)
+    }
+
+    pub fn registry_canister_id(&self) -> Principal {
+        self.canister_ids()
+            .and_then(|ids| ids.registry)
+            .unwrap_or_else(registry_canister_id)
+    }
+
+    pub fn sns_wasm_canister_id(&self) -> Principal {
+        self.canister_ids()
+            .and_then(|ids| ids.sns_wasm)
+            .unwrap_or_else(sns_wasm_canister_id)
+    }
+
+    pub fn icp_index_canister_id(&self) -> Principal {
+        self.canister_ids()
+            .and_then(|ids| ids.icp_index)
+            .unwrap_or_else(icp_index_canister_id)
+    }
+
+    pub fn ckbtc_canister_id(&self, testnet: bool) -> Principal {
+        self.canister_ids()
+            .and_then(|ids| ids.ckbtc_ledger)
+            .unwrap_or_else(|| ckbtc_canister_id(testnet))
+    }
+
+    pub fn ckbtc_index_canister_id(&self, testnet: bool) -> Principal {
+        self.canister_ids()
+            .and_then(|ids| ids.ckbtc_index)
+            .unwrap_or_else(|| ckbtc_index_canister_id(testnet))
+    }
+
+    pub fn ckbtc_minter_canister_id(&self, testnet: bool) -> Principal {
+        self.canister_ids()
+            .and_then(|ids| ids.ckbtc_minter)
+            .unwrap_or_else(|| ckbtc_minter_canister_id(testnet))
+    }
+
+    pub fn cketh_canister_id(&self, testnet: bool) -> Principal {
+        self.canister_ids()
+            .and_then(|ids| ids.cketh_ledger)
+            .unwrap_or_else(|| cketh_canister_id(testnet))
+    }
+
+    pub fn cketh_minter_canister_id(&self, testnet: bool) -> Principal {
+        self.canister_ids()
+            .and_then(|ids| ids.cketh_minter)
+            .unwrap_or_else(|| cketh_minter_canister_id(testnet))
+    }
+}
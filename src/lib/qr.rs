@@ -0,0 +1,218 @@
+//! How QR codes are output, and splitting payloads too large for one QR code into a sequence of
+//! frames and joining them back.
+//!
+//! Each frame is `quill:<index>-<count>/<crc32>/<chunk>`, with a 1-based index and the CRC-32 of
+//! the whole payload in hex, so that frames can be scanned in any order, more than once, and
+//! frames of different payloads are not mixed up. A payload that fits in one QR code is not
+//! framed at all.
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use anyhow::{bail, ensure, Context};
+use clap::ValueEnum;
+
+use super::AnyhowResult;
+
+const FRAME_PREFIX: &str = "quill:";
+
+/// Where QR codes are output.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QrFormat {
+    /// Block characters on the terminal
+    #[default]
+    Terminal,
+    /// PNG image files
+    Png,
+    /// SVG image files
+    Svg,
+}
+
+/// The error correction level of QR codes, i.e. how much of a code can be damaged or obscured
+/// and still be read. Higher levels make larger codes.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QrEcc {
+    /// About 7% of the code can be lost
+    Low,
+    /// About 15% of the code can be lost
+    #[default]
+    Medium,
+    /// About 25% of the code can be lost
+    Quartile,
+    /// About 30% of the code can be lost
+    High,
+}
+
+/// How QR codes are output, from --qr and the other --qr-* flags.
+#[derive(Clone, Debug)]
+pub struct QrOutputOpts {
+    /// Whether the output of commands is printed as QR codes rather than JSON.
+    pub enabled: bool,
+    pub format: QrFormat,
+    /// The directory image files are written to.
+    pub out: PathBuf,
+    pub ecc: QrEcc,
+    /// The size of a module (one black or white square) in image files, in pixels.
+    pub scale: u32,
+}
+
+/// The number of payload characters in each frame. Frames are kept small enough for a phone to
+/// scan them off a screen as they go by.
+pub const FRAME_CHUNK_LEN: usize = 280;
+
+/// Splits `payload` into frames. If it fits in one QR code, it is returned as is.
+pub fn split(payload: &str, fits_in_one: bool) -> Vec<String> {
+    if fits_in_one {
+        return vec![payload.to_string()];
+    }
+    let crc = crc32fast::hash(payload.as_bytes());
+    // the payload is base64, so it can be split anywhere
+    let chunks: Vec<_> = payload.as_bytes().chunks(FRAME_CHUNK_LEN).collect();
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            format!(
+                "{FRAME_PREFIX}{}-{}/{crc:08x}/{}",
+                i + 1,
+                chunks.len(),
+                String::from_utf8_lossy(chunk)
+            )
+        })
+        .collect()
+}
+
+/// The scanned text of one payload: either a whole payload that fit in one QR code, or the frames
+/// of one that did not.
+enum Scanned<'a> {
+    Whole(&'a str),
+    Framed {
+        count: usize,
+        crc: u32,
+        chunks: BTreeMap<usize, &'a str>,
+    },
+}
+
+/// Joins the frames of any number of payloads, in any order and with repeats, telling them apart
+/// by their checksum. Each unframed line is a whole payload. The payloads are returned in the order
+/// they were first scanned.
+pub fn join<'a>(frames: impl IntoIterator<Item = &'a str>) -> AnyhowResult<Vec<String>> {
+    let mut scanned: Vec<Scanned> = vec![];
+    for frame in frames.into_iter().map(str::trim) {
+        if !frame.starts_with(FRAME_PREFIX) {
+            if !scanned
+                .iter()
+                .any(|s| matches!(s, Scanned::Whole(whole) if *whole == frame))
+            {
+                scanned.push(Scanned::Whole(frame));
+            }
+            continue;
+        }
+        let (index, count, crc, chunk) =
+            parse_frame(frame).with_context(|| format!("Invalid QR frame '{frame}'"))?;
+        let group = scanned
+            .iter_mut()
+            .find(|s| matches!(s, Scanned::Framed { crc: other, .. } if *other == crc));
+        match group {
+            Some(Scanned::Framed {
+                count: expected,
+                chunks,
+                ..
+            }) => {
+                ensure!(
+                    *expected == count,
+                    "QR frames with checksum {crc:08x} disagree on the number of frames"
+                );
+                chunks.insert(index, chunk);
+            }
+            _ => scanned.push(Scanned::Framed {
+                count,
+                crc,
+                chunks: BTreeMap::from([(index, chunk)]),
+            }),
+        }
+    }
+    ensure!(!scanned.is_empty(), "No QR frames to decode");
+    scanned
+        .into_iter()
+        .map(|scanned| match scanned {
+            Scanned::Whole(payload) => Ok(payload.to_string()),
+            Scanned::Framed { count, crc, chunks } => join_frames(count, crc, chunks),
+        })
+        .collect()
+}
+
+/// Joins the chunks of the frames of one payload.
+fn join_frames(count: usize, crc: u32, chunks: BTreeMap<usize, &str>) -> AnyhowResult<String> {
+    let missing: Vec<_> = (1..=count).filter(|i| !chunks.contains_key(i)).collect();
+    ensure!(
+        missing.is_empty(),
+        "Missing {} of {count} QR frames of message {crc:08x}: {}",
+        missing.len(),
+        missing
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let payload: String = chunks.into_values().collect();
+    ensure!(
+        crc32fast::hash(payload.as_bytes()) == crc,
+        "The QR frames do not add up to the original message (checksum mismatch)"
+    );
+    Ok(payload)
+}
+
+fn parse_frame(frame: &str) -> AnyhowResult<(usize, usize, u32, &str)> {
+    let rest = frame
+        .strip_prefix(FRAME_PREFIX)
+        .context("Not a quill QR frame")?;
+    let mut parts = rest.splitn(3, '/');
+    let (Some(position), Some(crc), Some(chunk)) = (parts.next(), parts.next(), parts.next())
+    else {
+        bail!("Expected <index>-<count>/<checksum>/<data>");
+    };
+    let (index, count) = position
+        .split_once('-')
+        .context("Expected <index>-<count>")?;
+    let index: usize = index.parse()?;
+    let count: usize = count.parse()?;
+    ensure!(
+        (1..=count).contains(&index),
+        "Frame {index} of {count} is out of range"
+    );
+    Ok((index, count, u32::from_str_radix(crc, 16)?, chunk))
+}
+
+#[test]
+fn frames_round_trip() {
+    let payload = "H4sIAAAAAAAA/".repeat(100);
+    assert_eq!(split(&payload, true), [payload.clone()]);
+    let frames = split(&payload, false);
+    assert_eq!(frames.len(), 5);
+    assert!(frames[0].starts_with("quill:1-5/"));
+    // in any order, with repeats
+    let scanned = [
+        &frames[3], &frames[0], &frames[4], &frames[1], &frames[3], &frames[2],
+    ];
+    assert_eq!(
+        join(scanned.iter().map(|f| f.as_str())).unwrap(),
+        [payload.clone()]
+    );
+    assert_eq!(join([payload.as_str()]).unwrap(), [payload.clone()]);
+    let crc = &frames[0]["quill:1-5/".len()..][..8];
+    assert_eq!(
+        join(frames[..3].iter().map(|f| f.as_str()))
+            .unwrap_err()
+            .to_string(),
+        format!("Missing 2 of 5 QR frames of message {crc}: 4, 5")
+    );
+    // frames of different payloads are told apart, and each payload is joined
+    let other = split(&"A".repeat(1000), false);
+    let interleaved = other.iter().zip(&frames).flat_map(|(a, b)| [a, b]);
+    let rest = frames[other.len()..].iter();
+    assert_eq!(
+        join(interleaved.chain(rest).map(|f| f.as_str())).unwrap(),
+        ["A".repeat(1000), payload.clone()]
+    );
+    assert!(join([frames[0].as_str(), other[1].as_str()]).is_err());
+}
@@ -1,17 +1,27 @@
 use crate::lib::get_idl_string;
 use crate::lib::{AnyhowResult, AuthInfo};
-use anyhow::{anyhow, Context};
+use anyhow::{anyhow, bail, ensure, Context};
 use candid::Principal;
-use ic_agent::agent::UpdateBuilder;
-use ic_agent::RequestId;
+use ic_agent::agent::{Envelope, EnvelopeContent, UpdateBuilder};
+use ic_agent::{Identity, RequestId};
+use k256::ecdsa::signature::Verifier;
+use pkcs8::DecodePublicKey;
+use ring::signature::{UnparsedPublicKey, ED25519};
 use serde::{Deserialize, Serialize};
 use serde_cbor::Value;
+use std::borrow::Cow;
 use std::convert::TryFrom;
+use std::str::FromStr;
 use std::time::Duration;
 
 #[cfg(feature = "ledger")]
 use super::ledger::LedgerIdentity;
-use super::{get_agent, get_default_role};
+use super::{get_agent_with_expiry, get_default_role, get_identity, now_nanos, IngressExpiryOpts};
+
+/// The interval between the expiry times of successive copies of a message signed with
+/// `--ingress-expiry-copies`. This is shorter than the five-minute window in which the IC accepts a
+/// message, so that the validity windows of consecutive copies overlap.
+pub const COPY_INTERVAL: Duration = Duration::from_secs(4 * 60);
 
 /// Represents a signed message with the corresponding request id.
 #[derive(Clone)]
@@ -39,6 +49,14 @@ pub struct Ingress {
 pub struct IngressWithRequestId {
     pub ingress: Ingress,
     pub request_status: RequestStatus,
+    /// Copies of the same call signed with later expiry times. `quill send` submits whichever
+    /// copy is currently accepted by the IC.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub later_copies: Vec<IngressWithRequestId>,
+    /// Whether the unsigned call is to be signed as staking, as with
+    /// [`sign_staking_ingress_with_request_status_query`].
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub staking: bool,
 }
 
 impl Ingress {
@@ -80,14 +98,222 @@ impl Ingress {
         }
         Err(anyhow!("Invalid cbor content"))
     }
+
+    /// Returns the expiry time of the message, in nanoseconds since the Unix epoch.
+    pub fn ingress_expiry(&self) -> AnyhowResult<u64> {
+        let envelope = decode_envelope(&self.content)?;
+        match &*envelope.content {
+            EnvelopeContent::Call { ingress_expiry, .. }
+            | EnvelopeContent::Query { ingress_expiry, .. }
+            | EnvelopeContent::ReadState { ingress_expiry, .. } => Ok(*ingress_expiry),
+        }
+    }
+
+    /// Returns the canister, method name and Candid-encoded argument of the call.
+    pub fn call(&self) -> AnyhowResult<(Principal, String, Vec<u8>)> {
+        let envelope = decode_envelope(&self.content)?;
+        match &*envelope.content {
+            EnvelopeContent::Call {
+                canister_id,
+                method_name,
+                arg,
+                ..
+            }
+            | EnvelopeContent::Query {
+                canister_id,
+                method_name,
+                arg,
+                ..
+            } => Ok((*canister_id, method_name.clone(), arg.clone())),
+            EnvelopeContent::ReadState { .. } => bail!("The message is not a call"),
+        }
+    }
+
+    /// Computes the request ID of the message from its content.
+    pub fn compute_request_id(&self) -> AnyhowResult<RequestId> {
+        Ok(decode_envelope(&self.content)?.content.to_request_id())
+    }
+
+    /// Checks the signature of the message against its embedded public key, and that the
+    /// request ID recomputed from the content matches the one recorded alongside it.
+    pub fn verify(&self) -> AnyhowResult<RequestId> {
+        let envelope = decode_envelope(&self.content)?;
+        let request_id = verify_envelope(&envelope)?;
+        ensure!(
+            matches!(
+                (self.call_type.as_str(), &*envelope.content),
+                ("update", EnvelopeContent::Call { .. }) | ("query", EnvelopeContent::Query { .. })
+            ),
+            "Message content does not match call type `{}`",
+            self.call_type,
+        );
+        if let Some(recorded) = &self.request_id {
+            let recorded = RequestId::from_str(recorded)
+                .context("Invalid request id recorded in the message")?;
+            ensure!(
+                recorded == request_id,
+                "Request id 0x{} does not match the message content (expected 0x{})",
+                String::from(recorded),
+                String::from(request_id),
+            );
+        }
+        Ok(request_id)
+    }
+}
+
+impl RequestStatus {
+    /// Checks the signature of the request status query, and that it asks for the status of
+    /// `request_id` on `canister_id`.
+    pub fn verify(&self, request_id: RequestId, canister_id: Principal) -> AnyhowResult {
+        let envelope = decode_envelope(&self.content)?;
+        verify_envelope(&envelope)?;
+        let recorded =
+            RequestId::from_str(&self.request_id).context("Invalid argument: request_id")?;
+        ensure!(
+            recorded == request_id,
+            "Request status query is for request 0x{} instead of 0x{}",
+            String::from(recorded),
+            String::from(request_id),
+        );
+        ensure!(
+            self.canister_id == canister_id.to_string(),
+            "Request status query is for canister {} instead of {canister_id}",
+            self.canister_id,
+        );
+        let EnvelopeContent::ReadState { paths, .. } = &*envelope.content else {
+            bail!("Request status query is not a read_state request");
+        };
+        ensure!(
+            paths.iter().any(|path| matches!(
+                &path[..],
+                [label, id] if label.as_bytes() == b"request_status" && id.as_bytes() == request_id.as_slice()
+            )),
+            "Request status query does not cover request 0x{}",
+            String::from(request_id),
+        );
+        Ok(())
+    }
+}
+
+impl IngressWithRequestId {
+    /// Verifies both the message and its request status query, as well as any later copies.
+    pub fn verify(&self) -> AnyhowResult<RequestId> {
+        let request_id = self.ingress.verify()?;
+        let (sender, canister_id, method_name, args, _) = self.ingress.parse()?;
+        self.request_status.verify(request_id, canister_id)?;
+        for copy in &self.later_copies {
+            copy.verify()?;
+            let (copy_sender, copy_canister_id, copy_method_name, copy_args, _) =
+                copy.ingress.parse()?;
+            ensure!(
+                (copy_sender, copy_canister_id, copy_method_name, copy_args)
+                    == (sender, canister_id, method_name.clone(), args.clone()),
+                "Later copy of request 0x{} is a different call",
+                String::from(request_id),
+            );
+        }
+        Ok(request_id)
+    }
+
+    /// Returns the first copy of the message that the IC accepts at `now_nanos`, i.e. that has not
+    /// expired and does not expire more than five minutes in the future.
+    pub fn valid_copy(&self, now_nanos: u64) -> AnyhowResult<&IngressWithRequestId> {
+        // leave some time for the message to be submitted and polled
+        const MARGIN_NANOS: u64 = 30_000_000_000;
+        let max_expiry = now_nanos + super::DEFAULT_INGRESS_EXPIRY.as_nanos() as u64;
+        let mut earliest_future = None;
+        for copy in std::iter::once(self).chain(&self.later_copies) {
+            let expiry = copy.ingress.ingress_expiry()?;
+            if expiry > max_expiry {
+                earliest_future = Some(earliest_future.map_or(expiry, |e: u64| e.min(expiry)));
+            } else if expiry > now_nanos + MARGIN_NANOS {
+                return Ok(copy);
+            }
+        }
+        if let Some(expiry) = earliest_future {
+            bail!(
+                "This message cannot be sent yet. It can be sent starting at {}",
+                super::format::format_timestamp_nanoseconds(
+                    expiry - super::DEFAULT_INGRESS_EXPIRY.as_nanos() as u64
+                )
+            )
+        } else {
+            bail!("This message has expired and must be signed again")
+        }
+    }
+}
+
+fn decode_envelope(content: &str) -> AnyhowResult<Envelope<'static>> {
+    serde_cbor::from_slice(&hex::decode(content)?)
+        .context("Invalid cbor data in the content of the message.")
+}
+
+/// Recomputes the request ID of the envelope and checks the signature over it.
+fn verify_envelope(envelope: &Envelope) -> AnyhowResult<RequestId> {
+    let request_id = envelope.content.to_request_id();
+    let sender = envelope_sender(&envelope.content);
+    ensure!(
+        envelope.sender_delegation.is_none(),
+        "Messages signed with delegations cannot be verified"
+    );
+    match (&envelope.sender_pubkey, &envelope.sender_sig) {
+        (None, None) => ensure!(
+            sender == Principal::anonymous(),
+            "Message from {sender} is not signed"
+        ),
+        (Some(pubkey), Some(sig)) => {
+            ensure!(
+                Principal::self_authenticating(pubkey) == sender,
+                "Public key does not belong to sender {sender}"
+            );
+            verify_signature(pubkey, &request_id.signable(), sig).with_context(|| {
+                format!("Bad signature for request 0x{}", String::from(request_id))
+            })?;
+        }
+        _ => bail!("Message has a public key or a signature, but not both"),
+    }
+    Ok(request_id)
+}
+
+fn envelope_sender(content: &EnvelopeContent) -> Principal {
+    match content {
+        EnvelopeContent::Call { sender, .. }
+        | EnvelopeContent::Query { sender, .. }
+        | EnvelopeContent::ReadState { sender, .. } => *sender,
+    }
+}
+
+// DER prefix of an Ed25519 SubjectPublicKeyInfo; the raw 32-byte key follows.
+const ED25519_DER_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+fn verify_signature(pubkey: &[u8], message: &[u8], sig: &[u8]) -> AnyhowResult {
+    if let Some(raw) = pubkey.strip_prefix(&ED25519_DER_PREFIX[..]) {
+        UnparsedPublicKey::new(&ED25519, raw)
+            .verify(message, sig)
+            .map_err(|_| anyhow!("Ed25519 signature did not match"))
+    } else if let Ok(key) = k256::ecdsa::VerifyingKey::from_public_key_der(pubkey) {
+        let sig =
+            k256::ecdsa::Signature::from_slice(sig).context("Malformed secp256k1 signature")?;
+        key.verify(message, &sig)
+            .map_err(|_| anyhow!("secp256k1 signature did not match"))
+    } else if let Ok(key) = p256::ecdsa::VerifyingKey::from_public_key_der(pubkey) {
+        let sig = p256::ecdsa::Signature::from_slice(sig).context("Malformed P-256 signature")?;
+        key.verify(message, &sig)
+            .map_err(|_| anyhow!("P-256 signature did not match"))
+    } else {
+        bail!("Unsupported public key type")
+    }
 }
 
 pub fn request_status_sign(
     auth: &AuthInfo,
     request_id: RequestId,
     canister_id: Principal,
+    ingress_expiry: Duration,
 ) -> AnyhowResult<RequestStatus> {
-    let agent = get_agent(auth)?;
+    let agent = get_agent_with_expiry(auth, ingress_expiry)?;
     let val = agent.sign_request_status(canister_id, request_id)?;
     Ok(RequestStatus {
         canister_id: canister_id.to_string(),
@@ -103,9 +329,9 @@ pub fn sign(
     args: Vec<u8>,
     role: &str,
     #[allow(unused)] is_staking: bool,
+    ingress_expiry: Duration,
 ) -> AnyhowResult<SignedMessageWithRequestId> {
-    let ingress_expiry = Duration::from_secs(5 * 60);
-    let agent = get_agent(auth)?;
+    let agent = get_agent_with_expiry(auth, ingress_expiry)?;
     let signed_update = sign_with(auth, is_staking, || {
         UpdateBuilder::new(&agent, canister_id, method_name.to_string())
             .with_arg(args)
@@ -132,7 +358,7 @@ fn sign_with<T>(
     f: impl FnOnce() -> T,
 ) -> T {
     #[cfg(feature = "ledger")]
-    if is_staking && matches!(auth, AuthInfo::Ledger) {
+    if is_staking && matches!(auth, AuthInfo::Ledger { .. }) {
         return LedgerIdentity::with_staking(f);
     }
     f()
@@ -141,6 +367,7 @@ fn sign_with<T>(
 /// Generates a bundle of signed messages (ingress + request status query).
 pub fn sign_ingress_with_request_status_query(
     auth: &AuthInfo,
+    ingress_expiry: IngressExpiryOpts,
     canister_id: Principal,
     role: &str,
     method_name: &str,
@@ -148,6 +375,7 @@ pub fn sign_ingress_with_request_status_query(
 ) -> AnyhowResult<IngressWithRequestId> {
     sign_ingress_with_request_status_query_internal(
         auth,
+        ingress_expiry,
         canister_id,
         role,
         method_name,
@@ -159,6 +387,7 @@ pub fn sign_ingress_with_request_status_query(
 /// Same as [`sign_ingress_with_request_status_query`], but signals that the request is staking.
 pub fn sign_staking_ingress_with_request_status_query(
     auth: &AuthInfo,
+    ingress_expiry: IngressExpiryOpts,
     canister_id: Principal,
     role: &str,
     method_name: &str,
@@ -166,6 +395,7 @@ pub fn sign_staking_ingress_with_request_status_query(
 ) -> AnyhowResult<IngressWithRequestId> {
     sign_ingress_with_request_status_query_internal(
         auth,
+        ingress_expiry,
         canister_id,
         role,
         method_name,
@@ -176,29 +406,197 @@ pub fn sign_staking_ingress_with_request_status_query(
 
 fn sign_ingress_with_request_status_query_internal(
     auth: &AuthInfo,
+    ingress_expiry: IngressExpiryOpts,
     canister_id: Principal,
     role: &str,
     method_name: &str,
     args: Vec<u8>,
     is_staking: bool,
 ) -> AnyhowResult<IngressWithRequestId> {
+    check_ledger_support(auth, canister_id, method_name)?;
+    let IngressExpiryOpts {
+        ingress_expiry,
+        copies,
+    } = ingress_expiry;
+    let unsigned_sender = if let AuthInfo::SenderOnly(sender) = auth {
+        Some(*sender)
+    } else {
+        None
+    };
+    let mut messages = (0..copies.max(1))
+        .map(|i| {
+            if let Some(sender) = unsigned_sender {
+                return Ok(unsigned_message(
+                    sender,
+                    canister_id,
+                    method_name,
+                    args.clone(),
+                    role,
+                    is_staking,
+                    ingress_expiry + COPY_INTERVAL * i,
+                ));
+            }
+            let msg_with_req_id = sign(
+                auth,
+                canister_id,
+                method_name,
+                args.clone(),
+                role,
+                is_staking,
+                ingress_expiry + COPY_INTERVAL * i,
+            )?;
+            let request_id = msg_with_req_id
+                .request_id
+                .context("No request id for transfer call found")?;
+            let request_status = request_status_sign(
+                auth,
+                request_id,
+                canister_id,
+                ingress_expiry + COPY_INTERVAL * i,
+            )?;
+            Ok(IngressWithRequestId {
+                ingress: msg_with_req_id.message,
+                request_status,
+                later_copies: vec![],
+                staking: false,
+            })
+        })
+        .collect::<AnyhowResult<Vec<_>>>()?;
+    let mut message = messages.remove(0);
+    message.later_copies = messages;
+    Ok(message)
+}
+
+/// Constructs a message bundle like [`sign_ingress_with_request_status_query`] does, but leaves the
+/// envelopes unsigned, to be signed later by [`sign_unsigned_message`].
+fn unsigned_message(
+    sender: Principal,
+    canister_id: Principal,
+    method_name: &str,
+    args: Vec<u8>,
+    role: &str,
+    is_staking: bool,
+    ingress_expiry: Duration,
+) -> IngressWithRequestId {
+    let ingress_expiry = now_nanos() + ingress_expiry.as_nanos() as u64;
+    let call = EnvelopeContent::Call {
+        nonce: Some(rand::random::<[u8; 16]>().to_vec()),
+        ingress_expiry,
+        sender,
+        canister_id,
+        method_name: method_name.to_string(),
+        arg: args,
+    };
+    let request_id = call.to_request_id();
+    let read_state = EnvelopeContent::ReadState {
+        ingress_expiry,
+        sender,
+        paths: vec![vec![
+            "request_status".into(),
+            request_id.as_slice().to_vec().into(),
+        ]],
+    };
+    IngressWithRequestId {
+        ingress: Ingress {
+            call_type: "update".to_string(),
+            request_id: Some(request_id.into()),
+            content: encode_unsigned_envelope(call),
+            role: Some(role.to_owned()),
+        },
+        request_status: RequestStatus {
+            canister_id: canister_id.to_string(),
+            request_id: request_id.into(),
+            content: encode_unsigned_envelope(read_state),
+        },
+        later_copies: vec![],
+        staking: is_staking,
+    }
+}
+
+fn encode_unsigned_envelope(content: EnvelopeContent) -> String {
+    let envelope = Envelope {
+        content: Cow::Owned(content),
+        sender_pubkey: None,
+        sender_sig: None,
+        sender_delegation: None,
+    };
+    hex::encode(envelope.encode_bytes())
+}
+
+/// Signs a message bundle produced with `--unsigned-output`, including any later copies.
+pub fn sign_unsigned_message(
+    auth: &AuthInfo,
+    message: &IngressWithRequestId,
+) -> AnyhowResult<IngressWithRequestId> {
+    let (canister_id, method_name, _) = message.ingress.call()?;
+    check_ledger_support(auth, canister_id, &method_name)?;
+    let identity = get_identity(auth)?;
+    let signed = sign_unsigned_message_with(auth, &*identity, message)?;
+    signed.verify()?;
+    Ok(signed)
+}
+
+fn sign_unsigned_message_with(
+    auth: &AuthInfo,
+    identity: &dyn Identity,
+    message: &IngressWithRequestId,
+) -> AnyhowResult<IngressWithRequestId> {
+    Ok(IngressWithRequestId {
+        ingress: Ingress {
+            content: sign_with(auth, message.staking, || {
+                sign_envelope(identity, &message.ingress.content)
+            })?,
+            ..message.ingress.clone()
+        },
+        request_status: RequestStatus {
+            content: sign_envelope(identity, &message.request_status.content)?,
+            ..message.request_status.clone()
+        },
+        later_copies: message
+            .later_copies
+            .iter()
+            .map(|copy| sign_unsigned_message_with(auth, identity, copy))
+            .collect::<AnyhowResult<_>>()?,
+        staking: false,
+    })
+}
+
+/// Fails early if the call is one the IC app for Ledger cannot sign.
+fn check_ledger_support(
+    #[allow(unused)] auth: &AuthInfo,
+    #[allow(unused)] canister_id: Principal,
+    #[allow(unused)] method_name: &str,
+) -> AnyhowResult {
     #[cfg(feature = "ledger")]
-    if matches!(auth, AuthInfo::Ledger)
+    if matches!(auth, AuthInfo::Ledger { .. })
         && !super::ledger::supported_transaction(&canister_id, method_name)
     {
-        anyhow::bail!(
+        bail!(
             "Cannot use --ledger with this command. This version of Quill only supports transfers \
             and certain neuron management operations with a Ledger device"
         );
     }
-    let msg_with_req_id = sign(auth, canister_id, method_name, args, role, is_staking)?;
-    let request_id = msg_with_req_id
-        .request_id
-        .context("No request id for transfer call found")?;
-    let request_status = request_status_sign(auth, request_id, canister_id)?;
-    let message = IngressWithRequestId {
-        ingress: msg_with_req_id.message,
-        request_status,
+    Ok(())
+}
+
+fn sign_envelope(identity: &dyn Identity, content: &str) -> AnyhowResult<String> {
+    let envelope = decode_envelope(content)?;
+    ensure!(
+        envelope.sender_sig.is_none() && envelope.sender_delegation.is_none(),
+        "Message is already signed"
+    );
+    let signer = identity.sender().map_err(|e| anyhow!(e))?;
+    let sender = envelope_sender(&envelope.content);
+    ensure!(
+        sender == signer,
+        "Message was constructed for sender {sender}, but the key belongs to {signer}"
+    );
+    let signature = identity.sign(&envelope.content).map_err(|e| anyhow!(e))?;
+    let signed = Envelope {
+        content: envelope.content,
+        sender_pubkey: signature.public_key,
+        sender_sig: signature.signature,
+        sender_delegation: signature.delegations,
     };
-    Ok(message)
+    Ok(hex::encode(signed.encode_bytes()))
 }
@@ -0,0 +1,481 @@
+//! Shamir's secret sharing of seeds with SLIP-39 mnemonics.
+//!
+//! Only single-group share sets are created, but shares from multi-group sets created by other tools
+//! can be combined.
+//!
+//! See <https://github.com/satoshilabs/slips/blob/master/slip-0039.md>.
+
+use std::{collections::BTreeMap, num::NonZeroU32};
+
+use anyhow::{anyhow, bail, ensure};
+use rand::{rngs::OsRng, RngCore};
+use ring::{hmac, pbkdf2};
+
+use super::AnyhowResult;
+
+const WORDLIST: &str = include_str!("slip39_wordlist.txt");
+
+const RADIX_BITS: usize = 10;
+const ID_EXP_WORDS: usize = 2;
+const SHARE_PARAMS_WORDS: usize = 2;
+const CHECKSUM_WORDS: usize = 3;
+const METADATA_WORDS: usize = ID_EXP_WORDS + SHARE_PARAMS_WORDS + CHECKSUM_WORDS;
+const MIN_SECRET_LEN: usize = 16;
+const MAX_SHARE_COUNT: u8 = 16;
+const DIGEST_LEN: usize = 4;
+const DIGEST_INDEX: u8 = 254;
+const SECRET_INDEX: u8 = 255;
+const ROUND_COUNT: u8 = 4;
+const BASE_ITERATIONS: u32 = 10000;
+/// The iteration exponent of new shares, for 20000 PBKDF2 iterations per round.
+const ITERATION_EXPONENT: u8 = 1;
+
+/// Splits `secret` into `count` mnemonic shares, any `threshold` of which can recover it.
+pub fn split(
+    secret: &[u8],
+    threshold: u8,
+    count: u8,
+    passphrase: &[u8],
+) -> AnyhowResult<Vec<String>> {
+    ensure!(
+        secret.len() >= MIN_SECRET_LEN && secret.len() % 2 == 0,
+        "The secret must be an even number of bytes, and at least {MIN_SECRET_LEN}"
+    );
+    ensure!(
+        (1..=count).contains(&threshold),
+        "The threshold must be between 1 and the number of shares"
+    );
+    ensure!(
+        count <= MAX_SHARE_COUNT,
+        "There can be at most {MAX_SHARE_COUNT} shares"
+    );
+    ensure!(
+        threshold > 1 || count == 1,
+        "A threshold of 1 with more than one share would just be copies of the same share"
+    );
+    let identifier = (OsRng.next_u32() & 0x7fff) as u16;
+    let params = Params {
+        identifier,
+        extendable: false,
+        iteration_exponent: ITERATION_EXPONENT,
+        group_threshold: 1,
+        group_count: 1,
+    };
+    let encrypted = feistel(secret, passphrase, &params, (0..ROUND_COUNT).collect());
+    Ok(split_secret(threshold, count, &encrypted)
+        .into_iter()
+        .map(|(member_index, value)| {
+            Share {
+                params,
+                group_index: 0,
+                member_index,
+                member_threshold: threshold,
+                value,
+            }
+            .to_mnemonic()
+        })
+        .collect())
+}
+
+/// Recovers the secret from enough mnemonic shares of the same set.
+pub fn combine(mnemonics: &[impl AsRef<str>], passphrase: &[u8]) -> AnyhowResult<Vec<u8>> {
+    let shares = mnemonics
+        .iter()
+        .enumerate()
+        .map(|(i, mnemonic)| {
+            Share::from_mnemonic(mnemonic.as_ref()).map_err(|e| anyhow!("Share #{}: {e}", i + 1))
+        })
+        .collect::<AnyhowResult<Vec<_>>>()?;
+    let Some(first) = shares.first() else {
+        bail!("No shares were provided");
+    };
+    let params = first.params;
+    let mut groups = BTreeMap::<u8, Vec<&Share>>::new();
+    for share in &shares {
+        ensure!(
+            share.params == params,
+            "The shares are not all from the same set"
+        );
+        ensure!(
+            share.value.len() == first.value.len(),
+            "The shares are not all the same length"
+        );
+        groups.entry(share.group_index).or_default().push(share);
+    }
+    ensure!(
+        groups.len() == params.group_threshold as usize,
+        "Shares from {} groups are needed, but {} were provided",
+        params.group_threshold,
+        groups.len()
+    );
+    let mut group_shares = vec![];
+    for (group_index, members) in groups {
+        let threshold = members[0].member_threshold;
+        ensure!(
+            members.iter().all(|m| m.member_threshold == threshold),
+            "The shares of group {} disagree on its threshold",
+            group_index + 1
+        );
+        let mut member_shares = members
+            .iter()
+            .map(|m| (m.member_index, m.value.clone()))
+            .collect::<Vec<_>>();
+        member_shares.sort();
+        member_shares.dedup();
+        ensure!(
+            member_shares.windows(2).all(|w| w[0].0 != w[1].0),
+            "Two different shares have the same index"
+        );
+        ensure!(
+            member_shares.len() >= threshold as usize,
+            "{threshold} shares are needed, but {} distinct shares were provided",
+            member_shares.len()
+        );
+        member_shares.truncate(threshold as usize);
+        group_shares.push((group_index, recover_secret(threshold, &member_shares)?));
+    }
+    let encrypted = recover_secret(params.group_threshold, &group_shares)?;
+    Ok(feistel(
+        &encrypted,
+        passphrase,
+        &params,
+        (0..ROUND_COUNT).rev().collect(),
+    ))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Params {
+    identifier: u16,
+    extendable: bool,
+    iteration_exponent: u8,
+    group_threshold: u8,
+    group_count: u8,
+}
+
+#[derive(Debug)]
+struct Share {
+    params: Params,
+    group_index: u8,
+    member_index: u8,
+    member_threshold: u8,
+    value: Vec<u8>,
+}
+
+impl Share {
+    fn to_mnemonic(&self) -> String {
+        let p = &self.params;
+        let id_exp = (u32::from(p.identifier) << 5)
+            | (u32::from(p.extendable) << 4)
+            | u32::from(p.iteration_exponent);
+        let share_params = (u32::from(self.group_index) << 16)
+            | (u32::from(p.group_threshold - 1) << 12)
+            | (u32::from(p.group_count - 1) << 8)
+            | (u32::from(self.member_index) << 4)
+            | u32::from(self.member_threshold - 1);
+        let mut data = vec![
+            (id_exp >> 10) as u16,
+            (id_exp & 0x3ff) as u16,
+            (share_params >> 10) as u16,
+            (share_params & 0x3ff) as u16,
+        ];
+        data.extend(bytes_to_words(&self.value));
+        let checksum = create_checksum(&data, p.extendable);
+        data.extend(checksum);
+        let words = WORDLIST.lines().collect::<Vec<_>>();
+        data.iter()
+            .map(|&i| words[i as usize])
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn from_mnemonic(mnemonic: &str) -> AnyhowResult<Self> {
+        let data = mnemonic
+            .split_whitespace()
+            .map(|word| {
+                let lowercase = word.to_lowercase();
+                WORDLIST
+                    .lines()
+                    .position(|w| w == lowercase)
+                    .map(|i| i as u16)
+                    .ok_or_else(|| anyhow!("'{word}' is not a SLIP-39 word"))
+            })
+            .collect::<AnyhowResult<Vec<_>>>()?;
+        ensure!(
+            data.len() >= METADATA_WORDS + MIN_SECRET_LEN * 8 / RADIX_BITS + 1,
+            "Too few words for a SLIP-39 share"
+        );
+        let padding = (RADIX_BITS * (data.len() - METADATA_WORDS)) % 16;
+        ensure!(padding <= 8, "Invalid number of words for a SLIP-39 share");
+        let id_exp = (u32::from(data[0]) << 10) | u32::from(data[1]);
+        let extendable = (id_exp >> 4) & 1 == 1;
+        ensure!(
+            verify_checksum(&data, extendable),
+            "Invalid checksum; check for typos"
+        );
+        let share_params = (u32::from(data[2]) << 10) | u32::from(data[3]);
+        let params = Params {
+            identifier: (id_exp >> 5) as u16,
+            extendable,
+            iteration_exponent: (id_exp & 0xf) as u8,
+            group_threshold: ((share_params >> 12) & 0xf) as u8 + 1,
+            group_count: ((share_params >> 8) & 0xf) as u8 + 1,
+        };
+        ensure!(
+            params.group_threshold <= params.group_count,
+            "The group threshold is greater than the number of groups"
+        );
+        let value_words = &data[ID_EXP_WORDS + SHARE_PARAMS_WORDS..data.len() - CHECKSUM_WORDS];
+        let value = words_to_bytes(value_words, padding)?;
+        Ok(Self {
+            params,
+            group_index: (share_params >> 16) as u8,
+            member_index: ((share_params >> 4) & 0xf) as u8,
+            member_threshold: (share_params & 0xf) as u8 + 1,
+            value,
+        })
+    }
+}
+
+fn bytes_to_words(bytes: &[u8]) -> Vec<u16> {
+    let word_count = (bytes.len() * 8).div_ceil(RADIX_BITS);
+    // the padding goes at the front, so read the bits from the end
+    let mut words = vec![0; word_count];
+    let mut acc = 0u32;
+    let mut bits = 0;
+    let mut index = word_count;
+    for &byte in bytes.iter().rev() {
+        acc |= u32::from(byte) << bits;
+        bits += 8;
+        while bits >= RADIX_BITS {
+            index -= 1;
+            words[index] = (acc & 0x3ff) as u16;
+            acc >>= RADIX_BITS;
+            bits -= RADIX_BITS;
+        }
+    }
+    if bits > 0 {
+        words[index - 1] = acc as u16;
+    }
+    words
+}
+
+fn words_to_bytes(words: &[u16], padding: usize) -> AnyhowResult<Vec<u8>> {
+    let byte_count = (words.len() * RADIX_BITS - padding) / 8;
+    let mut bytes = vec![0; byte_count];
+    let mut acc = 0u32;
+    let mut bits = 0;
+    let mut index = byte_count;
+    for &word in words.iter().rev() {
+        acc |= u32::from(word) << bits;
+        bits += RADIX_BITS;
+        while bits >= 8 && index > 0 {
+            index -= 1;
+            bytes[index] = acc as u8;
+            acc >>= 8;
+            bits -= 8;
+        }
+    }
+    ensure!(acc == 0, "Invalid padding in SLIP-39 share");
+    Ok(bytes)
+}
+
+fn customization_string(extendable: bool) -> &'static [u8] {
+    if extendable {
+        b"shamir_extendable"
+    } else {
+        b"shamir"
+    }
+}
+
+fn polymod(values: impl IntoIterator<Item = u16>) -> u32 {
+    const GEN: [u32; 10] = [
+        0xe0e040, 0x1c1c080, 0x3838100, 0x7070200, 0xe0e0009, 0x1c0c2412, 0x38086c24, 0x3090fc48,
+        0x21b1f890, 0x3f3f120,
+    ];
+    let mut chk = 1;
+    for v in values {
+        let b = chk >> 20;
+        chk = ((chk & 0xfffff) << 10) ^ u32::from(v);
+        for (i, gen) in GEN.iter().enumerate() {
+            if (b >> i) & 1 == 1 {
+                chk ^= gen;
+            }
+        }
+    }
+    chk
+}
+
+fn create_checksum(data: &[u16], extendable: bool) -> Vec<u16> {
+    let values = customization_string(extendable)
+        .iter()
+        .map(|&b| u16::from(b))
+        .chain(data.iter().copied())
+        .chain([0; CHECKSUM_WORDS]);
+    let polymod = polymod(values) ^ 1;
+    (0..CHECKSUM_WORDS)
+        .map(|i| ((polymod >> (RADIX_BITS * (CHECKSUM_WORDS - 1 - i))) & 0x3ff) as u16)
+        .collect()
+}
+
+fn verify_checksum(data: &[u16], extendable: bool) -> bool {
+    let values = customization_string(extendable)
+        .iter()
+        .map(|&b| u16::from(b))
+        .chain(data.iter().copied());
+    polymod(values) == 1
+}
+
+/// Encrypts (with rounds in ascending order) or decrypts (descending) the master secret.
+fn feistel(secret: &[u8], passphrase: &[u8], params: &Params, rounds: Vec<u8>) -> Vec<u8> {
+    let (left, right) = secret.split_at(secret.len() / 2);
+    let (mut left, mut right) = (left.to_vec(), right.to_vec());
+    let mut salt = vec![];
+    if !params.extendable {
+        salt.extend_from_slice(customization_string(false));
+        salt.extend_from_slice(&params.identifier.to_be_bytes());
+    }
+    let iterations = NonZeroU32::new((BASE_ITERATIONS << params.iteration_exponent) / 4)
+        .expect("nonzero iterations");
+    for round in rounds {
+        let mut password = vec![round];
+        password.extend_from_slice(passphrase);
+        let mut round_salt = salt.clone();
+        round_salt.extend_from_slice(&right);
+        let mut f = vec![0; right.len()];
+        pbkdf2::derive(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            iterations,
+            &round_salt,
+            &password,
+            &mut f,
+        );
+        let new_right = left.iter().zip(&f).map(|(l, f)| l ^ f).collect();
+        left = std::mem::replace(&mut right, new_right);
+    }
+    right.extend(left);
+    right
+}
+
+fn split_secret(threshold: u8, count: u8, secret: &[u8]) -> Vec<(u8, Vec<u8>)> {
+    if threshold == 1 {
+        return (0..count).map(|i| (i, secret.to_vec())).collect();
+    }
+    let random_share_count = threshold - 2;
+    let mut shares = (0..random_share_count)
+        .map(|i| {
+            let mut value = vec![0; secret.len()];
+            OsRng.fill_bytes(&mut value);
+            (i, value)
+        })
+        .collect::<Vec<_>>();
+    let mut random_part = vec![0; secret.len() - DIGEST_LEN];
+    OsRng.fill_bytes(&mut random_part);
+    let mut digest = digest(&random_part, secret);
+    digest.extend(random_part);
+    let mut base_shares = shares.clone();
+    base_shares.push((DIGEST_INDEX, digest));
+    base_shares.push((SECRET_INDEX, secret.to_vec()));
+    for i in random_share_count..count {
+        shares.push((i, interpolate(&base_shares, i)));
+    }
+    shares
+}
+
+fn recover_secret(threshold: u8, shares: &[(u8, Vec<u8>)]) -> AnyhowResult<Vec<u8>> {
+    if threshold == 1 {
+        return Ok(shares[0].1.clone());
+    }
+    let secret = interpolate(shares, SECRET_INDEX);
+    let digest_share = interpolate(shares, DIGEST_INDEX);
+    let (digest_bytes, random_part) = digest_share.split_at(DIGEST_LEN);
+    ensure!(
+        digest(random_part, &secret) == digest_bytes,
+        "Invalid digest; the shares may not be from the same set"
+    );
+    Ok(secret)
+}
+
+fn digest(random_part: &[u8], secret: &[u8]) -> Vec<u8> {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, random_part);
+    hmac::sign(&key, secret).as_ref()[..DIGEST_LEN].to_vec()
+}
+
+/// Returns the exponent and logarithm tables of GF(256) with the Rijndael polynomial.
+fn gf256_tables() -> ([u8; 255], [u8; 256]) {
+    let mut exp = [0; 255];
+    let mut log = [0; 256];
+    let mut poly = 1u16;
+    for i in 0..255 {
+        exp[i] = poly as u8;
+        log[poly as usize] = i as u8;
+        poly = (poly << 1) ^ poly;
+        if poly & 0x100 != 0 {
+            poly ^= 0x11b;
+        }
+    }
+    (exp, log)
+}
+
+/// Evaluates at `x` the polynomial passing through `shares`, bytewise, with Lagrange interpolation.
+fn interpolate(shares: &[(u8, Vec<u8>)], x: u8) -> Vec<u8> {
+    if let Some((_, value)) = shares.iter().find(|(xi, _)| *xi == x) {
+        return value.clone();
+    }
+    let (exp, log) = gf256_tables();
+    let log = |v: u8| usize::from(log[v as usize]);
+    let log_prod: usize = shares.iter().map(|(xi, _)| log(xi ^ x)).sum();
+    let mut result = vec![0; shares[0].1.len()];
+    for (xi, yi) in shares {
+        let denominator: usize = shares.iter().map(|(xj, _)| log(xj ^ xi)).sum();
+        let log_basis = (log_prod + 255 * shares.len() - log(xi ^ x) - denominator) % 255;
+        for (r, &y) in result.iter_mut().zip(yi) {
+            if y != 0 {
+                *r ^= exp[(log(y) + log_basis) % 255];
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{combine, split};
+
+    #[test]
+    fn vectors() {
+        // from the SLIP-39 test vectors, with the passphrase "TREZOR"
+        let secret = combine(
+            &["duckling enlarge academic academic agency result length solution fridge kidney coal piece deal husband erode duke ajar critical decision keyboard"],
+            b"TREZOR",
+        )
+        .unwrap();
+        assert_eq!(hex::encode(secret), "bb54aac4b89dc868ba37d9cc21b2cece");
+        let shares = [
+            "shadow pistol academic always adequate wildlife fancy gross oasis cylinder mustang wrist rescue view short owner flip making coding armed",
+            "shadow pistol academic acid actress prayer class unknown daughter sweater depict flip twice unkind craft early superior advocate guest smoking",
+        ];
+        let secret = combine(&shares, b"TREZOR").unwrap();
+        assert_eq!(hex::encode(secret), "b43ceb7e57a0ea8766221624d01b0864");
+        assert!(combine(&shares[..1], b"TREZOR").is_err());
+        let typo = "duckling enlarge academic academic agency result length solution fridge kidney coal piece deal husband erode duke ajar critical decision kidney";
+        assert!(combine(&[typo], b"TREZOR").is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        for secret in [[7; 16].as_slice(), [0xa5; 32].as_slice()] {
+            let shares = split(secret, 3, 5, b"").unwrap();
+            assert_eq!(shares.len(), 5);
+            let words = if secret.len() == 16 { 20 } else { 33 };
+            assert!(shares.iter().all(|s| s.split(' ').count() == words));
+            assert_eq!(combine(&shares[1..4], b"").unwrap(), secret);
+            assert_eq!(
+                combine(&[&shares[4], &shares[0], &shares[2]], b"").unwrap(),
+                secret
+            );
+            assert!(combine(&shares[..2], b"").is_err());
+        }
+        assert!(split(&[0; 16], 1, 3, b"").is_err());
+        assert!(split(&[0; 16], 4, 3, b"").is_err());
+    }
+}
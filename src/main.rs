@@ -2,9 +2,17 @@
 #![allow(special_module_name)]
 use std::io::{stdin, IsTerminal, Read, Write};
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use crate::lib::AnyhowResult;
-use anyhow::{bail, Context};
+use crate::lib::{
+    delegation::DelegationChain,
+    parse_derivation_path, parse_ingress_expiry,
+    qr::{QrEcc, QrFormat, QrOutputOpts},
+    AnyhowResult, IngressExpiryOpts,
+};
+use anyhow::{bail, ensure, Context};
+use bip32::DerivationPath;
+use candid::Principal;
 use clap::{crate_version, Args, Parser};
 use dialoguer::Password;
 use k256::SecretKey;
@@ -28,6 +36,7 @@ pub struct CliOpts {
 
 const AUTH_FLAGS: &[&str] = &[
     "pem_file",
+    "identity",
     "password_file",
     "hsm",
     "hsm_libpath",
@@ -42,8 +51,22 @@ struct GlobalOpts {
     #[arg(long, global = true)]
     pem_file: Option<PathBuf>,
 
-    /// If the PEM file is encrypted, read the password from this file (use "-" for STDIN)
-    #[arg(long, global = true, requires = "pem_file")]
+    /// Use the key of this dfx identity, from ~/.config/dfx/identity. Plaintext, encrypted,
+    /// keyring-backed and HSM identities are supported.
+    #[arg(
+        long,
+        global = true,
+        conflicts_with_all = ["pem_file", "hsm", "hsm_libpath", "hsm_slot", "hsm_id", "ledger"]
+    )]
+    identity: Option<String>,
+
+    /// Sign as the principal of this delegation chain, in the JSON format agent-js exports, using
+    /// the session key it delegates to from the other authentication flags.
+    #[arg(long, global = true, conflicts_with_all = ["ledger", "sender"])]
+    delegation_file: Option<PathBuf>,
+
+    /// If the PEM file or dfx identity is encrypted, read the password from this file (use "-" for STDIN)
+    #[arg(long, global = true)]
     password_file: Option<PathBuf>,
 
     /// Use a hardware key to sign messages.
@@ -87,10 +110,97 @@ struct GlobalOpts {
     #[arg(long, global = true)]
     ledger: bool,
 
+    /// With --ledger, talk to the Speculos emulator at this address (e.g. 127.0.0.1:9999) instead
+    /// of a device. Defaults to the QUILL_LEDGER_SPECULOS environment variable.
+    #[cfg_attr(not(feature = "ledger"), arg(hide = true), allow(dead_code))]
+    #[arg(long, global = true, requires = "ledger")]
+    ledger_speculos: Option<String>,
+
+    /// Use the key at this index of the default derivation path, m/44'/223'/0'/0/<INDEX>, when
+    /// deriving keys from a seed phrase or a Ledger device.
+    #[arg(
+        long,
+        global = true,
+        value_parser = clap::value_parser!(u32).range(..0x8000_0000)
+    )]
+    derivation_index: Option<u32>,
+
+    /// Use the key at this BIP-32 derivation path (e.g. m/44'/223'/1'/0/0) when deriving keys
+    /// from a seed phrase or a Ledger device.
+    #[arg(
+        long,
+        global = true,
+        value_parser = parse_derivation_path,
+        conflicts_with = "derivation_index"
+    )]
+    derivation_path: Option<DerivationPath>,
+
     /// Output the result(s) as UTF-8 QR codes.
     #[arg(long, global = true)]
     qr: bool,
 
+    /// Output QR codes as image files in the --qr-out directory instead of on the terminal.
+    /// Implies --qr.
+    #[arg(long, global = true, value_enum, default_value_t)]
+    qr_format: QrFormat,
+
+    /// The directory to write QR code image files to.
+    #[arg(long, global = true, default_value = ".")]
+    qr_out: PathBuf,
+
+    /// The error correction level of QR codes.
+    #[arg(long, global = true, value_enum, default_value_t)]
+    qr_ecc: QrEcc,
+
+    /// The size of each module (black or white square) of QR code image files, in pixels.
+    #[arg(
+        long,
+        global = true,
+        default_value_t = 8,
+        value_parser = clap::value_parser!(u32).range(1..=100)
+    )]
+    qr_scale: u32,
+
+    /// Time after which signed messages expire, e.g. `90s`, `5m` or `2h`, up to 30 days. The IC
+    /// only accepts a message within five minutes of its expiry, so longer values delay when it
+    /// can be sent.
+    #[arg(long, global = true, value_parser = parse_ingress_expiry, default_value = "5m")]
+    ingress_expiry: Duration,
+
+    /// Sign this many copies of each message, each expiring four minutes after the previous one.
+    /// `quill send` will send whichever copy is currently valid.
+    #[arg(
+        long,
+        global = true,
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    ingress_expiry_copies: u32,
+
+    /// The name of a network defined in `networks.json` in quill's config directory (e.g.
+    /// ~/.config/quill/networks.json). Defaults to the IC mainnet, or the URL in `IC_URL` if set.
+    #[arg(long, global = true)]
+    network: Option<String>,
+
+    /// Output messages without signing them, to be signed later with `quill sign`.
+    #[arg(long, global = true)]
+    unsigned_output: bool,
+
+    /// With --unsigned-output, the principal that will sign the messages, instead of a key.
+    #[arg(
+        long,
+        global = true,
+        requires = "unsigned_output",
+        conflicts_with_all = ["pem_file", "identity", "hsm", "ledger"]
+    )]
+    sender: Option<Principal>,
+
+    /// How to display responses from the IC. With `json`, only the response is written to stdout.
+    // Read by the sending commands through `from_global`.
+    #[allow(dead_code)]
+    #[arg(long, global = true, value_enum, default_value_t)]
+    output: commands::OutputFormat,
+
     /// Fetches the root key before making requests so that interfacing with local instances is possible.
     /// DO NOT USE WITH ANY REAL INFORMATION
     #[arg(
@@ -103,20 +213,90 @@ struct GlobalOpts {
 
 fn main() -> AnyhowResult {
     let opts = CliOpts::parse();
-    let qr = opts.global_opts.qr;
-    let fetch_root_key = opts.global_opts.fetch_root_key;
-    let auth = if let commands::Command::Generate(_) = &opts.command {
+    let network = lib::network::Network::load(
+        opts.global_opts.network.as_deref(),
+        opts.global_opts.fetch_root_key,
+    )?;
+    ensure!(
+        lib::signing::COPY_INTERVAL
+            .checked_mul(opts.global_opts.ingress_expiry_copies - 1)
+            .and_then(|span| span.checked_add(opts.global_opts.ingress_expiry))
+            .is_some_and(|last| last <= lib::MAX_INGRESS_EXPIRY),
+        "The last copy from --ingress-expiry-copies would expire after more than 30 days"
+    );
+    let ingress_expiry = IngressExpiryOpts {
+        ingress_expiry: opts.global_opts.ingress_expiry,
+        copies: opts.global_opts.ingress_expiry_copies,
+    };
+    let qr = QrOutputOpts {
+        enabled: opts.global_opts.qr || opts.global_opts.qr_format != QrFormat::Terminal,
+        format: opts.global_opts.qr_format,
+        out: opts.global_opts.qr_out.clone(),
+        ecc: opts.global_opts.qr_ecc,
+        scale: opts.global_opts.qr_scale,
+    };
+    let derivation_path = derivation_path(&opts.global_opts)?;
+    let unsigned_output = opts.global_opts.unsigned_output;
+    let mut auth = if let commands::Command::Generate(_) = &opts.command {
         AuthInfo::NoAuth
     } else {
         get_auth(opts.global_opts)?
     };
-    commands::dispatch(&auth, opts.command, fetch_root_key, qr)?;
+    if unsigned_output {
+        ensure!(
+            !matches!(auth, AuthInfo::NoAuth),
+            "--unsigned-output requires --sender, or a key to take the sender from"
+        );
+        // only the sender is needed to construct the messages, which are signed with `quill sign`
+        auth = AuthInfo::SenderOnly(lib::get_principal(&auth)?);
+    }
+    commands::dispatch(
+        &auth,
+        opts.command,
+        ingress_expiry,
+        &derivation_path,
+        &network,
+        &qr,
+    )?;
     Ok(())
 }
 
-fn get_auth(opts: GlobalOpts) -> AnyhowResult<AuthInfo> {
+/// The BIP-32 path selected with --derivation-index or --derivation-path.
+fn derivation_path(opts: &GlobalOpts) -> AnyhowResult<DerivationPath> {
+    if let Some(index) = opts.derivation_index {
+        lib::derivation_path_for_index(index)
+    } else if let Some(path) = &opts.derivation_path {
+        Ok(path.clone())
+    } else {
+        Ok(lib::default_derivation_path())
+    }
+}
+
+fn get_auth(mut opts: GlobalOpts) -> AnyhowResult<AuthInfo> {
+    let derivation_flags = opts.derivation_index.is_some() || opts.derivation_path.is_some();
+    ensure!(
+        opts.password_file.is_none() || opts.pem_file.is_some() || opts.identity.is_some(),
+        "--password-file requires --pem-file or --identity"
+    );
+    if let Some(delegation_file) = opts.delegation_file.take() {
+        let chain = DelegationChain::from_json(&read_file(delegation_file, "delegation chain")?)?;
+        let session_key = get_auth(opts)?;
+        if let AuthInfo::NoAuth = session_key {
+            bail!("--delegation-file requires the session key it delegates to, e.g. with --pem-file or --identity");
+        }
+        let public_key = lib::get_identity(&session_key)?
+            .public_key()
+            .context("The session key has no public key")?;
+        chain.validate(&public_key)?;
+        return Ok(AuthInfo::Delegated {
+            session_key: Box::new(session_key),
+            chain,
+        });
+    }
     if opts.seed_file.is_some() {
         bail!("Seed phrases are not accepted by commands directly anymore. Use `quill generate --phrase`.");
+    } else if let Some(sender) = opts.sender {
+        Ok(AuthInfo::SenderOnly(sender))
     } else if opts.hsm
         || opts.hsm_libpath.is_some()
         || opts.hsm_slot.is_some()
@@ -125,6 +305,10 @@ fn get_auth(opts: GlobalOpts) -> AnyhowResult<AuthInfo> {
         #[cfg(feature = "hsm")]
         {
             anyhow::ensure!(!opts.ledger, "Ledger flags cannot be used with HSM flags");
+            anyhow::ensure!(
+                !derivation_flags,
+                "Derivation flags cannot be used with HSM flags"
+            );
             anyhow::ensure!(
                 opts.pem_file.is_none() && opts.password_file.is_none(),
                 "PEM file flags cannot be used with HSM flags"
@@ -152,42 +336,98 @@ fn get_auth(opts: GlobalOpts) -> AnyhowResult<AuthInfo> {
                 opts.pem_file.is_none() && opts.password_file.is_none(),
                 "PEM file flags cannot be used with Ledger flags"
             );
-            Ok(AuthInfo::Ledger)
+            Ok(AuthInfo::Ledger {
+                derivation_path: derivation_path(&opts)?,
+                speculos: opts.ledger_speculos,
+            })
         }
         #[cfg(not(feature = "ledger"))]
         {
             anyhow::bail!("This build of quill does not support Ledger functionality.")
         }
+    } else if let Some(name) = &opts.identity {
+        ensure!(
+            !derivation_flags,
+            "Derivation flags cannot be used with a dfx identity, which contains a single key."
+        );
+        identity_auth(name, opts.password_file.as_deref())
     } else if opts.pem_file.is_some() {
+        ensure!(
+            !derivation_flags,
+            "Derivation flags cannot be used with a PEM file, which contains a single key. \
+To derive another key from its seed phrase, use `quill generate --phrase` with them instead."
+        );
         pem_auth(opts)
     } else {
         Ok(AuthInfo::NoAuth)
     }
 }
 
+fn identity_auth(name: &str, password_file: Option<&Path>) -> AnyhowResult<AuthInfo> {
+    let identity = lib::dfx::load_identity(name, || {
+        if let Some(password_file) = password_file {
+            read_file(password_file, "password")
+        } else if stdin().is_terminal() {
+            Ok(Password::new()
+                .with_prompt(format!("Password for dfx identity '{name}'"))
+                .interact()?)
+        } else {
+            bail!("Must use --password-file if the dfx identity is encrypted and stdin cannot receive terminal input.");
+        }
+    })?;
+    match identity {
+        lib::dfx::DfxIdentity::Anonymous => Ok(AuthInfo::NoAuth),
+        lib::dfx::DfxIdentity::Pem { pem, source } => parse_pem(&pem, &source, None),
+        #[cfg(feature = "hsm")]
+        lib::dfx::DfxIdentity::Hsm {
+            pkcs11_lib_path,
+            key_id,
+        } => {
+            let mut hsm = lib::HSMInfo::new()?;
+            if let Some(path) = pkcs11_lib_path {
+                hsm.libpath = path;
+            }
+            hsm.slot = 0;
+            hsm.ident = key_id;
+            Ok(AuthInfo::Pkcs11Hsm(hsm))
+        }
+        #[cfg(not(feature = "hsm"))]
+        lib::dfx::DfxIdentity::Hsm { .. } => {
+            bail!("This build of quill does not support HSM functionality.")
+        }
+    }
+}
+
 fn pem_auth(opts: GlobalOpts) -> AnyhowResult<AuthInfo> {
     let file = opts
         .pem_file
         .as_ref()
         .expect("pem_file needed for pem_auth");
-    let pem = pem::parse_many(read_file(file, "PEM")?)?;
+    parse_pem(
+        &read_file(file, "PEM")?,
+        &format!("file {}", file.display()),
+        opts.password_file.as_deref(),
+    )
+}
+
+/// Reads the first key in `pem`. `source` names where it came from, for error messages.
+fn parse_pem(pem: &str, source: &str, password_file: Option<&Path>) -> AnyhowResult<AuthInfo> {
+    let pem = pem::parse_many(pem)?;
     for document in pem {
         match document.tag() {
             sec1::EcPrivateKey::PEM_LABEL => {
                 return Ok(AuthInfo::K256Key(
-                    SecretKey::from_sec1_der(document.contents()).with_context(|| {
-                        format!("File {} was not a valid secp256k1 key", file.display())
-                    })?,
+                    SecretKey::from_sec1_der(document.contents())
+                        .with_context(|| format!("Could not read {source} as a secp256k1 key"))?,
                 ))
             }
             "PRIVATE KEY" => {
-                Ed25519KeyPair::from_pkcs8_maybe_unchecked(document.contents()).with_context(
-                    || format!("File {} was not a valid Ed25519 key", file.display()),
-                )?;
+                Ed25519KeyPair::from_pkcs8_maybe_unchecked(document.contents())
+                    .with_context(|| format!("Could not read {source} as an Ed25519 key"))?;
                 return Ok(AuthInfo::Ed25519Key(document.into_contents()));
             }
             pkcs8::EncryptedPrivateKeyInfo::PEM_LABEL => {
-                let password = if let Some(password_file) = &opts.password_file {
+                let password = if let Some(password_file) = password_file {
                     read_file(password_file, "password")?
                 } else if stdin().is_terminal() {
                     Password::new()
@@ -196,20 +436,25 @@ fn pem_auth(opts: GlobalOpts) -> AnyhowResult<AuthInfo> {
                 } else {
                     bail!("Must use --password-file if PEM file is encrypted and stdin cannot receive terminal input.");
                 };
+                let decrypted = pkcs8::EncryptedPrivateKeyInfo::try_from(document.contents())
+                    .map_err(pkcs8::Error::from)
+                    .and_then(|info| info.decrypt(password))
+                    .with_context(|| format!("Could not decrypt {source}"))?;
+                let info = pkcs8::PrivateKeyInfo::try_from(decrypted.as_bytes())?;
+                if info.algorithm.oid == lib::ED25519_OID {
+                    Ed25519KeyPair::from_pkcs8_maybe_unchecked(decrypted.as_bytes())
+                        .with_context(|| format!("Could not read {source} as an Ed25519 key"))?;
+                    return Ok(AuthInfo::Ed25519Key(decrypted.as_bytes().to_vec()));
+                }
                 return Ok(AuthInfo::K256Key(
-                    SecretKey::from_pkcs8_encrypted_der(document.contents(), password)
-                        .with_context(|| {
-                            format!("Could not read file {} as a secp256k1 key", file.display())
-                        })?,
+                    SecretKey::from_pkcs8_der(decrypted.as_bytes())
+                        .with_context(|| format!("Could not read {source} as a secp256k1 key"))?,
                 ));
             }
             _ => {}
         }
     }
-    bail!(
-        "File {} contained no recognized key formats",
-        file.display()
-    );
+    bail!("No recognized key formats in {source}");
 }
 
 fn read_file(path: impl AsRef<Path>, name: &str) -> AnyhowResult<String> {
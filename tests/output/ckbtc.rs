@@ -1,5 +1,5 @@
 use crate::{
-    ledger_compatible, quill_authed, quill_query, quill_query_authed, quill_send, OutputExt,
+    ledger_compatible, quill_authed, quill_query, quill_query_authed, quill_send, OutputExt, ALICE,
     PRINCIPAL,
 };
 
@@ -17,6 +17,12 @@ fn balance() {
     quill_query_authed("ckbtc balance --testnet").diff("ckbtc/balance/testnet.txt");
 }
 
+#[test]
+fn account_history() {
+    quill_query(&format!("ckbtc account-history --of {PRINCIPAL}"))
+        .diff("ckbtc/account_history/simple.txt");
+}
+
 #[test]
 fn retrieve_btc() {
     quill_send("ckbtc retrieve-btc 3L2Uyh1eHpfPyPayqrh5WjfnTzWiG4xPLu --amount 3.14 --memo 9")
@@ -48,3 +54,13 @@ fn transfer() {
     ))
     .diff("ckbtc/transfer/simple.txt");
 }
+
+#[test]
+fn approve() {
+    quill_send(&format!("ckbtc approve {ALICE} --amount 3.14")).diff("ckbtc/approve/simple.txt");
+    quill_query_authed(&format!("ckbtc allowance {ALICE}")).diff("ckbtc/approve/allowance.txt");
+    quill_send(&format!(
+        "ckbtc transfer-from {ALICE} --satoshis 1000 --memo 3"
+    ))
+    .diff("ckbtc/approve/transfer_from.txt");
+}
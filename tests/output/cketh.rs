@@ -0,0 +1,20 @@
+use crate::{quill_authed, quill_query, quill_send, OutputExt};
+
+#[test]
+fn withdraw_eth() {
+    quill_send("cketh withdraw-eth 0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed --amount 0.1")
+        .diff("cketh/withdraw_eth.txt");
+    quill_authed("cketh withdraw-eth 0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD --amount 0.1")
+        .assert_err();
+}
+
+#[test]
+fn withdraw_erc20() {
+    quill_send("cketh withdraw-erc20 0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed --ledger-canister-id xevnm-gaaaa-aaaar-qafnq-cai --amount 25 --decimals 6 --max-fee 0.003")
+        .diff("cketh/withdraw_erc20.txt");
+}
+
+#[test]
+fn retrieve_eth_status() {
+    quill_query("cketh retrieve-eth-status 77").diff("cketh/retrieve_eth_status.txt");
+}
@@ -0,0 +1,45 @@
+use crate::{
+    quill, quill_authed, quill_query, quill_query_authed, quill_send, OutputExt, PRINCIPAL,
+};
+
+const CKETH_LEDGER: &str = "ss2fx-dyaaa-aaaar-qacoq-cai";
+
+#[test]
+fn transfer() {
+    quill_send(&format!(
+        "icrc1 --ledger-canister-id {CKETH_LEDGER} transfer {PRINCIPAL} --amount 0.05 --decimals 18"
+    ))
+    .diff("icrc1/transfer/simple.txt");
+    quill_send(&format!(
+        "icrc1 transfer {PRINCIPAL} --ledger-canister-id {CKETH_LEDGER} --amount 1.5 --fee 0.000002 --memo 7 --decimals 18"
+    ))
+    .diff("icrc1/transfer/fee_and_memo.txt");
+    quill_authed(&format!(
+        "icrc1 --ledger-canister-id {CKETH_LEDGER} transfer {PRINCIPAL} --amount 0.05 --decimals 1"
+    ))
+    .assert_err();
+}
+
+#[test]
+fn balance() {
+    quill_query_authed(&format!(
+        "icrc1 --ledger-canister-id {CKETH_LEDGER} balance"
+    ))
+    .diff("icrc1/balance/authed.txt");
+    quill_query(&format!(
+        "icrc1 balance --ledger-canister-id {CKETH_LEDGER} --of {PRINCIPAL} --of-subaccount 01"
+    ))
+    .diff("icrc1/balance/of_subaccount.txt");
+    quill(&format!(
+        "icrc1 --ledger-canister-id {CKETH_LEDGER} balance"
+    ))
+    .assert_err();
+}
+
+#[test]
+fn missing_ledger() {
+    quill_authed(&format!(
+        "icrc1 transfer {PRINCIPAL} --amount 1 --decimals 8"
+    ))
+    .assert_err();
+}
@@ -9,6 +9,8 @@ use std::{
 };
 
 mod ckbtc;
+mod cketh;
+mod icrc1;
 mod neuron_manage;
 mod root;
 mod sns;
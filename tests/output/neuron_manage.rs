@@ -1,4 +1,10 @@
-use crate::{ledger_compatible, quill_send, OutputExt, ALICE, PRINCIPAL};
+use std::io::Write;
+
+use tempfile::NamedTempFile;
+
+use crate::{
+    escape_p, ledger_compatible, quill, quill_authed, quill_send, OutputExt, ALICE, PRINCIPAL,
+};
 
 const NEURON_ID: &str = "2313380519530470538";
 
@@ -137,3 +143,92 @@ fn disburse_maturity() {
     quill_send(&format!("neuron-manage {NEURON_ID} --disburse-maturity --disburse-maturity-percentage 50 --disburse-maturity-to {ALICE}"))
         .diff("neuron_manage/disburse_maturity_percentage_to.txt");
 }
+
+#[test]
+fn send_order() {
+    // messages for different neurons are sent concurrently, and a claim after both
+    let mut bundle = vec![];
+    for cmd in [
+        format!("neuron-manage {NEURON_ID} --start-dissolving"),
+        "neuron-manage 123 --start-dissolving".to_string(),
+        format!("neuron-manage {NEURON_ID} --stop-dissolving"),
+        "neuron-stake --already-transferred --name myNeuron".to_string(),
+    ] {
+        let out = quill_authed(&cmd);
+        out.assert_success();
+        let messages: Vec<serde_json::Value> = serde_json::from_slice(&out.stdout).unwrap();
+        bundle.extend(messages);
+    }
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(&serde_json::to_vec(&bundle).unwrap())
+        .unwrap();
+    let out = quill(&format!("send --dry-run -y {}", escape_p(&file)));
+    out.assert_success();
+    assert!(String::from_utf8(out.stdout).unwrap().ends_with(
+        "
+Sending order:
+  Message 1: sent first
+  Message 2: sent first
+  Message 3: sent after message 1 succeeds
+  Message 4: sent after messages 2 and 3 succeed
+"
+    ));
+}
+
+#[test]
+fn send_journal() {
+    // a dry run shows which messages an earlier, interrupted run already sent
+    let mut bundle = vec![];
+    for cmd in [
+        format!("neuron-manage {NEURON_ID} --start-dissolving"),
+        "neuron-manage 123 --start-dissolving".to_string(),
+        format!("neuron-manage {NEURON_ID} --stop-dissolving"),
+    ] {
+        let out = quill_authed(&cmd);
+        out.assert_success();
+        let messages: Vec<serde_json::Value> = serde_json::from_slice(&out.stdout).unwrap();
+        bundle.extend(messages);
+    }
+    let request_ids: Vec<_> = bundle
+        .iter()
+        .map(|message| message["request_status"]["request_id"].as_str().unwrap())
+        .collect();
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("bundle.json");
+    let journal = dir.path().join("bundle.json.journal");
+    std::fs::write(&file, serde_json::to_vec(&bundle).unwrap()).unwrap();
+    // without a journal, nothing is skipped and none is created
+    let out = quill(&format!("send --dry-run -y {}", escape_p(&file)));
+    out.assert_success();
+    assert_eq!(
+        String::from_utf8(out.stdout)
+            .unwrap()
+            .matches("Sending message with")
+            .count(),
+        3
+    );
+    assert!(!journal.exists());
+    let contents = format!(
+        r#"{{"request_id":"{}","status":"submitted"}}
+{{"request_id":"{}","status":"replied"}}
+{{"request_id":"{}","status":"submitted"}}
+"#,
+        request_ids[0], request_ids[0], request_ids[1]
+    );
+    std::fs::write(&journal, &contents).unwrap();
+    let out = quill(&format!("send --dry-run -y {}", escape_p(&file)));
+    out.assert_success();
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(stdout.contains(&format!(
+        "Skipping request 0x{}, which was already completed according to {}",
+        request_ids[0],
+        journal.display()
+    )));
+    assert!(stdout.contains(&format!(
+        "Request 0x{} was already sent, checking its status",
+        request_ids[1]
+    )));
+    assert_eq!(stdout.matches("Sending message with").count(), 1);
+    assert!(stdout.contains("Method name: manage_neuron"));
+    assert_eq!(std::fs::read_to_string(&journal).unwrap(), contents);
+}
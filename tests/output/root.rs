@@ -3,8 +3,8 @@ use std::io::Write;
 use tempfile::NamedTempFile;
 
 use crate::{
-    escape_p, ledger_compatible, quill, quill_authed, quill_query, quill_query_authed, quill_send,
-    quill_sns, OutputExt,
+    asset, default_pem, escape_p, ledger_compatible, quill, quill_authed, quill_command,
+    quill_query, quill_query_authed, quill_send, quill_sns, OutputExt, ALICE, PRINCIPAL,
 };
 
 // Uncomment tests on next ledger app update
@@ -31,6 +31,25 @@ fn account_balance() {
     .diff("account_balance/icrc1.txt")
 }
 
+#[test]
+fn account_history() {
+    quill_query("account-history ec0e2456fb9ff6c80f1d475b301d9b2ab873612f96e7fd74e7c0c0b2d58e6693")
+        .diff("account_history/simple.txt");
+
+    quill_query_authed("account-history --max-results 5 --start 100")
+        .diff("account_history/authed.txt");
+}
+
+#[test]
+fn approve() {
+    quill_send(&format!("approve {ALICE} --amount 12.5")).diff("approve/simple.txt");
+    quill_send(&format!(
+        "approve {ALICE} --spender-subaccount 01 --amount 12.5 --expected-allowance 2 --expires-at 2025-01-01T00:00:00Z --memo 4"
+    ))
+    .diff("approve/expiring.txt");
+    quill_query_authed(&format!("allowance {ALICE}")).diff("approve/allowance.txt");
+}
+
 #[test]
 fn claim_neurons() {
     quill_send("claim-neurons").diff("claim_neurons/simple.txt");
@@ -52,6 +71,27 @@ fn list_neurons() {
     quill_send("list-neurons 123 456 789").diff("list_neurons/many.txt");
 }
 
+#[test]
+fn neuron_report() {
+    quill_send("neuron-report").diff("neuron_report/simple.txt");
+    quill("neuron-report").assert_err();
+    let out = quill_authed("neuron-report");
+    out.assert_success();
+    let mut message = NamedTempFile::new().unwrap();
+    message.write_all(&out.stdout).unwrap();
+    let send = |flags: &str| {
+        quill(&format!(
+            "send --dry-run -y {flags} {}",
+            escape_p(&message.path())
+        ))
+    };
+    // the progress messages go to stderr, so that the report can be redirected to a file
+    let out = send("--neuron-report --output csv");
+    out.assert_success();
+    assert!(out.stdout.is_empty());
+    send("--output csv").assert_err();
+}
+
 #[test]
 fn list_proposals() {
     quill_query("list-proposals").diff("list_proposals/simple.txt");
@@ -122,6 +162,314 @@ Legacy account id: ffc463646a2c92dce58d1179d26c64d4ccbaf1079a6edc5628cedc0d4b3b1
     .assert_success();
 }
 
+#[test]
+fn derivation_index() {
+    let pem = NamedTempFile::new().unwrap();
+    let mut phrase = NamedTempFile::new().unwrap();
+    phrase
+        .write_all(b"tornado allow zero warm have deer wool finish tiger ski dynamic strong")
+        .unwrap();
+    quill(&format!(
+        "public-ids --phrase-file {} --scan 3",
+        escape_p(&phrase)
+    ))
+    .diff("public_ids/scan.txt");
+    quill(&format!(
+        "public-ids --phrase-file {} --derivation-path \"m/44'/223'/1'/0/0\"",
+        escape_p(&phrase)
+    ))
+    .diff_s(
+        b"\
+Principal id: dicja-uka7z-o52kh-ljrox-nw4vq-ji7wv-yl2ee-npmq5-u3n6w-66ili-oqe
+Legacy account id: 565ba60672bc5563ba47c0b46fda0aa61defd04b989c58befb8b025eaebd35ba",
+    );
+    quill(&format!(
+        r#"generate --phrase "tornado allow zero warm have deer wool finish tiger ski dynamic strong"
+             --pem-file {pem} --overwrite-pem-file --storage-mode plaintext --derivation-index 2"#,
+        pem = escape_p(&pem),
+    ))
+    .assert_success();
+    quill(&format!("public-ids --pem-file {}", escape_p(&pem))).diff_s(
+        b"\
+Principal id: 3wcfq-u4a5j-expxd-xesdu-756xp-nzaa3-elv5c-hfquh-vbm4f-ajam2-jqe
+Legacy account id: 1a923b2ef5bf5004efacb5728f363baea6024cf417cf58a31ca08a31487ebc7e",
+    );
+    quill(&format!(
+        "public-ids --pem-file {} --derivation-index 1",
+        escape_p(&pem)
+    ))
+    .assert_err();
+    quill(&format!(
+        "public-ids --pem-file {} --scan 2",
+        escape_p(&pem)
+    ))
+    .assert_err();
+}
+
+#[test]
+fn generate_ed25519() {
+    let pem = NamedTempFile::new().unwrap();
+    let mut password = NamedTempFile::new().unwrap();
+    password.write_all(b"correct horse battery staple").unwrap();
+    quill(&format!(
+        r#"generate --phrase "tornado allow zero warm have deer wool finish tiger ski dynamic strong"
+             --pem-file {pem} --overwrite-pem-file --storage-mode plaintext --key-type ed25519"#,
+        pem = escape_p(&pem),
+    ))
+    .assert_success();
+    quill(&format!("public-ids --pem-file {}", escape_p(&pem))).diff_s(
+        b"\
+Principal id: hu2ag-25lz3-6i2c7-viyci-k4gsd-4htaa-zds7c-pokx5-bleyp-hrxak-qae
+Legacy account id: 2bed6762a7686af7f0728a0882b590864edc2193a8a9f46299f5d8baa942da56",
+    );
+    quill(&format!(
+        r#"generate --phrase "tornado allow zero warm have deer wool finish tiger ski dynamic strong"
+             --pem-file {pem} --overwrite-pem-file --password-file {password} --key-type ed25519"#,
+        pem = escape_p(&pem),
+        password = escape_p(&password),
+    ))
+    .assert_success();
+    quill(&format!(
+        "public-ids --pem-file {} --password-file {}",
+        escape_p(&pem),
+        escape_p(&password)
+    ))
+    .diff_s(
+        b"\
+Principal id: hu2ag-25lz3-6i2c7-viyci-k4gsd-4htaa-zds7c-pokx5-bleyp-hrxak-qae
+Legacy account id: 2bed6762a7686af7f0728a0882b590864edc2193a8a9f46299f5d8baa942da56",
+    );
+    let mut phrase = NamedTempFile::new().unwrap();
+    phrase
+        .write_all(b"tornado allow zero warm have deer wool finish tiger ski dynamic strong")
+        .unwrap();
+    quill(&format!(
+        "public-ids --phrase-file {} --key-type ed25519",
+        escape_p(&phrase)
+    ))
+    .diff_s(
+        b"\
+Principal id: hu2ag-25lz3-6i2c7-viyci-k4gsd-4htaa-zds7c-pokx5-bleyp-hrxak-qae
+Legacy account id: 2bed6762a7686af7f0728a0882b590864edc2193a8a9f46299f5d8baa942da56",
+    );
+    quill(&format!(
+        "public-ids --phrase-file {} --key-type ed25519 --genesis-dfn",
+        escape_p(&phrase)
+    ))
+    .assert_err();
+}
+
+#[test]
+fn generate_passphrase() {
+    let pem = NamedTempFile::new().unwrap();
+    let mut passphrase = NamedTempFile::new().unwrap();
+    passphrase
+        .write_all(b"correct horse battery staple\n")
+        .unwrap();
+    quill(&format!(
+        r#"generate --phrase "tornado allow zero warm have deer wool finish tiger ski dynamic strong"
+             --pem-file {pem} --overwrite-pem-file --storage-mode plaintext --passphrase-file {passphrase}"#,
+        pem = escape_p(&pem),
+        passphrase = escape_p(&passphrase),
+    ))
+    .assert_success();
+    quill(&format!("public-ids --pem-file {}", escape_p(&pem))).diff_s(
+        b"\
+Principal id: xk2s5-hkp5s-bosdm-disyv-loqno-bahjb-f7e27-o3jlw-qkpxd-kzyq6-jqe
+Legacy account id: a7a0d6a2e72718f0b5edf4b98cdba77127b22e70ac17ee2778f475c4ed7e6540",
+    );
+}
+
+#[test]
+fn generate_shares() {
+    let pem = NamedTempFile::new().unwrap();
+    let shares_file = NamedTempFile::new().unwrap();
+    quill(&format!(
+        r#"generate --phrase "tornado allow zero warm have deer wool finish tiger ski dynamic strong"
+             --shares 3 --threshold 2 --seed-file {shares} --overwrite-seed-file
+             --pem-file {pem} --overwrite-pem-file --storage-mode plaintext"#,
+        shares = escape_p(&shares_file),
+        pem = escape_p(&pem),
+    ))
+    .assert_success();
+    let shares = std::fs::read_to_string(shares_file.path()).unwrap();
+    let shares = shares.lines().collect::<Vec<_>>();
+    assert_eq!(shares.len(), 3);
+    quill(&format!(
+        r#"generate --phrase "{}" --phrase "{}" --pem-file {pem} --overwrite-pem-file --storage-mode plaintext"#,
+        shares[2],
+        shares[0],
+        pem = escape_p(&pem),
+    ))
+    .assert_success();
+    quill(&format!("public-ids --pem-file {}", escape_p(&pem))).diff_s(
+        b"\
+Principal id: beckf-r6bg7-t6ju6-s7k45-b5jtj-mcm57-zjaie-svgrr-7ekzs-55v75-sae
+Legacy account id: ffc463646a2c92dce58d1179d26c64d4ccbaf1079a6edc5628cedc0d4b3b1866",
+    );
+    quill(&format!(
+        r#"generate --phrase "{}" --pem-file {pem} --overwrite-pem-file --storage-mode plaintext"#,
+        shares[1],
+        pem = escape_p(&pem),
+    ))
+    .assert_err();
+    // the shares are not written unencrypted when the PEM file is password-protected
+    let mut password = NamedTempFile::new().unwrap();
+    password.write_all(b"correct horse battery staple").unwrap();
+    let shares_file = NamedTempFile::new().unwrap();
+    quill(&format!(
+        r#"generate --shares 3 --threshold 2 --seed-file {shares} --overwrite-seed-file
+             --pem-file {pem} --overwrite-pem-file --password-file {password}"#,
+        shares = escape_p(&shares_file),
+        pem = escape_p(&pem),
+        password = escape_p(&password),
+    ))
+    .assert_err();
+    assert_eq!(std::fs::read_to_string(shares_file.path()).unwrap(), "");
+    // a single share is enough with a threshold of 1
+    let out = quill(&format!(
+        r#"generate --phrase "tornado allow zero warm have deer wool finish tiger ski dynamic strong"
+             --shares 1 --threshold 1
+             --pem-file {pem} --overwrite-pem-file --storage-mode plaintext"#,
+        pem = escape_p(&pem),
+    ));
+    out.assert_success();
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    let share = stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("Share 1: "))
+        .unwrap();
+    quill(&format!(
+        r#"generate --phrase "{share}" --pem-file {pem} --overwrite-pem-file --storage-mode plaintext"#,
+        pem = escape_p(&pem),
+    ))
+    .assert_success();
+    quill(&format!("public-ids --pem-file {}", escape_p(&pem))).diff_s(
+        b"\
+Principal id: beckf-r6bg7-t6ju6-s7k45-b5jtj-mcm57-zjaie-svgrr-7ekzs-55v75-sae
+Legacy account id: ffc463646a2c92dce58d1179d26c64d4ccbaf1079a6edc5628cedc0d4b3b1866",
+    );
+}
+
+#[test]
+fn generate_encrypted_seed_file() {
+    let pem = NamedTempFile::new().unwrap();
+    let seed = NamedTempFile::new().unwrap();
+    let mut password = NamedTempFile::new().unwrap();
+    password.write_all(b"correct horse battery staple").unwrap();
+    let mut wrong_password = NamedTempFile::new().unwrap();
+    wrong_password.write_all(b"battery staple horse").unwrap();
+    quill(&format!(
+        r#"generate --phrase "tornado allow zero warm have deer wool finish tiger ski dynamic strong"
+             --seed-file {seed} --overwrite-seed-file --pem-file {pem} --overwrite-pem-file --password-file {password}"#,
+        seed = escape_p(&seed),
+        pem = escape_p(&pem),
+        password = escape_p(&password),
+    ))
+    .assert_success();
+    let contents = std::fs::read_to_string(seed.path()).unwrap();
+    assert!(contents.starts_with("-----BEGIN ENCRYPTED SEED PHRASE-----"));
+    assert!(!contents.contains("tornado"));
+    quill(&format!(
+        "generate --phrase-file {seed} --pem-file {pem} --overwrite-pem-file --storage-mode plaintext --password-file {password}",
+        seed = escape_p(&seed),
+        pem = escape_p(&pem),
+        password = escape_p(&password),
+    ))
+    .assert_success();
+    quill(&format!("public-ids --pem-file {}", escape_p(&pem))).diff_s(
+        b"\
+Principal id: beckf-r6bg7-t6ju6-s7k45-b5jtj-mcm57-zjaie-svgrr-7ekzs-55v75-sae
+Legacy account id: ffc463646a2c92dce58d1179d26c64d4ccbaf1079a6edc5628cedc0d4b3b1866",
+    );
+    quill(&format!(
+        "generate --phrase-file {seed} --pem-file {pem} --overwrite-pem-file --storage-mode plaintext --password-file {password}",
+        seed = escape_p(&seed),
+        pem = escape_p(&pem),
+        password = escape_p(&wrong_password),
+    ))
+    .diff_err("generate/wrong_seed_password.txt");
+}
+
+#[test]
+fn reencrypt_pem() {
+    let pem = NamedTempFile::new().unwrap();
+    let mut old_password = NamedTempFile::new().unwrap();
+    old_password
+        .write_all(b"correct horse battery staple")
+        .unwrap();
+    let mut new_password = NamedTempFile::new().unwrap();
+    new_password.write_all(b"battery staple horse").unwrap();
+    let expected = b"\
+Principal id: beckf-r6bg7-t6ju6-s7k45-b5jtj-mcm57-zjaie-svgrr-7ekzs-55v75-sae
+Legacy account id: ffc463646a2c92dce58d1179d26c64d4ccbaf1079a6edc5628cedc0d4b3b1866";
+    quill(&format!(
+        r#"generate --phrase "tornado allow zero warm have deer wool finish tiger ski dynamic strong"
+             --pem-file {pem} --overwrite-pem-file --storage-mode plaintext"#,
+        pem = escape_p(&pem),
+    ))
+    .assert_success();
+    // encrypt a plaintext key in place
+    quill(&format!(
+        "reencrypt-pem --pem-file {pem} {pem} --new-password-file {password} --scrypt-log-n 14",
+        pem = escape_p(&pem),
+        password = escape_p(&old_password),
+    ))
+    .assert_success();
+    quill(&format!("public-ids --pem-file {}", escape_p(&pem)))
+        .diff_err("base_command/need_password.txt");
+    quill(&format!(
+        "public-ids --pem-file {} --password-file {}",
+        escape_p(&pem),
+        escape_p(&old_password)
+    ))
+    .diff_s(expected);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = |pem: &NamedTempFile| std::fs::metadata(pem).unwrap().permissions().mode();
+        assert_eq!(mode(&pem) & 0o777, 0o600);
+        // other permissions are kept
+        std::fs::set_permissions(&pem, std::fs::Permissions::from_mode(0o640)).unwrap();
+        quill(&format!(
+            "reencrypt-pem --pem-file {pem} --password-file {old} {pem} --new-password-file {old}",
+            pem = escape_p(&pem),
+            old = escape_p(&old_password),
+        ))
+        .assert_success();
+        assert_eq!(mode(&pem) & 0o777, 0o640);
+    }
+    // rotate the password
+    quill(&format!(
+        "reencrypt-pem --pem-file {pem} --password-file {old} {pem} --new-password-file {new}",
+        pem = escape_p(&pem),
+        old = escape_p(&old_password),
+        new = escape_p(&new_password),
+    ))
+    .assert_success();
+    quill(&format!(
+        "public-ids --pem-file {} --password-file {}",
+        escape_p(&pem),
+        escape_p(&old_password)
+    ))
+    .assert_err();
+    quill(&format!(
+        "public-ids --pem-file {} --password-file {}",
+        escape_p(&pem),
+        escape_p(&new_password)
+    ))
+    .diff_s(expected);
+    let mut bad_password = NamedTempFile::new().unwrap();
+    bad_password.write_all(b"hunter2").unwrap();
+    quill(&format!(
+        "reencrypt-pem --pem-file {pem} --password-file {new} {pem} --new-password-file {bad}",
+        pem = escape_p(&pem),
+        new = escape_p(&new_password),
+        bad = escape_p(&bad_password),
+    ))
+    .diff_err("base_command/bad_password.txt");
+}
+
 #[test]
 fn public_ids() {
     quill_authed("public-ids").diff("public_ids/basic.txt");
@@ -186,6 +534,320 @@ fn transfer_icrc1() {
         .diff("transfer/icrc1.txt");
 }
 
+#[test]
+fn transfer_from() {
+    quill_send(&format!("transfer-from {ALICE} --amount 3")).diff("transfer_from/simple.txt");
+    quill_send(&format!(
+        "transfer-from {ALICE} --from-subaccount 02 --to {ALICE} --spender-subaccount 01 --amount 3 --fee 0.0001"
+    ))
+    .diff("transfer_from/subaccounts.txt");
+}
+
+#[test]
+fn verify() {
+    let out = quill_authed("transfer 345f723e9e619934daac6ae0f4be13a7b0ba57d6a608e511a00fd0ded5866752 --amount 0.000123");
+    out.assert_success();
+    let mut bundle = NamedTempFile::new().unwrap();
+    bundle.write_all(&out.stdout).unwrap();
+    quill(&format!("verify {}", escape_p(&bundle))).assert_success();
+
+    let mut messages: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    messages[0]["request_status"]["request_id"] = "00".repeat(32).into();
+    let mut tampered = NamedTempFile::new().unwrap();
+    tampered
+        .write_all(&serde_json::to_vec(&messages).unwrap())
+        .unwrap();
+    quill(&format!("verify {}", escape_p(&tampered))).assert_err();
+}
+
+#[test]
+fn ingress_expiry_copies() {
+    let out = quill_authed("transfer 345f723e9e619934daac6ae0f4be13a7b0ba57d6a608e511a00fd0ded5866752 --amount 0.000123 --ingress-expiry 1h --ingress-expiry-copies 3");
+    out.assert_success();
+    let messages: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(messages[0]["later_copies"].as_array().unwrap().len(), 2);
+    let mut bundle = NamedTempFile::new().unwrap();
+    bundle.write_all(&out.stdout).unwrap();
+    quill(&format!("verify {}", escape_p(&bundle))).assert_success();
+    quill("transfer 345f723e9e619934daac6ae0f4be13a7b0ba57d6a608e511a00fd0ded5866752 --amount 1 --ingress-expiry-copies 0")
+        .assert_err();
+    for args in [
+        "--ingress-expiry 0",
+        "--ingress-expiry 31d",
+        "--ingress-expiry 99999999999999999999d",
+        "--ingress-expiry 29d --ingress-expiry-copies 400",
+    ] {
+        quill_authed(&format!(
+            "transfer 345f723e9e619934daac6ae0f4be13a7b0ba57d6a608e511a00fd0ded5866752 --amount 1 {args}"
+        ))
+        .assert_err();
+    }
+}
+
+#[test]
+fn network_profiles() {
+    let mut networks = NamedTempFile::new().unwrap();
+    networks
+        .write_all(
+            br#"{
+    "local": {
+        "url": "http://127.0.0.1:4943",
+        "fetch_root_key": true,
+        "canister_ids": { "governance": "bkyz2-fmaaa-aaaaa-qaaaq-cai" }
+    }
+}"#,
+        )
+        .unwrap();
+    let out = quill_command()
+        .env("QUILL_NETWORKS_FILE", networks.path())
+        .args([
+            "list-neurons",
+            "--network",
+            "local",
+            "--pem-file",
+            default_pem(),
+        ])
+        .output()
+        .unwrap();
+    out.assert_success();
+    let messages: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(
+        messages[0]["request_status"]["canister_id"],
+        "bkyz2-fmaaa-aaaaa-qaaaq-cai"
+    );
+    quill_command()
+        .env("QUILL_NETWORKS_FILE", networks.path())
+        .args([
+            "list-neurons",
+            "--network",
+            "staging",
+            "--pem-file",
+            default_pem(),
+        ])
+        .output()
+        .unwrap()
+        .assert_err();
+}
+
+#[test]
+fn dfx_identity() {
+    let config_root = tempfile::tempdir().unwrap();
+    let identity_dir = config_root.path().join(".config/dfx/identity/alice");
+    std::fs::create_dir_all(&identity_dir).unwrap();
+    std::fs::copy(default_pem(), identity_dir.join("identity.pem")).unwrap();
+    let expected = quill(&format!("public-ids --pem-file {}", default_pem()));
+    expected.assert_success();
+    let out = quill_command()
+        .env("DFX_CONFIG_ROOT", config_root.path())
+        .args(["public-ids", "--identity", "alice"])
+        .output()
+        .unwrap();
+    out.assert_success();
+    assert_eq!(out.stdout, expected.stdout);
+    quill_command()
+        .env("DFX_CONFIG_ROOT", config_root.path())
+        .args(["public-ids", "--identity", "bob"])
+        .output()
+        .unwrap()
+        .assert_err();
+}
+
+#[test]
+fn dfx_encrypted_identity() {
+    // identity.pem encrypted the way dfx does, with Argon2id and AES-256-GCM, under the password
+    // "correct horse battery staple"
+    let config_root = tempfile::tempdir().unwrap();
+    let identity_dir = config_root.path().join(".config/dfx/identity/alice");
+    std::fs::create_dir_all(&identity_dir).unwrap();
+    for file in ["identity.json", "identity.pem.encrypted"] {
+        std::fs::copy(
+            asset(&format!("dfx_encrypted/{file}")),
+            identity_dir.join(file),
+        )
+        .unwrap();
+    }
+    let mut password = NamedTempFile::new().unwrap();
+    password.write_all(b"correct horse battery staple").unwrap();
+    let out = quill_command()
+        .env("DFX_CONFIG_ROOT", config_root.path())
+        .args(["public-ids", "--identity", "alice", "--password-file"])
+        .arg(password.path())
+        .output()
+        .unwrap();
+    out.assert_success();
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(stdout.starts_with(
+        "Principal id: fdsgv-62ihb-nbiqv-xgic5-iefsv-3cscz-tmbzv-63qd5-vh43v-dqfrt-pae\n"
+    ));
+    let mut wrong_password = NamedTempFile::new().unwrap();
+    wrong_password.write_all(b"horse correct").unwrap();
+    quill_command()
+        .env("DFX_CONFIG_ROOT", config_root.path())
+        .args(["public-ids", "--identity", "alice", "--password-file"])
+        .arg(wrong_password.path())
+        .output()
+        .unwrap()
+        .assert_err();
+}
+
+#[test]
+fn delegation_file() {
+    let mut chain = NamedTempFile::new().unwrap();
+    chain
+        .write_all(
+            br#"{
+    "delegations": [
+        {
+            "delegation": {
+                "expiration": "38eecfcf56a60000",
+                "pubkey": "3056301006072a8648ce3d020106052b8104000a03420004cbb34e3b863720ac396e16aa61b58951d6fc8d01482a14bfda0cafb592cd1080014814004efa5b077fddbc805775d11a88cad004b1c027d68e6765ae990172ab"
+            },
+            "signature": "21b3738a2b26594621a41504b34b6a868cb7a29a79af862fb5301d44628cd210a4b5a99ab5f5dad389329778b651d8878a62316809532ce23f7db438322bd70c"
+        }
+    ],
+    "publicKey": "302a300506032b65700321008a88e3dd7409f195fd52db2d3cba5d72ca6709bf1d94121bf3748801b40f6f5c"
+}"#,
+        )
+        .unwrap();
+    quill(&format!(
+        "public-ids --pem-file {} --delegation-file {}",
+        default_pem(),
+        escape_p(&chain)
+    ))
+    .diff_s(
+        b"\
+Principal id: wf3fv-4c4nr-7ks2b-xa4u7-kf3no-32glf-lf7e4-4ng4a-wwtlu-a2vnq-nae
+Legacy account id: 02741713ea757b4fd210c50618170a1fab3c24df3194f770c47f009b618ae055",
+    );
+    // the chain delegates to the key in identity.pem only
+    quill(&format!(
+        "public-ids --pem-file {} --delegation-file {}",
+        asset("identity2.pem"),
+        escape_p(&chain)
+    ))
+    .assert_err();
+    quill(&format!(
+        "public-ids --delegation-file {}",
+        escape_p(&chain)
+    ))
+    .assert_err();
+}
+
+#[test]
+#[cfg(feature = "hsm")]
+#[ignore = "requires SoftHSM2"]
+fn hsm_softhsm() {
+    let libpath = std::env::var("QUILL_HSM_LIBPATH")
+        .unwrap_or_else(|_| "/usr/lib/softhsm/libsofthsm2.so".into());
+    // a throwaway token, so the test always starts from an empty slot
+    let token_dir = tempfile::tempdir().unwrap();
+    let conf = token_dir.path().join("softhsm2.conf");
+    std::fs::write(
+        &conf,
+        format!("directories.tokendir = {}\n", token_dir.path().display()),
+    )
+    .unwrap();
+    std::process::Command::new("softhsm2-util")
+        .env("SOFTHSM2_CONF", &conf)
+        .args(["--init-token", "--free", "--label", "quill-test"])
+        .args(["--pin", "1234", "--so-pin", "5678"])
+        .output()
+        .unwrap()
+        .assert_success();
+    let quill_hsm = |args: &[&str]| {
+        quill_command()
+            .env("SOFTHSM2_CONF", &conf)
+            .env("QUILL_HSM_PIN", "1234")
+            .args(args)
+            .args(["--hsm-libpath", &libpath, "--hsm-id", "0a"])
+            .output()
+            .unwrap()
+    };
+    let slots = quill_hsm(&["hsm", "list-slots"]);
+    slots.assert_success();
+    assert!(String::from_utf8(slots.stdout)
+        .unwrap()
+        .starts_with("Slot 0: quill-test ("));
+    let generated = quill_hsm(&["hsm", "generate-key", "--label", "test key"]);
+    generated.assert_success();
+    let ids = String::from_utf8(generated.stdout).unwrap();
+    assert!(ids.starts_with("Principal id: "));
+    let keys = quill_hsm(&["hsm", "list-keys"]);
+    keys.assert_success();
+    assert_eq!(
+        String::from_utf8(keys.stdout).unwrap(),
+        format!("Key ID: 0a\nLabel: test key\n{ids}")
+    );
+    let public_ids = quill_hsm(&["public-ids"]);
+    public_ids.assert_success();
+    assert_eq!(String::from_utf8(public_ids.stdout).unwrap(), ids);
+    quill_hsm(&["hsm", "generate-key"]).assert_err();
+}
+
+#[test]
+fn json_output() {
+    let out = quill_query(
+        "account-balance ec0e2456fb9ff6c80f1d475b301d9b2ab873612f96e7fd74e7c0c0b2d58e6693 --output json",
+    );
+    out.assert_success();
+    assert!(out.stdout.is_empty());
+    assert!(String::from_utf8(out.stderr)
+        .unwrap()
+        .contains("Sending message with"));
+}
+
+#[test]
+fn unsigned_output() {
+    let out = quill(&format!(
+        "transfer 345f723e9e619934daac6ae0f4be13a7b0ba57d6a608e511a00fd0ded5866752 --amount 0.000123 --unsigned-output --sender {PRINCIPAL}"
+    ));
+    out.assert_success();
+    let mut unsigned = NamedTempFile::new().unwrap();
+    unsigned.write_all(&out.stdout).unwrap();
+    quill(&format!("verify {}", escape_p(&unsigned))).assert_err();
+
+    let out = quill_authed(&format!("sign {}", escape_p(&unsigned)));
+    out.assert_success();
+    let mut signed = NamedTempFile::new().unwrap();
+    signed.write_all(&out.stdout).unwrap();
+    quill(&format!("verify {}", escape_p(&signed))).assert_success();
+    // already signed
+    quill_authed(&format!("sign {}", escape_p(&signed))).assert_err();
+
+    let out = quill(&format!(
+        "transfer 345f723e9e619934daac6ae0f4be13a7b0ba57d6a608e511a00fd0ded5866752 --amount 0.000123 --unsigned-output --sender {ALICE}"
+    ));
+    out.assert_success();
+    let mut unsigned = NamedTempFile::new().unwrap();
+    unsigned.write_all(&out.stdout).unwrap();
+    // wrong key
+    quill_authed(&format!("sign {}", escape_p(&unsigned))).assert_err();
+    // no sender, which would otherwise be anonymous
+    quill("transfer 345f723e9e619934daac6ae0f4be13a7b0ba57d6a608e511a00fd0ded5866752 --amount 0.000123 --unsigned-output")
+        .assert_err();
+
+    // the IC app is told the claim is staking when it is signed later
+    let out = quill_sns(&format!(
+        "sns stake-neuron --memo 777 --claim-only --unsigned-output --sender {PRINCIPAL}"
+    ));
+    out.assert_success();
+    let messages: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(messages[0]["staking"], true);
+
+    // unsupported messages are refused before the Ledger device is used
+    let out = quill(&format!(
+        "replace-node-provider-id --node-operator-id {PRINCIPAL} --node-provider-id {ALICE} --unsigned-output --sender {PRINCIPAL}"
+    ));
+    out.assert_success();
+    let mut unsigned = NamedTempFile::new().unwrap();
+    unsigned.write_all(&out.stdout).unwrap();
+    let out = quill(&format!("sign --ledger {}", escape_p(&unsigned)));
+    out.assert_err();
+    assert!(String::from_utf8(out.stderr)
+        .unwrap()
+        .contains("Cannot use --ledger with this command"));
+}
+
 #[test]
 fn ledger_fail_early() {
     quill("replace-node-provider-id --ledger --node-operator-id fdsgv-62ihb-nbiqv-xgic5-iefsv-3cscz-tmbzv-63qd5-vh43v-dqfrt-pae \
@@ -196,3 +858,69 @@ fn ledger_fail_early() {
     quill("neuron-manage 1 --ledger --disburse-maturity")
         .diff_err("ledger_incompatible/by_flag.txt");
 }
+
+#[test]
+fn qr_decode() {
+    let mut frames = NamedTempFile::new().unwrap();
+    frames
+        .write_all(
+            b"quill:3-3/c59f1ffe/BQDRQQnYEQAAAA==
+quill:1-3/c59f1ffe/H4sIAAAAAAACA6tWyk
+
+quill:3-3/c59f1ffe/BQDRQQnYEQAAAA==
+quill:2-3/c59f1ffe/jNyclXslIqzy/KSVGq
+",
+        )
+        .unwrap();
+    quill(&format!("qr-decode {}", escape_p(&frames))).diff_s(br#"[{"hello":"world"}]"#);
+    let mut single = NamedTempFile::new().unwrap();
+    single
+        .write_all(b"H4sIAAAAAAACA6tWykjNyclXslIqzy/KSVGqBQDRQQnYEQAAAA==")
+        .unwrap();
+    quill(&format!("qr-decode {}", escape_p(&single))).diff_s(br#"[{"hello":"world"}]"#);
+    // the QR codes of several messages are decoded into one bundle
+    let mut several = NamedTempFile::new().unwrap();
+    several
+        .write_all(
+            b"quill:2-3/c59f1ffe/jNyclXslIqzy/KSVGq
+H4sIAAAAAAACA6tWykjNyclXslIqLM3MyVGqBQA9orcAEQAAAA==
+quill:1-3/c59f1ffe/H4sIAAAAAAACA6tWyk
+quill:3-3/c59f1ffe/BQDRQQnYEQAAAA==
+",
+        )
+        .unwrap();
+    quill(&format!("qr-decode {}", escape_p(&several)))
+        .diff_s(br#"[{"hello":"world"},{"hello":"quill"}]"#);
+    let mut missing = NamedTempFile::new().unwrap();
+    missing
+        .write_all(b"quill:1-3/c59f1ffe/H4sIAAAAAAACA6tWyk")
+        .unwrap();
+    quill(&format!("qr-decode {}", escape_p(&missing))).assert_err();
+}
+
+#[test]
+fn qr_code_files() {
+    let out = tempfile::tempdir().unwrap();
+    quill(&format!(
+        "qr-code --string hello --qr-format svg --qr-out {}",
+        escape_p(&out.path())
+    ))
+    .assert_success();
+    let svg = std::fs::read_to_string(out.path().join("qr-20221121-233824-001.svg")).unwrap();
+    assert!(svg.starts_with("<?xml"));
+    quill(&format!(
+        "scanner-qr-code --qr-format png --qr-ecc high --qr-scale 2 --qr-out {}",
+        escape_p(&out.path())
+    ))
+    .assert_success();
+    let png = std::fs::read(out.path().join("qr-20221121-233824-001.png")).unwrap();
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    // codes from an earlier run are not overwritten, nor mixed up with these
+    let before = std::fs::read_dir(out.path()).unwrap().count();
+    quill(&format!(
+        "qr-code --string hello --qr-format svg --qr-out {}",
+        escape_p(&out.path())
+    ))
+    .assert_err();
+    assert_eq!(std::fs::read_dir(out.path()).unwrap().count(), before);
+}
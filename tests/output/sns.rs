@@ -1,6 +1,6 @@
 use crate::{
     asset, ledger_compatible, quill, quill_authed, quill_query, quill_sns_query,
-    quill_sns_query_authed, quill_sns_send, OutputExt, PRINCIPAL,
+    quill_sns_query_authed, quill_sns_send, OutputExt, ALICE, PRINCIPAL,
 };
 
 const NEURON_ID: &str = "83a7d2b12f654ff58335e5a2512ccae0d7839c744b1807a47c96f5b9f3969069";
@@ -26,6 +26,16 @@ fn balance() {
     quill_sns_query(&format!("sns balance --of {PRINCIPAL}")).diff("sns/balance/simple.txt");
 }
 
+#[test]
+fn approve() {
+    quill_sns_send(&format!("sns approve {ALICE} --amount 0.5 --fee 0.0023"))
+        .diff("sns/approve/simple.txt");
+    quill_sns_send(&format!(
+        "sns transfer-from {ALICE} --from-subaccount 02 --amount 0.5"
+    ))
+    .diff("sns/approve/transfer_from.txt");
+}
+
 #[test]
 fn dissolve_delay() {
     quill_sns_send(&format!(